@@ -0,0 +1,554 @@
+use soroban_sdk::{contract, contractimpl, contractmeta, contracttype, token, Address, Env, IntoVal, Symbol, Val, U256};
+
+use crate::error::ConcentratedPoolError;
+use crate::math::{add_delta, compute_swap_step, get_amount0_delta_signed, get_amount1_delta_signed, to_i128, FEE_DENOMINATOR};
+use raumfi_library::{
+    from_host_u256, get_sqrt_price_at_tick, get_tick_at_sqrt_price, mul_div, to_host_u256,
+    Rounding, MAX_SQRT_PRICE, MAX_TICK, MIN_SQRT_PRICE, MIN_TICK, Q128,
+};
+use crate::tick::{self, TickInfo};
+
+/// Largest tick spacing a pool may use
+pub const MAX_TICK_SPACING: i32 = 16_384;
+
+const DAY_IN_LEDGERS: u32 = 17280;
+const PERSISTENT_BUMP_AMOUNT: u32 = 30 * DAY_IN_LEDGERS;
+const PERSISTENT_LIFETIME_THRESHOLD: u32 = PERSISTENT_BUMP_AMOUNT - DAY_IN_LEDGERS;
+
+contractmeta!(
+    key = "Description",
+    val = "RaumFi concentrated liquidity pool"
+);
+
+#[contracttype]
+#[derive(Clone)]
+pub enum DataKey {
+    Factory,
+    Token0,
+    Token1,
+    Fee,
+    TickSpacing,
+    MaxLiquidityPerTick,
+    /// Current Q64.96 sqrt price of token0 in token1, and its tick
+    SqrtPrice,
+    CurrentTick,
+    /// Liquidity of the positions whose range contains the current price
+    Liquidity,
+    /// Fees earned per unit of liquidity over the life of the pool, in Q128
+    FeeGrowthGlobal0,
+    FeeGrowthGlobal1,
+    PositionCount,
+    Position(u64),
+    Tick(i32),
+    /// Words of 128 bits flagging initialized ticks, indexed by `tick / tick_spacing`
+    TickBitmap(i32),
+    Unlocked,
+}
+
+/// Liquidity provided over the price range `[tick_lower, tick_upper)`, identified by the
+/// position ID returned by `mint`
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Position {
+    pub owner: Address,
+    pub tick_lower: i32,
+    pub tick_upper: i32,
+    pub liquidity: u128,
+    /// Fee growth inside the range, in Q128, as of the last update of the position
+    pub fee_growth_inside_0_last: U256,
+    pub fee_growth_inside_1_last: U256,
+    /// Earned fees and burned liquidity, waiting to be collected
+    pub tokens_owed_0: i128,
+    pub tokens_owed_1: i128,
+}
+
+pub trait RaumFiConcentratedPoolTrait {
+    fn initialize(env: Env, token0: Address, token1: Address, factory: Address, fee: u32, tick_spacing: i32, sqrt_price: U256) -> Result<(), ConcentratedPoolError>;
+    fn get_tokens(env: Env) -> (Address, Address);
+    fn get_fee(env: Env) -> u32;
+    fn get_tick_spacing(env: Env) -> i32;
+    fn get_sqrt_price(env: Env) -> U256;
+    fn get_tick(env: Env) -> i32;
+    fn get_liquidity(env: Env) -> u128;
+    fn get_fee_growth_global(env: Env) -> (U256, U256);
+    fn get_tick_info(env: Env, tick: i32) -> Option<TickInfo>;
+    fn get_position(env: Env, position_id: u64) -> Option<Position>;
+    fn mint(env: Env, owner: Address, tick_lower: i32, tick_upper: i32, liquidity: u128) -> Result<(u64, i128, i128), ConcentratedPoolError>;
+    fn increase_liquidity(env: Env, position_id: u64, liquidity: u128) -> Result<(i128, i128), ConcentratedPoolError>;
+    fn burn(env: Env, position_id: u64, liquidity: u128) -> Result<(i128, i128), ConcentratedPoolError>;
+    fn collect(env: Env, position_id: u64, to: Address, amount0_requested: i128, amount1_requested: i128) -> Result<(i128, i128), ConcentratedPoolError>;
+    fn transfer_position(env: Env, position_id: u64, to: Address) -> Result<(), ConcentratedPoolError>;
+    fn swap(env: Env, sender: Address, to: Address, zero_for_one: bool, amount_specified: i128, sqrt_price_limit: U256) -> Result<(i128, i128), ConcentratedPoolError>;
+}
+
+#[contract]
+pub struct RaumFiConcentratedPool;
+
+#[contractimpl]
+impl RaumFiConcentratedPoolTrait for RaumFiConcentratedPool {
+    /// Sets up a pool of `token0` and `token1`, sorted by address, starting at the Q64.96
+    /// sqrt price `sqrt_price`. Positions must start and end on multiples of `tick_spacing`.
+    fn initialize(env: Env, token0: Address, token1: Address, factory: Address, fee: u32, tick_spacing: i32, sqrt_price: U256) -> Result<(), ConcentratedPoolError> {
+        if env.storage().instance().has(&DataKey::Factory) {
+            return Err(ConcentratedPoolError::AlreadyInitialized);
+        }
+        if token0 >= token1 {
+            return Err(ConcentratedPoolError::InvalidToken);
+        }
+        if fee >= FEE_DENOMINATOR {
+            return Err(ConcentratedPoolError::InvalidFee);
+        }
+        if tick_spacing <= 0 || tick_spacing > MAX_TICK_SPACING {
+            return Err(ConcentratedPoolError::InvalidTickSpacing);
+        }
+        let tick = get_tick_at_sqrt_price(from_host_u256(&sqrt_price))?;
+
+        let storage = env.storage().instance();
+        storage.set(&DataKey::Factory, &factory);
+        storage.set(&DataKey::Token0, &token0);
+        storage.set(&DataKey::Token1, &token1);
+        storage.set(&DataKey::Fee, &fee);
+        storage.set(&DataKey::TickSpacing, &tick_spacing);
+        storage.set(&DataKey::MaxLiquidityPerTick, &tick::max_liquidity_per_tick(tick_spacing));
+        storage.set(&DataKey::SqrtPrice, &sqrt_price);
+        storage.set(&DataKey::CurrentTick, &tick);
+        storage.set(&DataKey::Liquidity, &0u128);
+        storage.set(&DataKey::FeeGrowthGlobal0, &U256::from_u32(&env, 0));
+        storage.set(&DataKey::FeeGrowthGlobal1, &U256::from_u32(&env, 0));
+        storage.set(&DataKey::PositionCount, &0u64);
+        Ok(())
+    }
+
+    fn get_tokens(env: Env) -> (Address, Address) {
+        let storage = env.storage().instance();
+        (storage.get(&DataKey::Token0).unwrap(), storage.get(&DataKey::Token1).unwrap())
+    }
+
+    /// Returns the swap fee of the pool in basis points
+    fn get_fee(env: Env) -> u32 {
+        env.storage().instance().get(&DataKey::Fee).unwrap_or(0)
+    }
+
+    fn get_tick_spacing(env: Env) -> i32 {
+        env.storage().instance().get(&DataKey::TickSpacing).unwrap_or(0)
+    }
+
+    /// Returns the Q64.96 square root of the price of token0 in token1
+    fn get_sqrt_price(env: Env) -> U256 {
+        env.storage().instance().get(&DataKey::SqrtPrice).unwrap_or(U256::from_u32(&env, 0))
+    }
+
+    /// Returns the greatest tick whose price is at most the current price
+    fn get_tick(env: Env) -> i32 {
+        env.storage().instance().get(&DataKey::CurrentTick).unwrap_or(0)
+    }
+
+    /// Returns the liquidity of the positions whose range contains the current price
+    fn get_liquidity(env: Env) -> u128 {
+        env.storage().instance().get(&DataKey::Liquidity).unwrap_or(0)
+    }
+
+    /// Returns the fees of each token earned per unit of liquidity since the pool was
+    /// created, in Q128
+    fn get_fee_growth_global(env: Env) -> (U256, U256) {
+        let storage = env.storage().instance();
+        (
+            storage.get(&DataKey::FeeGrowthGlobal0).unwrap_or(U256::from_u32(&env, 0)),
+            storage.get(&DataKey::FeeGrowthGlobal1).unwrap_or(U256::from_u32(&env, 0)),
+        )
+    }
+
+    fn get_tick_info(env: Env, tick: i32) -> Option<TickInfo> {
+        tick::get(&env, tick)
+    }
+
+    fn get_position(env: Env, position_id: u64) -> Option<Position> {
+        read_position(&env, position_id)
+    }
+
+    /// Opens a position of `liquidity` over `[tick_lower, tick_upper)` for `owner`, who
+    /// pays the token amounts it needs at the current price. Returns the new position ID
+    /// and the amounts paid.
+    fn mint(env: Env, owner: Address, tick_lower: i32, tick_upper: i32, liquidity: u128) -> Result<(u64, i128, i128), ConcentratedPoolError> {
+        check_locked(&env)?;
+        let _guard = Guard::new(&env);
+
+        owner.require_auth();
+        check_ticks(&env, tick_lower, tick_upper)?;
+        let liquidity_delta = positive_liquidity(liquidity)?;
+
+        let position_id = env.storage().instance().get::<_, u64>(&DataKey::PositionCount).ok_or(ConcentratedPoolError::NotInitialized)? + 1;
+        let zero = U256::from_u32(&env, 0);
+        let mut position = Position {
+            owner: owner.clone(),
+            tick_lower,
+            tick_upper,
+            liquidity: 0,
+            fee_growth_inside_0_last: zero.clone(),
+            fee_growth_inside_1_last: zero,
+            tokens_owed_0: 0,
+            tokens_owed_1: 0,
+        };
+        let (amount0, amount1) = modify_position(&env, &mut position, liquidity_delta)?;
+        pay_in(&env, &owner, amount0, amount1);
+
+        write_position(&env, position_id, &position);
+        env.storage().instance().set(&DataKey::PositionCount, &position_id);
+        env.events().publish(
+            (Symbol::new(&env, "RaumFiConcentratedPool"), Symbol::new(&env, "Mint")),
+            (owner, position_id, tick_lower, tick_upper, liquidity, amount0, amount1)
+        );
+        Ok((position_id, amount0, amount1))
+    }
+
+    /// Adds `liquidity` to a position, paid for by its owner at the current price
+    fn increase_liquidity(env: Env, position_id: u64, liquidity: u128) -> Result<(i128, i128), ConcentratedPoolError> {
+        check_locked(&env)?;
+        let _guard = Guard::new(&env);
+
+        let mut position = read_position(&env, position_id).ok_or(ConcentratedPoolError::PositionNotFound)?;
+        position.owner.require_auth();
+        let liquidity_delta = positive_liquidity(liquidity)?;
+
+        let (amount0, amount1) = modify_position(&env, &mut position, liquidity_delta)?;
+        pay_in(&env, &position.owner, amount0, amount1);
+
+        write_position(&env, position_id, &position);
+        env.events().publish(
+            (Symbol::new(&env, "RaumFiConcentratedPool"), Symbol::new(&env, "Mint")),
+            (position.owner, position_id, position.tick_lower, position.tick_upper, liquidity, amount0, amount1)
+        );
+        Ok((amount0, amount1))
+    }
+
+    /// Removes `liquidity` from a position and credits the tokens it held to the position,
+    /// to be withdrawn with `collect`. Burning zero liquidity only settles earned fees.
+    fn burn(env: Env, position_id: u64, liquidity: u128) -> Result<(i128, i128), ConcentratedPoolError> {
+        check_locked(&env)?;
+        let _guard = Guard::new(&env);
+
+        let mut position = read_position(&env, position_id).ok_or(ConcentratedPoolError::PositionNotFound)?;
+        position.owner.require_auth();
+        if liquidity > position.liquidity {
+            return Err(ConcentratedPoolError::InsufficientLiquidity);
+        }
+        let liquidity_delta = i128::try_from(liquidity).map_err(|_| ConcentratedPoolError::Overflow)?;
+
+        let (amount0, amount1) = modify_position(&env, &mut position, -liquidity_delta)?;
+        let (amount0, amount1) = (-amount0, -amount1);
+        position.tokens_owed_0 = position.tokens_owed_0.checked_add(amount0).ok_or(ConcentratedPoolError::Overflow)?;
+        position.tokens_owed_1 = position.tokens_owed_1.checked_add(amount1).ok_or(ConcentratedPoolError::Overflow)?;
+
+        write_position(&env, position_id, &position);
+        env.events().publish(
+            (Symbol::new(&env, "RaumFiConcentratedPool"), Symbol::new(&env, "Burn")),
+            (position.owner, position_id, liquidity, amount0, amount1)
+        );
+        Ok((amount0, amount1))
+    }
+
+    /// Sends up to the requested amounts of the fees and burned liquidity owed to a
+    /// position to `to`
+    fn collect(env: Env, position_id: u64, to: Address, amount0_requested: i128, amount1_requested: i128) -> Result<(i128, i128), ConcentratedPoolError> {
+        check_locked(&env)?;
+        let _guard = Guard::new(&env);
+
+        let mut position = read_position(&env, position_id).ok_or(ConcentratedPoolError::PositionNotFound)?;
+        position.owner.require_auth();
+        if amount0_requested < 0 || amount1_requested < 0 {
+            return Err(ConcentratedPoolError::InvalidAmount);
+        }
+
+        let amount0 = amount0_requested.min(position.tokens_owed_0);
+        let amount1 = amount1_requested.min(position.tokens_owed_1);
+        position.tokens_owed_0 -= amount0;
+        position.tokens_owed_1 -= amount1;
+        write_position(&env, position_id, &position);
+
+        let (token0, token1) = Self::get_tokens(env.clone());
+        if amount0 > 0 {
+            token::Client::new(&env, &token0).transfer(&env.current_contract_address(), &to, &amount0);
+        }
+        if amount1 > 0 {
+            token::Client::new(&env, &token1).transfer(&env.current_contract_address(), &to, &amount1);
+        }
+        env.events().publish(
+            (Symbol::new(&env, "RaumFiConcentratedPool"), Symbol::new(&env, "Collect")),
+            (position_id, to, amount0, amount1)
+        );
+        Ok((amount0, amount1))
+    }
+
+    /// Hands a position, with its liquidity and uncollected fees, over to `to`
+    fn transfer_position(env: Env, position_id: u64, to: Address) -> Result<(), ConcentratedPoolError> {
+        let mut position = read_position(&env, position_id).ok_or(ConcentratedPoolError::PositionNotFound)?;
+        position.owner.require_auth();
+
+        let from = position.owner;
+        position.owner = to.clone();
+        write_position(&env, position_id, &position);
+        env.events().publish(
+            (Symbol::new(&env, "RaumFiConcentratedPool"), Symbol::new(&env, "Transfer")),
+            (position_id, from, to)
+        );
+        Ok(())
+    }
+
+    /// Swaps token0 for token1 (`zero_for_one`) or back, crossing ticks until
+    /// `amount_specified` is filled or the price reaches `sqrt_price_limit`. A positive
+    /// amount is an exact input and a negative amount an exact output. `sender` pays the
+    /// input and `to` receives the output; the returned amounts are the changes of the
+    /// pool's balances.
+    fn swap(env: Env, sender: Address, to: Address, zero_for_one: bool, amount_specified: i128, sqrt_price_limit: U256) -> Result<(i128, i128), ConcentratedPoolError> {
+        check_locked(&env)?;
+        let _guard = Guard::new(&env);
+
+        sender.require_auth();
+        if amount_specified == 0 {
+            return Err(ConcentratedPoolError::InvalidAmount);
+        }
+        let storage = env.storage().instance();
+        let (token0, token1) = Self::get_tokens(env.clone());
+        if to == token0 || to == token1 {
+            return Err(ConcentratedPoolError::InvalidTo);
+        }
+
+        let sqrt_price_limit = from_host_u256(&sqrt_price_limit);
+        let mut sqrt_price = from_host_u256(&Self::get_sqrt_price(env.clone()));
+        let valid_limit = if zero_for_one {
+            sqrt_price_limit < sqrt_price && sqrt_price_limit > MIN_SQRT_PRICE
+        } else {
+            sqrt_price_limit > sqrt_price && sqrt_price_limit < MAX_SQRT_PRICE
+        };
+        if !valid_limit {
+            return Err(ConcentratedPoolError::InvalidPriceLimit);
+        }
+
+        let fee = Self::get_fee(env.clone());
+        let tick_spacing = Self::get_tick_spacing(env.clone());
+        let mut tick = Self::get_tick(env.clone());
+        let mut liquidity = Self::get_liquidity(env.clone());
+        let (fee_growth_global_0, fee_growth_global_1) = Self::get_fee_growth_global(env.clone());
+        let (fee_growth_global_0, fee_growth_global_1) = (from_host_u256(&fee_growth_global_0), from_host_u256(&fee_growth_global_1));
+        let mut fee_growth_global_in = if zero_for_one { fee_growth_global_0 } else { fee_growth_global_1 };
+
+        let exact_input = amount_specified > 0;
+        let mut amount_remaining = amount_specified;
+        let mut amount_calculated: i128 = 0;
+        while amount_remaining != 0 && sqrt_price != sqrt_price_limit {
+            let sqrt_price_start = sqrt_price;
+            let (tick_next, initialized) = tick::next_initialized_tick_within_one_word(&env, tick, tick_spacing, zero_for_one);
+            let tick_next = tick_next.clamp(MIN_TICK, MAX_TICK);
+            let sqrt_price_next = get_sqrt_price_at_tick(tick_next)?;
+            let sqrt_price_target = if zero_for_one {
+                sqrt_price_next.max(sqrt_price_limit)
+            } else {
+                sqrt_price_next.min(sqrt_price_limit)
+            };
+
+            let step = compute_swap_step(sqrt_price, sqrt_price_target, liquidity, amount_remaining, fee)?;
+            sqrt_price = step.sqrt_price_next;
+            let amount_in = to_i128(step.amount_in + step.fee_amount)?;
+            let amount_out = to_i128(step.amount_out)?;
+            if exact_input {
+                amount_remaining -= amount_in;
+                amount_calculated -= amount_out;
+            } else {
+                amount_remaining += amount_out;
+                amount_calculated += amount_in;
+            }
+            if liquidity > 0 {
+                let fee_growth = mul_div(step.fee_amount, Q128, ethnum::U256::new(liquidity), Rounding::Down)?;
+                fee_growth_global_in = fee_growth_global_in.wrapping_add(fee_growth);
+            }
+
+            if sqrt_price == sqrt_price_next {
+                // Reached the next tick: bring in or drop the positions it bounds
+                if initialized {
+                    let (fee_growth_0, fee_growth_1) = if zero_for_one {
+                        (fee_growth_global_in, fee_growth_global_1)
+                    } else {
+                        (fee_growth_global_0, fee_growth_global_in)
+                    };
+                    let liquidity_net = tick::cross(&env, tick_next, fee_growth_0, fee_growth_1);
+                    liquidity = add_delta(liquidity, if zero_for_one { -liquidity_net } else { liquidity_net })?;
+                }
+                tick = if zero_for_one { tick_next - 1 } else { tick_next };
+            } else if sqrt_price != sqrt_price_start {
+                tick = get_tick_at_sqrt_price(sqrt_price)?;
+            }
+        }
+
+        storage.set(&DataKey::SqrtPrice, &to_host_u256(&env, sqrt_price));
+        storage.set(&DataKey::CurrentTick, &tick);
+        storage.set(&DataKey::Liquidity, &liquidity);
+        if zero_for_one {
+            storage.set(&DataKey::FeeGrowthGlobal0, &to_host_u256(&env, fee_growth_global_in));
+        } else {
+            storage.set(&DataKey::FeeGrowthGlobal1, &to_host_u256(&env, fee_growth_global_in));
+        }
+
+        let (amount0, amount1) = if zero_for_one == exact_input {
+            (amount_specified - amount_remaining, amount_calculated)
+        } else {
+            (amount_calculated, amount_specified - amount_remaining)
+        };
+        pay_in(&env, &sender, amount0, amount1);
+        if amount0 < 0 {
+            token::Client::new(&env, &token0).transfer(&env.current_contract_address(), &to, &-amount0);
+        }
+        if amount1 < 0 {
+            token::Client::new(&env, &token1).transfer(&env.current_contract_address(), &to, &-amount1);
+        }
+
+        env.events().publish(
+            (Symbol::new(&env, "RaumFiConcentratedPool"), Symbol::new(&env, "Swap")),
+            (sender, to, amount0, amount1, to_host_u256(&env, sqrt_price), liquidity, tick)
+        );
+        Ok((amount0, amount1))
+    }
+}
+
+pub(crate) fn bump_persistent<K: IntoVal<Env, Val>>(env: &Env, key: &K) {
+    env.storage()
+        .persistent()
+        .extend_ttl(key, PERSISTENT_LIFETIME_THRESHOLD, PERSISTENT_BUMP_AMOUNT);
+}
+
+fn read_position(env: &Env, position_id: u64) -> Option<Position> {
+    let key = DataKey::Position(position_id);
+    let position = env.storage().persistent().get(&key);
+    if position.is_some() {
+        bump_persistent(env, &key);
+    }
+    position
+}
+
+fn write_position(env: &Env, position_id: u64, position: &Position) {
+    let key = DataKey::Position(position_id);
+    env.storage().persistent().set(&key, position);
+    bump_persistent(env, &key);
+}
+
+fn check_ticks(env: &Env, tick_lower: i32, tick_upper: i32) -> Result<(), ConcentratedPoolError> {
+    let tick_spacing = RaumFiConcentratedPool::get_tick_spacing(env.clone());
+    if tick_spacing == 0 {
+        return Err(ConcentratedPoolError::NotInitialized);
+    }
+    if tick_lower >= tick_upper
+        || tick_lower < MIN_TICK
+        || tick_upper > MAX_TICK
+        || tick_lower % tick_spacing != 0
+        || tick_upper % tick_spacing != 0
+    {
+        return Err(ConcentratedPoolError::InvalidTicks);
+    }
+    Ok(())
+}
+
+fn positive_liquidity(liquidity: u128) -> Result<i128, ConcentratedPoolError> {
+    if liquidity == 0 {
+        return Err(ConcentratedPoolError::InvalidAmount);
+    }
+    i128::try_from(liquidity).map_err(|_| ConcentratedPoolError::Overflow)
+}
+
+/// Applies `liquidity_delta` to `position` and its ticks, settling the fees it earned so
+/// far. Returns the token amounts the pool is owed (positive) or owes (negative) for the
+/// change at the current price.
+fn modify_position(env: &Env, position: &mut Position, liquidity_delta: i128) -> Result<(i128, i128), ConcentratedPoolError> {
+    let storage = env.storage().instance();
+    let tick_current = RaumFiConcentratedPool::get_tick(env.clone());
+    let (fee_growth_global_0, fee_growth_global_1) = RaumFiConcentratedPool::get_fee_growth_global(env.clone());
+    let (fee_growth_global_0, fee_growth_global_1) = (from_host_u256(&fee_growth_global_0), from_host_u256(&fee_growth_global_1));
+    let (tick_lower, tick_upper) = (position.tick_lower, position.tick_upper);
+
+    let mut flipped_lower = false;
+    let mut flipped_upper = false;
+    if liquidity_delta != 0 {
+        let max_liquidity: u128 = storage.get(&DataKey::MaxLiquidityPerTick).ok_or(ConcentratedPoolError::NotInitialized)?;
+        let tick_spacing = RaumFiConcentratedPool::get_tick_spacing(env.clone());
+        flipped_lower = tick::update(env, tick_lower, tick_current, liquidity_delta, (fee_growth_global_0, fee_growth_global_1), false, max_liquidity)?;
+        flipped_upper = tick::update(env, tick_upper, tick_current, liquidity_delta, (fee_growth_global_0, fee_growth_global_1), true, max_liquidity)?;
+        if flipped_lower {
+            tick::flip(env, tick_lower, tick_spacing);
+        }
+        if flipped_upper {
+            tick::flip(env, tick_upper, tick_spacing);
+        }
+    }
+
+    // Fees earned since the last update accrue at the liquidity held until now
+    let (fee_growth_inside_0, fee_growth_inside_1) =
+        tick::get_fee_growth_inside(env, tick_lower, tick_upper, tick_current, fee_growth_global_0, fee_growth_global_1);
+    let liquidity = ethnum::U256::new(position.liquidity);
+    let fees_0 = mul_div(fee_growth_inside_0.wrapping_sub(from_host_u256(&position.fee_growth_inside_0_last)), liquidity, Q128, Rounding::Down)?;
+    let fees_1 = mul_div(fee_growth_inside_1.wrapping_sub(from_host_u256(&position.fee_growth_inside_1_last)), liquidity, Q128, Rounding::Down)?;
+    position.tokens_owed_0 = position.tokens_owed_0.checked_add(to_i128(fees_0)?).ok_or(ConcentratedPoolError::Overflow)?;
+    position.tokens_owed_1 = position.tokens_owed_1.checked_add(to_i128(fees_1)?).ok_or(ConcentratedPoolError::Overflow)?;
+    position.fee_growth_inside_0_last = to_host_u256(env, fee_growth_inside_0);
+    position.fee_growth_inside_1_last = to_host_u256(env, fee_growth_inside_1);
+    position.liquidity = add_delta(position.liquidity, liquidity_delta)?;
+
+    // Ticks no longer bounding any position are dropped once the fees are settled
+    if flipped_lower && liquidity_delta < 0 {
+        tick::clear(env, tick_lower);
+    }
+    if flipped_upper && liquidity_delta < 0 {
+        tick::clear(env, tick_upper);
+    }
+
+    let sqrt_price_lower = get_sqrt_price_at_tick(tick_lower)?;
+    let sqrt_price_upper = get_sqrt_price_at_tick(tick_upper)?;
+    if tick_current < tick_lower {
+        // The range is above the price and holds token0 only
+        Ok((get_amount0_delta_signed(sqrt_price_lower, sqrt_price_upper, liquidity_delta)?, 0))
+    } else if tick_current < tick_upper {
+        let sqrt_price = from_host_u256(&RaumFiConcentratedPool::get_sqrt_price(env.clone()));
+        let pool_liquidity = add_delta(RaumFiConcentratedPool::get_liquidity(env.clone()), liquidity_delta)?;
+        storage.set(&DataKey::Liquidity, &pool_liquidity);
+        Ok((
+            get_amount0_delta_signed(sqrt_price, sqrt_price_upper, liquidity_delta)?,
+            get_amount1_delta_signed(sqrt_price_lower, sqrt_price, liquidity_delta)?,
+        ))
+    } else {
+        // The range is below the price and holds token1 only
+        Ok((0, get_amount1_delta_signed(sqrt_price_lower, sqrt_price_upper, liquidity_delta)?))
+    }
+}
+
+/// Takes the amounts the pool is owed from `from`
+fn pay_in(env: &Env, from: &Address, amount0: i128, amount1: i128) {
+    let (token0, token1) = RaumFiConcentratedPool::get_tokens(env.clone());
+    if amount0 > 0 {
+        token::Client::new(env, &token0).transfer(from, &env.current_contract_address(), &amount0);
+    }
+    if amount1 > 0 {
+        token::Client::new(env, &token1).transfer(from, &env.current_contract_address(), &amount1);
+    }
+}
+
+fn check_locked(env: &Env) -> Result<(), ConcentratedPoolError> {
+    let unlocked: bool = env.storage().instance().get(&DataKey::Unlocked).unwrap_or(true);
+    if !unlocked {
+        return Err(ConcentratedPoolError::Locked);
+    }
+    Ok(())
+}
+
+struct Guard<'a> {
+    env: &'a Env,
+}
+
+impl<'a> Guard<'a> {
+    fn new(env: &'a Env) -> Self {
+        env.storage().instance().set(&DataKey::Unlocked, &false);
+        Guard { env }
+    }
+}
+
+impl<'a> Drop for Guard<'a> {
+    fn drop(&mut self) {
+        self.env.storage().instance().set(&DataKey::Unlocked, &true);
+    }
+}
@@ -0,0 +1,40 @@
+use raumfi_library::RaumFiLibraryError;
+use soroban_sdk::contracterror;
+
+#[contracterror]
+#[derive(Copy, Clone, Debug, Eq, PartialEq, PartialOrd, Ord)]
+#[repr(u32)]
+pub enum ConcentratedPoolError {
+    Locked = 1,
+    AlreadyInitialized = 2,
+    /// Only the owner of a position may change it
+    Forbidden = 3,
+    InsufficientLiquidity = 7,
+    InvalidTo = 8,
+    Overflow = 11,
+    InvalidAmount = 14,
+    InvalidFee = 16,
+    NotInitialized = 17,
+    InvalidToken = 21,
+    /// Ticks must be ordered, within `MIN_TICK..=MAX_TICK` and multiples of the tick spacing
+    InvalidTicks = 26,
+    InvalidTickSpacing = 27,
+    /// The sqrt price is outside `MIN_SQRT_PRICE..MAX_SQRT_PRICE`
+    InvalidSqrtPrice = 28,
+    /// The price limit of a swap is on the wrong side of the current price
+    InvalidPriceLimit = 29,
+    /// A tick would reference more liquidity than the pool allows per tick
+    TickLiquidityOverflow = 30,
+    PositionNotFound = 31,
+}
+
+impl From<RaumFiLibraryError> for ConcentratedPoolError {
+    fn from(error: RaumFiLibraryError) -> Self {
+        match error {
+            RaumFiLibraryError::InvalidTick => ConcentratedPoolError::InvalidTicks,
+            RaumFiLibraryError::InvalidSqrtPrice => ConcentratedPoolError::InvalidSqrtPrice,
+            RaumFiLibraryError::InsufficientLiquidity => ConcentratedPoolError::InsufficientLiquidity,
+            _ => ConcentratedPoolError::Overflow,
+        }
+    }
+}
@@ -0,0 +1,12 @@
+#![no_std]
+
+mod error;
+mod math;
+mod tick;
+pub mod concentrated_pool;
+
+pub use error::ConcentratedPoolError;
+pub use concentrated_pool::RaumFiConcentratedPoolClient;
+
+#[cfg(test)]
+mod test;
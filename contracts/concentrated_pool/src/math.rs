@@ -0,0 +1,128 @@
+//! Swap and liquidity math of the concentrated liquidity pool, on top of the sqrt-price
+//! and tick math of `raumfi_library`.
+
+use ethnum::U256;
+use raumfi_library::{
+    calculate_next_sqrt_price_from_input, calculate_next_sqrt_price_from_output, get_amount0_delta,
+    get_amount1_delta, mul_div, Rounding,
+};
+
+use crate::error::ConcentratedPoolError;
+
+/// Swap fees are expressed in basis points of the input amount
+pub const FEE_DENOMINATOR: u32 = 10_000;
+
+pub fn to_i128(amount: U256) -> Result<i128, ConcentratedPoolError> {
+    i128::try_from(amount).map_err(|_| ConcentratedPoolError::Overflow)
+}
+
+/// Token0 owed to the pool for adding `liquidity_delta`, or owed by the pool for removing
+/// it. Rounds in favor of the pool either way.
+pub fn get_amount0_delta_signed(sqrt_price_a: U256, sqrt_price_b: U256, liquidity_delta: i128) -> Result<i128, ConcentratedPoolError> {
+    if liquidity_delta < 0 {
+        Ok(-to_i128(get_amount0_delta(sqrt_price_a, sqrt_price_b, liquidity_delta.unsigned_abs(), Rounding::Down)?)?)
+    } else {
+        to_i128(get_amount0_delta(sqrt_price_a, sqrt_price_b, liquidity_delta as u128, Rounding::Up)?)
+    }
+}
+
+/// Token1 owed to the pool for adding `liquidity_delta`, or owed by the pool for removing
+/// it. Rounds in favor of the pool either way.
+pub fn get_amount1_delta_signed(sqrt_price_a: U256, sqrt_price_b: U256, liquidity_delta: i128) -> Result<i128, ConcentratedPoolError> {
+    if liquidity_delta < 0 {
+        Ok(-to_i128(get_amount1_delta(sqrt_price_a, sqrt_price_b, liquidity_delta.unsigned_abs(), Rounding::Down)?)?)
+    } else {
+        to_i128(get_amount1_delta(sqrt_price_a, sqrt_price_b, liquidity_delta as u128, Rounding::Up)?)
+    }
+}
+
+/// Applies a signed liquidity change
+pub fn add_delta(liquidity: u128, delta: i128) -> Result<u128, ConcentratedPoolError> {
+    if delta < 0 {
+        liquidity.checked_sub(delta.unsigned_abs()).ok_or(ConcentratedPoolError::InsufficientLiquidity)
+    } else {
+        liquidity.checked_add(delta as u128).ok_or(ConcentratedPoolError::Overflow)
+    }
+}
+
+/// Result of swapping within a single tick range
+pub struct SwapStep {
+    pub sqrt_price_next: U256,
+    pub amount_in: U256,
+    pub amount_out: U256,
+    pub fee_amount: U256,
+}
+
+/// Swaps `amount_remaining` from `sqrt_price_current` towards `sqrt_price_target` within
+/// `liquidity`. A positive amount is an exact input, including the fee; a negative amount
+/// an exact output.
+pub fn compute_swap_step(
+    sqrt_price_current: U256,
+    sqrt_price_target: U256,
+    liquidity: u128,
+    amount_remaining: i128,
+    fee: u32,
+) -> Result<SwapStep, ConcentratedPoolError> {
+    let zero_for_one = sqrt_price_current >= sqrt_price_target;
+    let exact_in = amount_remaining >= 0;
+    let amount_remaining_abs = U256::new(amount_remaining.unsigned_abs());
+    let fee_complement = U256::from(FEE_DENOMINATOR - fee);
+
+    let sqrt_price_next;
+    let mut amount_in = U256::ZERO;
+    let mut amount_out = U256::ZERO;
+    if exact_in {
+        let amount_remaining_less_fee = mul_div(amount_remaining_abs, fee_complement, U256::from(FEE_DENOMINATOR), Rounding::Down)?;
+        amount_in = if zero_for_one {
+            get_amount0_delta(sqrt_price_target, sqrt_price_current, liquidity, Rounding::Up)?
+        } else {
+            get_amount1_delta(sqrt_price_current, sqrt_price_target, liquidity, Rounding::Up)?
+        };
+        sqrt_price_next = if amount_remaining_less_fee >= amount_in {
+            sqrt_price_target
+        } else {
+            calculate_next_sqrt_price_from_input(sqrt_price_current, liquidity, amount_remaining_less_fee, zero_for_one)?
+        };
+    } else {
+        amount_out = if zero_for_one {
+            get_amount1_delta(sqrt_price_target, sqrt_price_current, liquidity, Rounding::Down)?
+        } else {
+            get_amount0_delta(sqrt_price_current, sqrt_price_target, liquidity, Rounding::Down)?
+        };
+        sqrt_price_next = if amount_remaining_abs >= amount_out {
+            sqrt_price_target
+        } else {
+            calculate_next_sqrt_price_from_output(sqrt_price_current, liquidity, amount_remaining_abs, zero_for_one)?
+        };
+    }
+
+    // Amounts already computed for the whole step are reused when the target is reached
+    let reached_target = sqrt_price_next == sqrt_price_target;
+    if zero_for_one {
+        if !reached_target || !exact_in {
+            amount_in = get_amount0_delta(sqrt_price_next, sqrt_price_current, liquidity, Rounding::Up)?;
+        }
+        if !reached_target || exact_in {
+            amount_out = get_amount1_delta(sqrt_price_next, sqrt_price_current, liquidity, Rounding::Down)?;
+        }
+    } else {
+        if !reached_target || !exact_in {
+            amount_in = get_amount1_delta(sqrt_price_current, sqrt_price_next, liquidity, Rounding::Up)?;
+        }
+        if !reached_target || exact_in {
+            amount_out = get_amount0_delta(sqrt_price_current, sqrt_price_next, liquidity, Rounding::Down)?;
+        }
+    }
+    if !exact_in && amount_out > amount_remaining_abs {
+        amount_out = amount_remaining_abs;
+    }
+
+    // An exact input that stops short of the target pays the rest of its amount as fee
+    let fee_amount = if exact_in && !reached_target {
+        amount_remaining_abs - amount_in
+    } else {
+        mul_div(amount_in, U256::from(fee), fee_complement, Rounding::Up)?
+    };
+
+    Ok(SwapStep { sqrt_price_next, amount_in, amount_out, fee_amount })
+}
@@ -0,0 +1,291 @@
+#![cfg(test)]
+extern crate std;
+
+use soroban_sdk::{testutils::Address as _, token, Address, Env, U256};
+
+use crate::concentrated_pool::{RaumFiConcentratedPool, RaumFiConcentratedPoolClient};
+use token::Client as TokenClient;
+use token::StellarAssetClient as TokenAdminClient;
+
+/// Q64.96 sqrt price of 1
+const SQRT_PRICE_1: u128 = 1 << 96;
+/// Sqrt price just above the lower bound, as a swap limit that never binds
+const MIN_SQRT_PRICE_LIMIT: u128 = 4_295_128_740;
+
+fn create_token_contract<'a>(e: &Env, admin: &Address) -> (TokenClient<'a>, TokenAdminClient<'a>) {
+    let sac = e.register_stellar_asset_contract(admin.clone());
+    (
+        token::Client::new(e, &sac),
+        token::StellarAssetClient::new(e, &sac),
+    )
+}
+
+fn max_sqrt_price_limit(env: &Env) -> U256 {
+    // 1461446703485210103287273052203988822378723970342 - 1
+    U256::from_parts(env, 0, 0xfffd8963, 0xefd1fc6a50648849, 0x5d951d5263988d25)
+}
+
+struct ConcentratedPoolTest<'a> {
+    env: Env,
+    client: RaumFiConcentratedPoolClient<'a>,
+    token0: TokenClient<'a>,
+    token1: TokenClient<'a>,
+    user: Address,
+}
+
+fn setup_test() -> ConcentratedPoolTest<'static> {
+    let env = Env::default();
+    env.mock_all_auths();
+    env.budget().reset_unlimited();
+
+    let user = Address::generate(&env);
+    let (mut token0, token0_admin) = create_token_contract(&env, &user);
+    let (mut token1, token1_admin) = create_token_contract(&env, &user);
+    token0_admin.mint(&user, &1_000_000_000_000);
+    token1_admin.mint(&user, &1_000_000_000_000);
+    if token1.address < token0.address {
+        std::mem::swap(&mut token0, &mut token1);
+    }
+
+    let client = RaumFiConcentratedPoolClient::new(&env, &env.register_contract(None, RaumFiConcentratedPool {}));
+    client.initialize(
+        &token0.address,
+        &token1.address,
+        &Address::generate(&env),
+        &30,
+        &60,
+        &U256::from_u128(&env, SQRT_PRICE_1),
+    );
+    ConcentratedPoolTest { env, client, token0, token1, user }
+}
+
+#[test]
+fn test_initialize() {
+    let test = setup_test();
+    assert_eq!(test.client.get_tokens(), (test.token0.address.clone(), test.token1.address.clone()));
+    assert_eq!(test.client.get_fee(), 30);
+    assert_eq!(test.client.get_tick_spacing(), 60);
+    assert_eq!(test.client.get_sqrt_price(), U256::from_u128(&test.env, SQRT_PRICE_1));
+    assert_eq!(test.client.get_tick(), 0);
+    assert_eq!(test.client.get_liquidity(), 0);
+}
+
+#[test]
+#[should_panic(expected = "Error(Contract, #2)")]
+fn test_initialize_twice() {
+    let test = setup_test();
+    test.client.initialize(
+        &test.token0.address,
+        &test.token1.address,
+        &Address::generate(&test.env),
+        &30,
+        &60,
+        &U256::from_u128(&test.env, SQRT_PRICE_1),
+    );
+}
+
+#[test]
+#[should_panic(expected = "Error(Contract, #28)")]
+fn test_initialize_invalid_sqrt_price() {
+    let env = Env::default();
+    let client = RaumFiConcentratedPoolClient::new(&env, &env.register_contract(None, RaumFiConcentratedPool {}));
+    let (token0, token1) = (Address::generate(&env), Address::generate(&env));
+    let (token0, token1) = if token0 < token1 { (token0, token1) } else { (token1, token0) };
+    client.initialize(&token0, &token1, &Address::generate(&env), &30, &60, &U256::from_u32(&env, 1));
+}
+
+#[test]
+fn test_mint_in_range() {
+    let test = setup_test();
+    let (position_id, amount0, amount1) = test.client.mint(&test.user, &-600, &600, &10_000_000_000);
+    assert_eq!(position_id, 1);
+    // Symmetric around a price of 1, the range needs the same amount of both tokens
+    assert_eq!((amount0, amount1), (295_530_109, 295_530_109));
+    assert_eq!(test.token0.balance(&test.client.address), amount0);
+    assert_eq!(test.token1.balance(&test.client.address), amount1);
+    assert_eq!(test.client.get_liquidity(), 10_000_000_000);
+
+    let position = test.client.get_position(&position_id).unwrap();
+    assert_eq!(position.owner, test.user);
+    assert_eq!((position.tick_lower, position.tick_upper), (-600, 600));
+    assert_eq!(position.liquidity, 10_000_000_000);
+    assert_eq!(test.client.get_tick_info(&-600).unwrap().liquidity_net, 10_000_000_000);
+    assert_eq!(test.client.get_tick_info(&600).unwrap().liquidity_net, -10_000_000_000);
+}
+
+#[test]
+fn test_mint_out_of_range() {
+    let test = setup_test();
+    // Above the price the range holds token0 only, below it token1 only
+    let (_, amount0, amount1) = test.client.mint(&test.user, &600, &1200, &10_000_000_000);
+    assert_eq!((amount0, amount1), (286_796_305, 0));
+    let (_, amount0, amount1) = test.client.mint(&test.user, &-1200, &-600, &10_000_000_000);
+    assert_eq!((amount0, amount1), (0, 286_796_305));
+    assert_eq!(test.client.get_liquidity(), 0);
+}
+
+#[test]
+#[should_panic(expected = "Error(Contract, #26)")]
+fn test_mint_tick_not_on_spacing() {
+    let test = setup_test();
+    test.client.mint(&test.user, &-600, &610, &10_000_000_000);
+}
+
+#[test]
+#[should_panic(expected = "Error(Contract, #26)")]
+fn test_mint_ticks_out_of_order() {
+    let test = setup_test();
+    test.client.mint(&test.user, &600, &-600, &10_000_000_000);
+}
+
+#[test]
+fn test_swap_exact_input() {
+    let test = setup_test();
+    test.client.mint(&test.user, &-600, &600, &10_000_000_000);
+    let balance1 = test.token1.balance(&test.user);
+
+    let (amount0, amount1) = test.client.swap(
+        &test.user,
+        &test.user,
+        &true,
+        &10_000_000,
+        &U256::from_u128(&test.env, MIN_SQRT_PRICE_LIMIT),
+    );
+    assert_eq!((amount0, amount1), (10_000_000, -9_960_069));
+    assert_eq!(test.token1.balance(&test.user), balance1 + 9_960_069);
+    assert_eq!(test.client.get_sqrt_price(), U256::from_u128(&test.env, 79_149_250_711_305_166_342_700_278_159));
+    assert_eq!(test.client.get_tick(), -20);
+
+    // The whole fee accrues to token0, the input token
+    let (fee_growth_0, fee_growth_1) = test.client.get_fee_growth_global();
+    assert_eq!(fee_growth_0, U256::from_u128(&test.env, 1_020_847_100_762_815_390_390_123_822_295_304));
+    assert_eq!(fee_growth_1, U256::from_u32(&test.env, 0));
+}
+
+#[test]
+fn test_swap_exact_output() {
+    let test = setup_test();
+    test.client.mint(&test.user, &-600, &600, &10_000_000_000);
+    let balance0 = test.token0.balance(&test.user);
+
+    let (amount0, amount1) = test.client.swap(
+        &test.user,
+        &test.user,
+        &false,
+        &-10_000_000,
+        &max_sqrt_price_limit(&test.env),
+    );
+    assert_eq!((amount0, amount1), (-10_000_000, 10_040_132));
+    assert_eq!(test.token0.balance(&test.user), balance0 + 10_000_000);
+    assert_eq!(test.client.get_tick(), 20);
+}
+
+#[test]
+fn test_swap_crosses_ticks() {
+    let test = setup_test();
+    test.client.mint(&test.user, &-600, &600, &10_000_000_000);
+    test.client.mint(&test.user, &-1200, &-600, &20_000_000_000);
+    test.client.mint(&test.user, &600, &1200, &10_000_000_000);
+
+    // Crossing tick -600 downwards swaps the first range out and the second one in
+    let (amount0, amount1) = test.client.swap(
+        &test.user,
+        &test.user,
+        &true,
+        &400_000_000,
+        &U256::from_u128(&test.env, MIN_SQRT_PRICE_LIMIT),
+    );
+    assert_eq!((amount0, amount1), (400_000_000, -383_906_373));
+    assert_eq!(test.client.get_tick(), -692);
+    assert_eq!(test.client.get_liquidity(), 20_000_000_000);
+}
+
+#[test]
+fn test_swap_stops_at_price_limit() {
+    let test = setup_test();
+    test.client.mint(&test.user, &-600, &600, &10_000_000_000);
+
+    let limit = U256::from_u128(&test.env, 79_000_000_000_000_000_000_000_000_000);
+    let (amount0, _) = test.client.swap(&test.user, &test.user, &true, &1_000_000_000, &limit);
+    // Only part of the input is used when the limit is reached first
+    assert!(amount0 > 0 && amount0 < 1_000_000_000);
+    assert_eq!(test.client.get_sqrt_price(), limit);
+}
+
+#[test]
+#[should_panic(expected = "Error(Contract, #29)")]
+fn test_swap_invalid_price_limit() {
+    let test = setup_test();
+    test.client.mint(&test.user, &-600, &600, &10_000_000_000);
+    // Selling token0 lowers the price, so the limit must be below the current price
+    test.client.swap(&test.user, &test.user, &true, &1_000_000, &max_sqrt_price_limit(&test.env));
+}
+
+#[test]
+fn test_fees_accrue_inside_range() {
+    let test = setup_test();
+    let (in_range, _, _) = test.client.mint(&test.user, &-600, &600, &10_000_000_000);
+    let (out_of_range, _, _) = test.client.mint(&test.user, &600, &1200, &10_000_000_000);
+    test.client.swap(&test.user, &test.user, &true, &10_000_000, &U256::from_u128(&test.env, MIN_SQRT_PRICE_LIMIT));
+
+    // Burning no liquidity settles the fees earned so far
+    test.client.burn(&in_range, &0);
+    test.client.burn(&out_of_range, &0);
+    let position = test.client.get_position(&in_range).unwrap();
+    // 0.3% of 10_000_000, less rounding
+    assert_eq!((position.tokens_owed_0, position.tokens_owed_1), (29_999, 0));
+    let position = test.client.get_position(&out_of_range).unwrap();
+    assert_eq!((position.tokens_owed_0, position.tokens_owed_1), (0, 0));
+
+    let recipient = Address::generate(&test.env);
+    let collected = test.client.collect(&in_range, &recipient, &i128::MAX, &i128::MAX);
+    assert_eq!(collected, (29_999, 0));
+    assert_eq!(test.token0.balance(&recipient), 29_999);
+    assert_eq!(test.client.get_position(&in_range).unwrap().tokens_owed_0, 0);
+}
+
+#[test]
+fn test_burn_and_collect() {
+    let test = setup_test();
+    let (position_id, amount0, amount1) = test.client.mint(&test.user, &-600, &600, &10_000_000_000);
+
+    let (burned0, burned1) = test.client.burn(&position_id, &10_000_000_000);
+    // Minting rounds up and burning rounds down
+    assert_eq!((burned0, burned1), (amount0 - 1, amount1 - 1));
+    assert_eq!(test.client.get_liquidity(), 0);
+    // Ticks no longer bounding any position are cleared
+    assert_eq!(test.client.get_tick_info(&-600), None);
+    assert_eq!(test.client.get_tick_info(&600), None);
+
+    let recipient = Address::generate(&test.env);
+    test.client.collect(&position_id, &recipient, &i128::MAX, &i128::MAX);
+    assert_eq!(test.token0.balance(&recipient), burned0);
+    assert_eq!(test.token1.balance(&recipient), burned1);
+}
+
+#[test]
+#[should_panic(expected = "Error(Contract, #7)")]
+fn test_burn_more_than_position() {
+    let test = setup_test();
+    let (position_id, _, _) = test.client.mint(&test.user, &-600, &600, &10_000_000_000);
+    test.client.burn(&position_id, &10_000_000_001);
+}
+
+#[test]
+fn test_increase_liquidity_and_transfer_position() {
+    let test = setup_test();
+    let (position_id, amount0, amount1) = test.client.mint(&test.user, &-600, &600, &10_000_000_000);
+    assert_eq!(test.client.increase_liquidity(&position_id, &10_000_000_000), (amount0, amount1));
+    assert_eq!(test.client.get_position(&position_id).unwrap().liquidity, 20_000_000_000);
+
+    let new_owner = Address::generate(&test.env);
+    test.client.transfer_position(&position_id, &new_owner);
+    assert_eq!(test.client.get_position(&position_id).unwrap().owner, new_owner);
+}
+
+#[test]
+#[should_panic(expected = "Error(Contract, #31)")]
+fn test_burn_unknown_position() {
+    let test = setup_test();
+    test.client.burn(&1, &0);
+}
@@ -0,0 +1,195 @@
+//! Initialized ticks of the pool, and the bitmap the swap walks to find the next one.
+
+use ethnum::U256;
+use soroban_sdk::{contracttype, Env};
+
+use crate::concentrated_pool::{bump_persistent, DataKey};
+use crate::error::ConcentratedPoolError;
+use crate::math::add_delta;
+use raumfi_library::{from_host_u256, to_host_u256, MAX_TICK, MIN_TICK};
+
+/// State of a tick that bounds at least one position
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct TickInfo {
+    /// Total liquidity of the positions bounded by this tick
+    pub liquidity_gross: u128,
+    /// Liquidity added to the pool when the price crosses this tick upwards
+    pub liquidity_net: i128,
+    /// Fee growth per unit of liquidity, in Q128, on the other side of this tick from
+    /// the current price
+    pub fee_growth_outside_0: soroban_sdk::U256,
+    pub fee_growth_outside_1: soroban_sdk::U256,
+}
+
+/// Largest liquidity a single tick may reference, so that the pool's liquidity cannot
+/// overflow even with every usable tick initialized
+pub fn max_liquidity_per_tick(tick_spacing: i32) -> u128 {
+    let min_tick = (MIN_TICK / tick_spacing) * tick_spacing;
+    let max_tick = (MAX_TICK / tick_spacing) * tick_spacing;
+    let num_ticks = ((max_tick - min_tick) / tick_spacing) as u128 + 1;
+    u128::MAX / num_ticks
+}
+
+pub fn get(env: &Env, tick: i32) -> Option<TickInfo> {
+    let key = DataKey::Tick(tick);
+    let info = env.storage().persistent().get(&key);
+    if info.is_some() {
+        bump_persistent(env, &key);
+    }
+    info
+}
+
+fn set(env: &Env, tick: i32, info: &TickInfo) {
+    let key = DataKey::Tick(tick);
+    env.storage().persistent().set(&key, info);
+    bump_persistent(env, &key);
+}
+
+pub fn clear(env: &Env, tick: i32) {
+    env.storage().persistent().remove(&DataKey::Tick(tick));
+}
+
+fn fee_growth_outside(env: &Env, tick: i32) -> (U256, U256) {
+    match get(env, tick) {
+        Some(info) => (from_host_u256(&info.fee_growth_outside_0), from_host_u256(&info.fee_growth_outside_1)),
+        None => (U256::ZERO, U256::ZERO),
+    }
+}
+
+/// Adds `liquidity_delta` to the positions bounded by `tick` and returns whether the tick
+/// flipped between initialized and uninitialized. A tick left without liquidity is kept
+/// until the caller has settled the fees of the position and calls `clear`.
+pub fn update(
+    env: &Env,
+    tick: i32,
+    tick_current: i32,
+    liquidity_delta: i128,
+    (fee_growth_global_0, fee_growth_global_1): (U256, U256),
+    upper: bool,
+    max_liquidity: u128,
+) -> Result<bool, ConcentratedPoolError> {
+    let mut info = get(env, tick).unwrap_or(TickInfo {
+        liquidity_gross: 0,
+        liquidity_net: 0,
+        fee_growth_outside_0: to_host_u256(env, U256::ZERO),
+        fee_growth_outside_1: to_host_u256(env, U256::ZERO),
+    });
+
+    let liquidity_gross_before = info.liquidity_gross;
+    let liquidity_gross_after = add_delta(liquidity_gross_before, liquidity_delta)?;
+    if liquidity_gross_after > max_liquidity {
+        return Err(ConcentratedPoolError::TickLiquidityOverflow);
+    }
+    let flipped = (liquidity_gross_after == 0) != (liquidity_gross_before == 0);
+
+    // By convention, all fee growth before a tick is initialized happened below it
+    if liquidity_gross_before == 0 && tick <= tick_current {
+        info.fee_growth_outside_0 = to_host_u256(env, fee_growth_global_0);
+        info.fee_growth_outside_1 = to_host_u256(env, fee_growth_global_1);
+    }
+    info.liquidity_gross = liquidity_gross_after;
+    info.liquidity_net = if upper {
+        info.liquidity_net.checked_sub(liquidity_delta)
+    } else {
+        info.liquidity_net.checked_add(liquidity_delta)
+    }
+    .ok_or(ConcentratedPoolError::Overflow)?;
+
+    set(env, tick, &info);
+    Ok(flipped)
+}
+
+/// Flips the fee growth outside of `tick` as the price crosses it, and returns the
+/// liquidity to add when crossing upwards
+pub fn cross(env: &Env, tick: i32, fee_growth_global_0: U256, fee_growth_global_1: U256) -> i128 {
+    let Some(mut info) = get(env, tick) else {
+        return 0;
+    };
+    info.fee_growth_outside_0 = to_host_u256(env, fee_growth_global_0.wrapping_sub(from_host_u256(&info.fee_growth_outside_0)));
+    info.fee_growth_outside_1 = to_host_u256(env, fee_growth_global_1.wrapping_sub(from_host_u256(&info.fee_growth_outside_1)));
+    set(env, tick, &info);
+    info.liquidity_net
+}
+
+/// Fee growth per unit of liquidity between `tick_lower` and `tick_upper`. Fee growth
+/// counters only ever increase, so differences are taken modulo 2^256.
+pub fn get_fee_growth_inside(
+    env: &Env,
+    tick_lower: i32,
+    tick_upper: i32,
+    tick_current: i32,
+    fee_growth_global_0: U256,
+    fee_growth_global_1: U256,
+) -> (U256, U256) {
+    let (lower_0, lower_1) = fee_growth_outside(env, tick_lower);
+    let (upper_0, upper_1) = fee_growth_outside(env, tick_upper);
+
+    let (below_0, below_1) = if tick_current >= tick_lower {
+        (lower_0, lower_1)
+    } else {
+        (fee_growth_global_0.wrapping_sub(lower_0), fee_growth_global_1.wrapping_sub(lower_1))
+    };
+    let (above_0, above_1) = if tick_current < tick_upper {
+        (upper_0, upper_1)
+    } else {
+        (fee_growth_global_0.wrapping_sub(upper_0), fee_growth_global_1.wrapping_sub(upper_1))
+    };
+
+    (
+        fee_growth_global_0.wrapping_sub(below_0).wrapping_sub(above_0),
+        fee_growth_global_1.wrapping_sub(below_1).wrapping_sub(above_1),
+    )
+}
+
+/// Word and bit of a tick index, already divided by the tick spacing, in the bitmap
+fn bitmap_position(compressed: i32) -> (i32, u32) {
+    (compressed >> 7, (compressed & 127) as u32)
+}
+
+fn bitmap_word(env: &Env, word_position: i32) -> u128 {
+    env.storage().persistent().get(&DataKey::TickBitmap(word_position)).unwrap_or(0)
+}
+
+/// Marks `tick` as initialized if it was not, and as uninitialized otherwise
+pub fn flip(env: &Env, tick: i32, tick_spacing: i32) {
+    let (word_position, bit_position) = bitmap_position(tick / tick_spacing);
+    let key = DataKey::TickBitmap(word_position);
+    let word = bitmap_word(env, word_position) ^ (1u128 << bit_position);
+    if word == 0 {
+        env.storage().persistent().remove(&key);
+    } else {
+        env.storage().persistent().set(&key, &word);
+        bump_persistent(env, &key);
+    }
+}
+
+/// Returns the next initialized tick at or below `tick` (`lte`) or above it, looking no
+/// further than the bitmap word of `tick`. When none is found, returns the last tick of
+/// the word and `false`.
+pub fn next_initialized_tick_within_one_word(env: &Env, tick: i32, tick_spacing: i32, lte: bool) -> (i32, bool) {
+    let compressed = tick.div_euclid(tick_spacing);
+    if lte {
+        let (word_position, bit_position) = bitmap_position(compressed);
+        // The current bit and every bit below it
+        let mask = (1u128 << bit_position) - 1 + (1u128 << bit_position);
+        let masked = bitmap_word(env, word_position) & mask;
+        if masked != 0 {
+            let most_significant_bit = 127 - masked.leading_zeros();
+            ((compressed - (bit_position - most_significant_bit) as i32) * tick_spacing, true)
+        } else {
+            ((compressed - bit_position as i32) * tick_spacing, false)
+        }
+    } else {
+        let (word_position, bit_position) = bitmap_position(compressed + 1);
+        // The next bit and every bit above it
+        let mask = !((1u128 << bit_position) - 1);
+        let masked = bitmap_word(env, word_position) & mask;
+        if masked != 0 {
+            let least_significant_bit = masked.trailing_zeros();
+            ((compressed + 1 + (least_significant_bit - bit_position) as i32) * tick_spacing, true)
+        } else {
+            ((compressed + 1 + (127 - bit_position) as i32) * tick_spacing, false)
+        }
+    }
+}
@@ -12,5 +12,12 @@ pub enum RaumFiFactoryError {
     PairDoesNotExist = 5,
     IndexDoesNotExist = 6,
     PairAlreadyExists = 7,
+    InvalidFeeTier = 8,
+    InvalidProtocolFeeShare = 9,
+    InvalidDynamicFeeBounds = 10,
+    InvalidWeights = 11,
+    InvalidDirectionalFees = 12,
+    InvalidCreatorFeeShare = 13,
+    InvalidMinInitialLiquidity = 14,
 }
 
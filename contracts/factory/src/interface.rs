@@ -1,4 +1,4 @@
-use soroban_sdk::{contractclient, contractspecfn, Address, Env , BytesN};
+use soroban_sdk::{contractclient, contractspecfn, Address, Env , BytesN, Vec, U256};
 
 
 pub use crate::error::RaumFiFactoryError;
@@ -30,8 +30,110 @@ pub trait RaumFiFactoryInterface {
     /// Set the address that can change the fee recipient
     fn set_fee_to_setter(env: Env, fee_to_setter: Address);
 
+    /// Turn the protocol fee on or off for every pair
+    fn set_fees_enabled(env: Env, is_enabled: bool);
+
+    /// Whether pairs mint the protocol fee to `fee_to`; on until switched off
+    fn fees_enabled(env: Env) -> bool;
+
+    /// Set the share of LP fee growth minted to `fee_to`, in basis points
+    fn set_protocol_fee_share(env: Env, share: u32) -> Result<(), RaumFiFactoryError>;
+
+    /// Get the share of LP fee growth minted to `fee_to`, in basis points
+    fn get_protocol_fee_share(env: Env) -> u32;
+
+    /// Set the LP units the first depositor of every pair must receive on top of the locked minimum
+    fn set_min_initial_liquidity(env: Env, liquidity: i128) -> Result<(), RaumFiFactoryError>;
+
+    /// Get the LP units the first depositor of every pair must receive on top of the locked minimum
+    fn get_min_initial_liquidity(env: Env) -> i128;
+
+    /// Pause swaps and mints on the pair of the given tokens; withdrawals stay open
+    fn pause_pair(env: Env, token_a: Address, token_b: Address) -> Result<(), RaumFiFactoryError>;
+
+    /// Resume trading on a paused pair
+    fn unpause_pair(env: Env, token_a: Address, token_b: Address) -> Result<(), RaumFiFactoryError>;
+
+    /// Let the pair's swap fee follow its price volatility between `min_fee` and `max_fee` basis points
+    fn set_pair_dynamic_fee(env: Env, token_a: Address, token_b: Address, min_fee: u32, max_fee: u32) -> Result<(), RaumFiFactoryError>;
+
+    /// Return the pair to the fee tier it was created with
+    fn disable_pair_dynamic_fee(env: Env, token_a: Address, token_b: Address) -> Result<(), RaumFiFactoryError>;
+
+    /// Charge `fee_a_in` basis points on swaps sending `token_a` into the pair and `fee_b_in` on
+    /// swaps sending `token_b`, in place of its fee tier
+    fn set_pair_directional_fees(env: Env, token_a: Address, token_b: Address, fee_a_in: u32, fee_b_in: u32) -> Result<(), RaumFiFactoryError>;
+
+    /// Return the pair to the same fee in both directions
+    fn disable_pair_directional_fees(env: Env, token_a: Address, token_b: Address) -> Result<(), RaumFiFactoryError>;
+
+    /// Make LP of the pair wait `min_ledgers` after it was minted before it can be burned, rejecting
+    /// earlier burns or, with a non-zero `exit_fee` in basis points, charging them for the remaining LPs
+    fn set_pair_lp_hold(env: Env, token_a: Address, token_b: Address, min_ledgers: u32, exit_fee: u32) -> Result<(), RaumFiFactoryError>;
+
+    /// Lift the LP hold period of the pair
+    fn disable_pair_lp_hold(env: Env, token_a: Address, token_b: Address) -> Result<(), RaumFiFactoryError>;
+
+    /// Reject swaps moving the pair's spot price more than `max_ledger_move` basis points within a
+    /// ledger, and pause the pair once it moves more than `max_window_move` within the last `window`
+    /// seconds
+    fn set_pair_circuit_breaker(env: Env, token_a: Address, token_b: Address, max_ledger_move: u32, max_window_move: u32, window: u64) -> Result<(), RaumFiFactoryError>;
+
+    /// Lift the price circuit breaker of the pair
+    fn disable_pair_circuit_breaker(env: Env, token_a: Address, token_b: Address) -> Result<(), RaumFiFactoryError>;
+
+    /// Replace the code of the pair with the uploaded wasm `new_wasm_hash`, keeping its storage
+    fn upgrade_pair(env: Env, token_a: Address, token_b: Address, new_wasm_hash: BytesN<32>) -> Result<(), RaumFiFactoryError>;
+
+    /// Set the wasm hash StableSwap pairs are deployed from
+    fn set_stable_pair_wasm_hash(env: Env, stable_pair_wasm_hash: BytesN<32>);
+
+    /// Create a StableSwap pair for the given tokens with amplification `amp` and a swap fee tier
+    fn create_stable_pair(env: Env, token_a: Address, token_b: Address, amp: u32, fee: u32) -> Result<Address, RaumFiFactoryError>;
+
+    /// Get the address of the StableSwap pair for the given tokens
+    fn get_stable_pair(env: Env, token_a: Address, token_b: Address) -> Option<Address>;
+
+    /// Ramp the amplification coefficient of a StableSwap pair to `future_a` by `future_time`
+    fn ramp_stable_pair_a(env: Env, token_a: Address, token_b: Address, future_a: u32, future_time: u64) -> Result<(), RaumFiFactoryError>;
+
+    /// Freeze the amplification coefficient of a StableSwap pair at its current value
+    fn stop_stable_pair_ramp(env: Env, token_a: Address, token_b: Address) -> Result<(), RaumFiFactoryError>;
+
+    /// Set the wasm hash weighted pools are deployed from
+    fn set_weighted_pool_wasm_hash(env: Env, weighted_pool_wasm_hash: BytesN<32>);
+
+    /// Create a pool of 2 to 8 tokens weighted by `weights` in basis points, with a swap fee tier
+    fn create_weighted_pool(env: Env, tokens: Vec<Address>, weights: Vec<u32>, fee: u32) -> Result<Address, RaumFiFactoryError>;
+
+    /// Get the addresses of all weighted pools, in order of creation
+    fn get_weighted_pools(env: Env) -> Vec<Address>;
+
+    /// Set the wasm hash concentrated liquidity pools are deployed from
+    fn set_concentrated_pool_wasm_hash(env: Env, concentrated_pool_wasm_hash: BytesN<32>);
+
+    /// Create a concentrated liquidity pool for the given tokens and swap fee tier, starting at
+    /// the Q64.96 sqrt price of the lower-addressed token in the other
+    fn create_concentrated_pool(env: Env, token_a: Address, token_b: Address, fee: u32, sqrt_price: U256) -> Result<Address, RaumFiFactoryError>;
+
+    /// Get the address of the concentrated liquidity pool for the given tokens and fee tier
+    fn get_concentrated_pool(env: Env, token_a: Address, token_b: Address, fee: u32) -> Option<Address>;
+
     fn create_new_pair(env: Env, token_a: Address, token_b: Address) -> Result<Address, RaumFiFactoryError>;
 
+    /// Create a new pair for the given tokens with a swap fee tier in basis points. Tiers other than
+    /// the default are set by the fee setter only, as the pair takes the one slot of its tokens
+    fn create_pair_with_fee(env: Env, token_a: Address, token_b: Address, fee: u32) -> Result<Address, RaumFiFactoryError>;
+
+    /// Create a new pair for the given tokens charging `fee_a_in` basis points on swaps sending
+    /// `token_a` in and `fee_b_in` on swaps sending `token_b` in. Only the fee setter may call it
+    fn create_pair_with_fees(env: Env, token_a: Address, token_b: Address, fee_a_in: u32, fee_b_in: u32) -> Result<Address, RaumFiFactoryError>;
+
+    /// Create a new pair for the given tokens with a swap fee tier, minting `creator_fee_share`
+    /// basis points of its LP fee growth to `creator_fee_recipient`. Only the fee setter may call it,
+    /// as the pair takes the one slot of its tokens
+    fn create_pair_with_creator_fee(env: Env, token_a: Address, token_b: Address, fee: u32, creator_fee_recipient: Address, creator_fee_share: u32) -> Result<Address, RaumFiFactoryError>;
+
     fn pair_exists(env: Env, token0: Address, token1: Address) -> bool;
 
 }
\ No newline at end of file
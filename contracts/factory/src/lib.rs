@@ -10,7 +10,7 @@ pub use crate::error::RaumFiFactoryError;
 use interface::RaumFiFactoryInterface;
 
 use soroban_sdk::{
-    contract, contracterror, contractimpl,contractimport ,contracttype, symbol_short, vec, xdr::ToXdr, Address, Bytes, BytesN, Env, IntoVal, Map, Symbol, Vec , log
+    contract, contracterror, contractimpl,contractimport ,contracttype, symbol_short, vec, xdr::ToXdr, Address, Bytes, BytesN, Env, IntoVal, Map, Symbol, Vec , U256, log
 };
 
 mod pair {
@@ -20,6 +20,42 @@ mod pair {
 
 use pair::PairClient;
 
+mod stable_pair {
+    soroban_sdk::contractimport!(file = "D:/RaumFiV2/RaumFiV2/target/wasm32-unknown-unknown/release/stable_pair.wasm");
+    pub type StablePairClient<'a> = Client<'a>;
+}
+
+use stable_pair::StablePairClient;
+
+mod weighted_pool {
+    soroban_sdk::contractimport!(file = "D:/RaumFiV2/RaumFiV2/target/wasm32-unknown-unknown/release/weighted_pool.wasm");
+    pub type WeightedPoolClient<'a> = Client<'a>;
+}
+
+use weighted_pool::WeightedPoolClient;
+
+mod concentrated_pool {
+    soroban_sdk::contractimport!(file = "D:/RaumFiV2/RaumFiV2/target/wasm32-unknown-unknown/release/concentrated_pool.wasm");
+    pub type ConcentratedPoolClient<'a> = Client<'a>;
+}
+
+use concentrated_pool::ConcentratedPoolClient;
+
+/// Swap fee tiers, in basis points, that a pair can be created with
+pub const FEE_TIERS: [u32; 4] = [1, 5, 30, 100];
+/// Tick spacing of the concentrated pools of each fee tier, in the order of `FEE_TIERS`
+pub const TICK_SPACINGS: [i32; 4] = [1, 10, 60, 200];
+/// Fee tier used when a pair is created without an explicit tier (0.3%)
+pub const DEFAULT_FEE_TIER: u32 = 30;
+/// Share of the LP fee growth minted to `fee_to`, in basis points (~1/6)
+pub const DEFAULT_PROTOCOL_FEE_SHARE: u32 = 1667;
+/// Upper bound on the protocol share, in basis points (50%)
+pub const MAX_PROTOCOL_FEE_SHARE: u32 = 5000;
+/// Upper bound on the share of LP fee growth a pair creator can take, in basis points (50%)
+pub const MAX_CREATOR_FEE_SHARE: u32 = 5000;
+/// LP units the first depositor of a pair must receive on top of the locked minimum
+pub const DEFAULT_MIN_INITIAL_LIQUIDITY: i128 = 1000;
+
 #[contracterror]
 #[derive(Copy, Clone, Debug, Eq, PartialEq, PartialOrd, Ord)]
 #[repr(u32)]
@@ -34,6 +70,15 @@ pub enum Error {
 #[derive(Clone)]
  pub enum DataKey {
     Pair(Address, Address),
+    StablePair(Address, Address),
+    StablePairHash,
+    /// Weighted pool deployed under a salt of its sorted tokens, weights and fee
+    WeightedPool(BytesN<32>),
+    WeightedPools,
+    WeightedPoolHash,
+    /// Concentrated liquidity pool of two sorted tokens at a fee tier
+    ConcentratedPool(Address, Address, u32),
+    ConcentratedPoolHash,
 }
 
 #[contracttype]
@@ -69,6 +114,7 @@ pub trait RaumFiFactoryTrait {
     fn set_fee_to_setter(env: Env, fee_to_setter: Address);
     fn pair_exists(env: &Env, token0: &Address, token1: &Address) -> bool;
     fn create_new_pair(env: Env, token_a: Address, token_b: Address) -> Result<Address, RaumFiFactoryError>;
+    fn create_pair_with_fee(env: Env, token_a: Address, token_b: Address, fee: u32) -> Result<Address, RaumFiFactoryError>;
 } 
 
 pub struct RaumFiV2Factory;
@@ -91,6 +137,8 @@ impl RaumFiFactoryInterface for FactoryContract  {
         env.storage().persistent().set(&symbol_short!("feeto"), &fee_to_setter);
         env.storage().persistent().set(&symbol_short!("pair_hash"), &pair_wasm_hash);
         env.storage().persistent().set(&symbol_short!("pairs"), &0u32);
+        env.storage().persistent().set(&symbol_short!("fees_on"), &true);
+        env.storage().persistent().set(&symbol_short!("fee_share"), &DEFAULT_PROTOCOL_FEE_SHARE);
     }
 
     fn all_pairs_length(env: Env) -> u32 {
@@ -125,20 +173,375 @@ impl RaumFiFactoryInterface for FactoryContract  {
         env.storage().persistent().set(&symbol_short!("feesetter"), &fee_to_setter);
     }
 
+    fn set_fees_enabled(env: Env, is_enabled: bool) {
+        let setter: Address = env.storage().persistent().get(&symbol_short!("feesetter")).unwrap();
+        setter.require_auth();
+        env.storage().persistent().set(&symbol_short!("fees_on"), &is_enabled);
+        env.events().publish((Symbol::new(&env, "fees_enabled"),), is_enabled);
+    }
+
+    fn fees_enabled(env: Env) -> bool {
+        env.storage().persistent().get(&symbol_short!("fees_on")).unwrap_or(true)
+    }
+
+    fn set_protocol_fee_share(env: Env, share: u32) -> Result<(), RaumFiFactoryError> {
+        let setter: Address = env.storage().persistent().get(&symbol_short!("feesetter")).unwrap();
+        setter.require_auth();
+        if share == 0 || share > MAX_PROTOCOL_FEE_SHARE {
+            return Err(RaumFiFactoryError::InvalidProtocolFeeShare);
+        }
+        env.storage().persistent().set(&symbol_short!("fee_share"), &share);
+        env.events().publish((Symbol::new(&env, "protocol_fee_share"),), share);
+        Ok(())
+    }
+
+    fn get_protocol_fee_share(env: Env) -> u32 {
+        env.storage().persistent().get(&symbol_short!("fee_share")).unwrap_or(DEFAULT_PROTOCOL_FEE_SHARE)
+    }
+
+    fn set_min_initial_liquidity(env: Env, liquidity: i128) -> Result<(), RaumFiFactoryError> {
+        let setter: Address = env.storage().persistent().get(&symbol_short!("feesetter")).unwrap();
+        setter.require_auth();
+        if liquidity < 0 {
+            return Err(RaumFiFactoryError::InvalidMinInitialLiquidity);
+        }
+        env.storage().persistent().set(&symbol_short!("min_liq"), &liquidity);
+        env.events().publish((Symbol::new(&env, "min_initial_liquidity"),), liquidity);
+        Ok(())
+    }
+
+    fn get_min_initial_liquidity(env: Env) -> i128 {
+        env.storage().persistent().get(&symbol_short!("min_liq")).unwrap_or(DEFAULT_MIN_INITIAL_LIQUIDITY)
+    }
+
+    fn pause_pair(env: Env, token_a: Address, token_b: Address) -> Result<(), RaumFiFactoryError> {
+        let pair = RaumFiV2Factory::pair_for_admin(&env, &token_a, &token_b)?;
+        PairClient::new(&env, &pair).pause();
+        Ok(())
+    }
+
+    fn unpause_pair(env: Env, token_a: Address, token_b: Address) -> Result<(), RaumFiFactoryError> {
+        let pair = RaumFiV2Factory::pair_for_admin(&env, &token_a, &token_b)?;
+        PairClient::new(&env, &pair).unpause();
+        Ok(())
+    }
+
+    fn set_pair_dynamic_fee(env: Env, token_a: Address, token_b: Address, min_fee: u32, max_fee: u32) -> Result<(), RaumFiFactoryError> {
+        if min_fee > max_fee || max_fee > FEE_TIERS[FEE_TIERS.len() - 1] {
+            return Err(RaumFiFactoryError::InvalidDynamicFeeBounds);
+        }
+        let pair = RaumFiV2Factory::pair_for_admin(&env, &token_a, &token_b)?;
+        PairClient::new(&env, &pair).set_dynamic_fee(&Some(pair::DynamicFeeConfig { min_fee, max_fee }));
+        Ok(())
+    }
+
+    fn disable_pair_dynamic_fee(env: Env, token_a: Address, token_b: Address) -> Result<(), RaumFiFactoryError> {
+        let pair = RaumFiV2Factory::pair_for_admin(&env, &token_a, &token_b)?;
+        PairClient::new(&env, &pair).set_dynamic_fee(&None);
+        Ok(())
+    }
+
+    fn set_pair_directional_fees(env: Env, token_a: Address, token_b: Address, fee_a_in: u32, fee_b_in: u32) -> Result<(), RaumFiFactoryError> {
+        let fees = RaumFiV2Factory::directional_fees(&token_a, &token_b, fee_a_in, fee_b_in)?;
+        let pair = RaumFiV2Factory::pair_for_admin(&env, &token_a, &token_b)?;
+        PairClient::new(&env, &pair).set_directional_fees(&Some(fees));
+        Ok(())
+    }
+
+    fn disable_pair_directional_fees(env: Env, token_a: Address, token_b: Address) -> Result<(), RaumFiFactoryError> {
+        let pair = RaumFiV2Factory::pair_for_admin(&env, &token_a, &token_b)?;
+        PairClient::new(&env, &pair).set_directional_fees(&None);
+        Ok(())
+    }
+
+    fn set_pair_lp_hold(env: Env, token_a: Address, token_b: Address, min_ledgers: u32, exit_fee: u32) -> Result<(), RaumFiFactoryError> {
+        let pair = RaumFiV2Factory::pair_for_admin(&env, &token_a, &token_b)?;
+        PairClient::new(&env, &pair).set_lp_hold(&Some(pair::LpHoldConfig { min_ledgers, exit_fee }));
+        Ok(())
+    }
+
+    fn disable_pair_lp_hold(env: Env, token_a: Address, token_b: Address) -> Result<(), RaumFiFactoryError> {
+        let pair = RaumFiV2Factory::pair_for_admin(&env, &token_a, &token_b)?;
+        PairClient::new(&env, &pair).set_lp_hold(&None);
+        Ok(())
+    }
+
+    fn set_pair_circuit_breaker(env: Env, token_a: Address, token_b: Address, max_ledger_move: u32, max_window_move: u32, window: u64) -> Result<(), RaumFiFactoryError> {
+        let pair = RaumFiV2Factory::pair_for_admin(&env, &token_a, &token_b)?;
+        PairClient::new(&env, &pair).set_circuit_breaker(&Some(pair::CircuitBreakerConfig { max_ledger_move, max_window_move, window }));
+        Ok(())
+    }
+
+    fn disable_pair_circuit_breaker(env: Env, token_a: Address, token_b: Address) -> Result<(), RaumFiFactoryError> {
+        let pair = RaumFiV2Factory::pair_for_admin(&env, &token_a, &token_b)?;
+        PairClient::new(&env, &pair).set_circuit_breaker(&None);
+        Ok(())
+    }
+
+    fn upgrade_pair(env: Env, token_a: Address, token_b: Address, new_wasm_hash: BytesN<32>) -> Result<(), RaumFiFactoryError> {
+        let pair = RaumFiV2Factory::pair_for_admin(&env, &token_a, &token_b)?;
+        PairClient::new(&env, &pair).upgrade(&new_wasm_hash);
+        Ok(())
+    }
+
+    fn set_stable_pair_wasm_hash(env: Env, stable_pair_wasm_hash: BytesN<32>) {
+        let setter: Address = env.storage().persistent().get(&symbol_short!("feesetter")).unwrap();
+        setter.require_auth();
+        env.storage().persistent().set(&DataKey::StablePairHash, &stable_pair_wasm_hash);
+    }
+
+    fn create_stable_pair(env: Env, token_a: Address, token_b: Address, amp: u32, fee: u32) -> Result<Address, RaumFiFactoryError> {
+        RaumFiV2Factory::create_stable_pair(&env, &token_a, &token_b, amp, fee)
+    }
+
+    fn get_stable_pair(env: Env, token_a: Address, token_b: Address) -> Option<Address> {
+        let (token0, token1) = sort_tokens(&token_a, &token_b);
+        env.storage().persistent().get(&DataKey::StablePair(token0, token1))
+    }
+
+    fn ramp_stable_pair_a(env: Env, token_a: Address, token_b: Address, future_a: u32, future_time: u64) -> Result<(), RaumFiFactoryError> {
+        let pair = RaumFiV2Factory::stable_pair_for_admin(&env, &token_a, &token_b)?;
+        StablePairClient::new(&env, &pair).ramp_a(&future_a, &future_time);
+        Ok(())
+    }
+
+    fn stop_stable_pair_ramp(env: Env, token_a: Address, token_b: Address) -> Result<(), RaumFiFactoryError> {
+        let pair = RaumFiV2Factory::stable_pair_for_admin(&env, &token_a, &token_b)?;
+        StablePairClient::new(&env, &pair).stop_ramp_a();
+        Ok(())
+    }
+
+    fn set_weighted_pool_wasm_hash(env: Env, weighted_pool_wasm_hash: BytesN<32>) {
+        let setter: Address = env.storage().persistent().get(&symbol_short!("feesetter")).unwrap();
+        setter.require_auth();
+        env.storage().persistent().set(&DataKey::WeightedPoolHash, &weighted_pool_wasm_hash);
+    }
+
+    fn create_weighted_pool(env: Env, tokens: Vec<Address>, weights: Vec<u32>, fee: u32) -> Result<Address, RaumFiFactoryError> {
+        RaumFiV2Factory::create_weighted_pool(&env, &tokens, &weights, fee)
+    }
+
+    fn get_weighted_pools(env: Env) -> Vec<Address> {
+        env.storage().persistent().get(&DataKey::WeightedPools).unwrap_or(Vec::new(&env))
+    }
+
+    fn set_concentrated_pool_wasm_hash(env: Env, concentrated_pool_wasm_hash: BytesN<32>) {
+        let setter: Address = env.storage().persistent().get(&symbol_short!("feesetter")).unwrap();
+        setter.require_auth();
+        env.storage().persistent().set(&DataKey::ConcentratedPoolHash, &concentrated_pool_wasm_hash);
+    }
+
+    fn create_concentrated_pool(env: Env, token_a: Address, token_b: Address, fee: u32, sqrt_price: U256) -> Result<Address, RaumFiFactoryError> {
+        RaumFiV2Factory::create_concentrated_pool(&env, &token_a, &token_b, fee, &sqrt_price)
+    }
+
+    fn get_concentrated_pool(env: Env, token_a: Address, token_b: Address, fee: u32) -> Option<Address> {
+        let (token0, token1) = sort_tokens(&token_a, &token_b);
+        env.storage().persistent().get(&DataKey::ConcentratedPool(token0, token1, fee))
+    }
+
     fn create_new_pair(env: Env, token_a: Address, token_b: Address) -> Result<Address, RaumFiFactoryError> {
-        let pair = RaumFiV2Factory::create_pair(&env, &token_a, &token_b)?;
+        let pair = RaumFiV2Factory::create_pair(&env, &token_a, &token_b, DEFAULT_FEE_TIER)?;
+        Ok(pair)
+    }
+
+    fn create_pair_with_fee(env: Env, token_a: Address, token_b: Address, fee: u32) -> Result<Address, RaumFiFactoryError> {
+        if fee != DEFAULT_FEE_TIER {
+            RaumFiV2Factory::require_fee_setter(&env)?;
+        }
+        let pair = RaumFiV2Factory::create_pair(&env, &token_a, &token_b, fee)?;
+        Ok(pair)
+    }
+
+    fn create_pair_with_creator_fee(env: Env, token_a: Address, token_b: Address, fee: u32, creator_fee_recipient: Address, creator_fee_share: u32) -> Result<Address, RaumFiFactoryError> {
+        RaumFiV2Factory::require_fee_setter(&env)?;
+        if creator_fee_share == 0 || creator_fee_share > MAX_CREATOR_FEE_SHARE {
+            return Err(RaumFiFactoryError::InvalidCreatorFeeShare);
+        }
+        let pair = RaumFiV2Factory::create_pair(&env, &token_a, &token_b, fee)?;
+        PairClient::new(&env, &pair).set_creator_fee(&pair::CreatorFee { recipient: creator_fee_recipient, share: creator_fee_share });
+        Ok(pair)
+    }
+
+    fn create_pair_with_fees(env: Env, token_a: Address, token_b: Address, fee_a_in: u32, fee_b_in: u32) -> Result<Address, RaumFiFactoryError> {
+        RaumFiV2Factory::require_fee_setter(&env)?;
+        let fees = RaumFiV2Factory::directional_fees(&token_a, &token_b, fee_a_in, fee_b_in)?;
+        let pair = RaumFiV2Factory::create_pair(&env, &token_a, &token_b, DEFAULT_FEE_TIER)?;
+        PairClient::new(&env, &pair).set_directional_fees(&Some(fees));
         Ok(pair)
     }
 }
 
 impl RaumFiV2Factory {
 
+    /// Checks the fee setter's authorization and returns the stable pair of the given tokens
+    fn stable_pair_for_admin(env: &Env, token_a: &Address, token_b: &Address) -> Result<Address, RaumFiFactoryError> {
+        let setter: Address = env.storage().persistent().get(&symbol_short!("feesetter")).ok_or(RaumFiFactoryError::NotInitialized)?;
+        setter.require_auth();
+        FactoryContract::get_stable_pair(env.clone(), token_a.clone(), token_b.clone()).ok_or(RaumFiFactoryError::PairDoesNotExist)
+    }
+
+    fn create_stable_pair(env: &Env, token_a: &Address, token_b: &Address, amp: u32, fee: u32) -> Result<Address, RaumFiFactoryError> {
+        if token_a == token_b {
+            return Err(RaumFiFactoryError::CreatePairIdenticalTokens);
+        }
+        if !FEE_TIERS.contains(&fee) {
+            return Err(RaumFiFactoryError::InvalidFeeTier);
+        }
+        let (token0, token1) = sort_tokens(token_a, token_b);
+        let key = DataKey::StablePair(token0.clone(), token1.clone());
+        if env.storage().persistent().has(&key) {
+            return Err(RaumFiFactoryError::PairAlreadyExists);
+        }
+        let stable_pair_wasm_hash: BytesN<32> = env.storage().persistent().get(&DataKey::StablePairHash)
+            .ok_or(RaumFiFactoryError::NotInitialized)?;
+
+        // Salted apart from the constant-product pair of the same tokens
+        let mut salt = Bytes::new(env);
+        salt.append(&token0.clone().to_xdr(env));
+        salt.append(&token1.clone().to_xdr(env));
+        salt.append(&Bytes::from_slice(env, b"stable"));
+        let salt = env.crypto().sha256(&salt);
+        let pair: Address = env.deployer().with_current_contract(salt).deploy(stable_pair_wasm_hash);
+        StablePairClient::new(env, &pair).initialize(
+            &token0,
+            &token1,
+            &env.current_contract_address(),
+            &fee,
+            &amp,
+        );
+
+        let topics = (Symbol::new(env, "stablepairmade"), token0.clone(), token1.clone());
+        env.events().publish(topics, pair.clone());
+        env.storage().persistent().set(&key, &pair);
+        Ok(pair)
+    }
+
+    fn create_weighted_pool(env: &Env, tokens: &Vec<Address>, weights: &Vec<u32>, fee: u32) -> Result<Address, RaumFiFactoryError> {
+        if !FEE_TIERS.contains(&fee) {
+            return Err(RaumFiFactoryError::InvalidFeeTier);
+        }
+        if tokens.len() != weights.len() {
+            return Err(RaumFiFactoryError::InvalidWeights);
+        }
+
+        // Sort the tokens, carrying their weights along, so that a pool has a single address
+        let mut sorted_tokens: Vec<Address> = Vec::new(env);
+        let mut sorted_weights: Vec<u32> = Vec::new(env);
+        for (token, weight) in tokens.iter().zip(weights.iter()) {
+            let mut index = 0;
+            while index < sorted_tokens.len() && sorted_tokens.get(index).unwrap() < token {
+                index += 1;
+            }
+            if sorted_tokens.get(index) == Some(token.clone()) {
+                return Err(RaumFiFactoryError::CreatePairIdenticalTokens);
+            }
+            sorted_tokens.insert(index, token);
+            sorted_weights.insert(index, weight);
+        }
+
+        let mut salt = Bytes::new(env);
+        salt.append(&sorted_tokens.clone().to_xdr(env));
+        salt.append(&sorted_weights.clone().to_xdr(env));
+        salt.append(&fee.to_xdr(env));
+        salt.append(&Bytes::from_slice(env, b"weighted"));
+        let salt = env.crypto().sha256(&salt);
+        let key = DataKey::WeightedPool(salt.to_bytes());
+        if env.storage().persistent().has(&key) {
+            return Err(RaumFiFactoryError::PairAlreadyExists);
+        }
+        let weighted_pool_wasm_hash: BytesN<32> = env.storage().persistent().get(&DataKey::WeightedPoolHash)
+            .ok_or(RaumFiFactoryError::NotInitialized)?;
+
+        let pool: Address = env.deployer().with_current_contract(salt).deploy(weighted_pool_wasm_hash);
+        WeightedPoolClient::new(env, &pool).initialize(
+            &sorted_tokens,
+            &sorted_weights,
+            &env.current_contract_address(),
+            &fee,
+        );
+
+        let topics = (Symbol::new(env, "weightedpoolmade"), sorted_tokens, sorted_weights);
+        env.events().publish(topics, pool.clone());
+        env.storage().persistent().set(&key, &pool);
+        let mut pools = FactoryContract::get_weighted_pools(env.clone());
+        pools.push_back(pool.clone());
+        env.storage().persistent().set(&DataKey::WeightedPools, &pools);
+        Ok(pool)
+    }
+
+    fn create_concentrated_pool(env: &Env, token_a: &Address, token_b: &Address, fee: u32, sqrt_price: &U256) -> Result<Address, RaumFiFactoryError> {
+        if token_a == token_b {
+            return Err(RaumFiFactoryError::CreatePairIdenticalTokens);
+        }
+        let tier = FEE_TIERS.iter().position(|tier| *tier == fee).ok_or(RaumFiFactoryError::InvalidFeeTier)?;
+        let (token0, token1) = sort_tokens(token_a, token_b);
+        let key = DataKey::ConcentratedPool(token0.clone(), token1.clone(), fee);
+        if env.storage().persistent().has(&key) {
+            return Err(RaumFiFactoryError::PairAlreadyExists);
+        }
+        let concentrated_pool_wasm_hash: BytesN<32> = env.storage().persistent().get(&DataKey::ConcentratedPoolHash)
+            .ok_or(RaumFiFactoryError::NotInitialized)?;
+
+        // One pool per fee tier, salted apart from the other pool types of the same tokens
+        let mut salt = Bytes::new(env);
+        salt.append(&token0.clone().to_xdr(env));
+        salt.append(&token1.clone().to_xdr(env));
+        salt.append(&fee.to_xdr(env));
+        salt.append(&Bytes::from_slice(env, b"concentrated"));
+        let salt = env.crypto().sha256(&salt);
+        let pool: Address = env.deployer().with_current_contract(salt).deploy(concentrated_pool_wasm_hash);
+        ConcentratedPoolClient::new(env, &pool).initialize(
+            &token0,
+            &token1,
+            &env.current_contract_address(),
+            &fee,
+            &TICK_SPACINGS[tier],
+            sqrt_price,
+        );
+
+        let topics = (Symbol::new(env, "concentratedpoolmade"), token0, token1, fee);
+        env.events().publish(topics, pool.clone());
+        env.storage().persistent().set(&key, &pool);
+        Ok(pool)
+    }
 
-    fn create_pair(env: &Env, token_a: &Address, token_b: &Address) -> Result<Address, RaumFiFactoryError> {
+    /// Orders fees given for each of two unsorted tokens sent in by the pair's token0 and
+    /// token1. Each is bounded by the highest fee tier, like dynamic fees.
+    fn directional_fees(token_a: &Address, token_b: &Address, fee_a_in: u32, fee_b_in: u32) -> Result<pair::DirectionalFees, RaumFiFactoryError> {
+        let max_fee = FEE_TIERS[FEE_TIERS.len() - 1];
+        if fee_a_in > max_fee || fee_b_in > max_fee {
+            return Err(RaumFiFactoryError::InvalidDirectionalFees);
+        }
+        Ok(if token_a < token_b {
+            pair::DirectionalFees { fee0_in: fee_a_in, fee1_in: fee_b_in }
+        } else {
+            pair::DirectionalFees { fee0_in: fee_b_in, fee1_in: fee_a_in }
+        })
+    }
+
+    /// Checks the fee setter's authorization
+    fn require_fee_setter(env: &Env) -> Result<(), RaumFiFactoryError> {
+        let setter: Address = env.storage().persistent().get(&symbol_short!("feesetter")).ok_or(RaumFiFactoryError::NotInitialized)?;
+        setter.require_auth();
+        Ok(())
+    }
+
+    /// Checks the fee setter's authorization and returns the pair of the given tokens
+    fn pair_for_admin(env: &Env, token_a: &Address, token_b: &Address) -> Result<Address, RaumFiFactoryError> {
+        RaumFiV2Factory::require_fee_setter(env)?;
+        FactoryContract::get_pair(env.clone(), token_a.clone(), token_b.clone()).ok_or(RaumFiFactoryError::PairDoesNotExist)
+    }
+
+
+    fn create_pair(env: &Env, token_a: &Address, token_b: &Address, fee: u32) -> Result<Address, RaumFiFactoryError> {
         
         if token_a == token_b {
             return Err(RaumFiFactoryError::CreatePairIdenticalTokens);
         }
+        if !FEE_TIERS.contains(&fee) {
+            return Err(RaumFiFactoryError::InvalidFeeTier);
+        }
         let (token0, token1) = if token_a < token_b {
             (token_a, token_b)
         } else {
@@ -171,11 +574,12 @@ impl RaumFiV2Factory {
             &token0, 
             &token1,
             &env.current_contract_address(),
+            &fee,
         );
 
         let topics = (Symbol::new(env, "pairmade"), token_a.clone(), token_b.clone());
         env.events().publish(topics, pair.clone());
-        env.storage().persistent().set(&DataKey::Pair(token_a.clone(), token_b.clone()), &pair);
+        env.storage().persistent().set(&key, &pair);
         let current_pairs = env.storage().persistent().get(&symbol_short!("pairs")).unwrap_or(0u32);
         env.storage().persistent().set(&symbol_short!("pairs"), &(current_pairs + 1));
 
@@ -1,7 +1,7 @@
 #![cfg(test)]
 
-use crate::{FactoryContract, FactoryContractClient,  RaumFiV2Factory, Error};
-use soroban_sdk::{testutils::Address as TestAddress, Address, BytesN, Env, symbol_short, IntoVal, Symbol , token , Vec , String};
+use crate::{FactoryContract, FactoryContractClient, RaumFiFactoryError, Error, DEFAULT_FEE_TIER, DEFAULT_MIN_INITIAL_LIQUIDITY, DEFAULT_PROTOCOL_FEE_SHARE};
+use soroban_sdk::{testutils::{Address as TestAddress, Ledger}, Address, BytesN, Env, symbol_short, IntoVal, Symbol , token , Vec , String};
 use token::Client as TokenClient;
 use token::StellarAssetClient as TokenAdminClient;
 
@@ -9,9 +9,50 @@ fn pair_token_wasm(e: &Env) -> BytesN<32> {
     soroban_sdk::contractimport!(
         file = "D:/RaumFiV2/RaumFiV2/target/wasm32-unknown-unknown/release/pair.wasm"
     );
+    // Tests register the pair's wasm several times over, which outgrows the default budget
+    e.budget().reset_unlimited();
     e.deployer().upload_contract_wasm(WASM)
 }
 
+fn stable_pair_wasm(e: &Env) -> BytesN<32> {
+    soroban_sdk::contractimport!(
+        file = "D:/RaumFiV2/RaumFiV2/target/wasm32-unknown-unknown/release/stable_pair.wasm"
+    );
+    e.deployer().upload_contract_wasm(WASM)
+}
+
+pub mod stable_pair {
+    soroban_sdk::contractimport!(file = "D:/RaumFiV2/RaumFiV2/target/wasm32-unknown-unknown/release/stable_pair.wasm");
+    pub type StablePairClient<'a> = Client<'a>;
+}
+use stable_pair::StablePairClient;
+
+fn weighted_pool_wasm(e: &Env) -> BytesN<32> {
+    soroban_sdk::contractimport!(
+        file = "D:/RaumFiV2/RaumFiV2/target/wasm32-unknown-unknown/release/weighted_pool.wasm"
+    );
+    e.deployer().upload_contract_wasm(WASM)
+}
+
+pub mod weighted_pool {
+    soroban_sdk::contractimport!(file = "D:/RaumFiV2/RaumFiV2/target/wasm32-unknown-unknown/release/weighted_pool.wasm");
+    pub type WeightedPoolClient<'a> = Client<'a>;
+}
+use weighted_pool::WeightedPoolClient;
+
+fn concentrated_pool_wasm(e: &Env) -> BytesN<32> {
+    soroban_sdk::contractimport!(
+        file = "D:/RaumFiV2/RaumFiV2/target/wasm32-unknown-unknown/release/concentrated_pool.wasm"
+    );
+    e.deployer().upload_contract_wasm(WASM)
+}
+
+pub mod concentrated_pool {
+    soroban_sdk::contractimport!(file = "D:/RaumFiV2/RaumFiV2/target/wasm32-unknown-unknown/release/concentrated_pool.wasm");
+    pub type ConcentratedPoolClient<'a> = Client<'a>;
+}
+use concentrated_pool::ConcentratedPoolClient;
+
 pub mod pair {
     soroban_sdk::contractimport!(file = "D:/RaumFiV2/RaumFiV2/target/wasm32-unknown-unknown/release/pair.wasm");
     pub type PairClient<'a> = Client<'a>;
@@ -52,6 +93,36 @@ fn test_initialize() {
     assert_eq!(stored_pairs, 0);
 }
 
+#[test]
+fn test_initialize_storage() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let fee_to_setter = Address::generate(&env);
+
+    let contract_id = env.register_contract(None, FactoryContract{});
+    let client = FactoryContractClient::new(&env, &contract_id);
+    let pair_wasm = pair_token_wasm(&env);
+    client.initialize(&fee_to_setter, &pair_wasm);
+
+    // The setter and the pair wasm hash are kept in persistent storage
+    let stored_fee_setter: Address = env.as_contract(&contract_id, || {
+        env.storage().persistent().get(&symbol_short!("feesetter"))
+        .unwrap()
+    });
+    assert_eq!(stored_fee_setter, fee_to_setter);
+
+    let stored_pair_hash: BytesN<32> = env.as_contract(&contract_id, || {
+        env.storage().persistent().get(&Symbol::short("pair_hash"))
+        .unwrap()
+    });
+    assert_eq!(stored_pair_hash, pair_wasm);
+
+    // Protocol fees start switched on, as they were before the switch existed
+    assert_eq!(client.fees_enabled(), true);
+    assert_eq!(client.get_protocol_fee_share(), DEFAULT_PROTOCOL_FEE_SHARE);
+}
+
 #[test]
 fn test_create_pair() {
     let env = Env::default();
@@ -91,7 +162,6 @@ fn test_create_pair() {
 }
 
 #[test]
-#[should_panic(expected = "1")]
 fn test_create_pair_identical_addresses() {
     let env = Env::default();
 
@@ -107,8 +177,9 @@ fn test_create_pair_identical_addresses() {
 
     client.initialize(&fee_to_setter, &pair_wasm);
 
-    // This should panic with IdenticalAddresses error
-    RaumFiV2Factory::create_pair(&env, &token_0.address, &token_0.address);
+    // This should fail with IdenticalAddresses error
+    let result = client.try_create_pair_with_fee(&token_0.address, &token_0.address, &DEFAULT_FEE_TIER);
+    assert_eq!(result, Err(Ok(RaumFiFactoryError::CreatePairIdenticalTokens)));
 }
 
 #[test]
@@ -117,6 +188,7 @@ fn test_create_pair_exists() {
     let env = Env::default();
 
     env.mock_all_auths();
+    env.budget().reset_unlimited();
 
     let fee_to_setter = Address::generate(&env);
 
@@ -133,6 +205,192 @@ fn test_create_pair_exists() {
     client.create_new_pair( &token_0.address, &token_1.address);
 }
 
+#[test]
+fn test_create_pair_with_fee() {
+    let env = Env::default();
+
+    env.mock_all_auths();
+    env.budget().reset_unlimited();
+
+    let fee_to_setter = Address::generate(&env);
+
+    let contract_id = env.register_contract(None, FactoryContract{});
+    let client = FactoryContractClient::new(&env, &contract_id);
+    let pair_wasm = pair_token_wasm(&env);  
+    let token_0 = TokenClient::new(&env, &env.register_contract_wasm(None,WASM));
+    let token_1 = TokenClient::new(&env, &env.register_contract_wasm(None,WASM));
+    let token_2 = TokenClient::new(&env, &env.register_contract_wasm(None,WASM));
+
+    client.initialize(&fee_to_setter, &pair_wasm);
+
+    let stable_pair = client.create_pair_with_fee(&token_0.address, &token_1.address, &5);
+    assert_eq!(PairClient::new(&env, &stable_pair).get_fee(), 5);
+
+    let default_pair = client.create_new_pair(&token_0.address, &token_2.address);
+    assert_eq!(PairClient::new(&env, &default_pair).get_fee(), DEFAULT_FEE_TIER);
+}
+
+#[test]
+fn test_create_pair_with_fee_unauthorized() {
+    let env = Env::default();
+    env.budget().reset_unlimited();
+
+    let fee_to_setter = Address::generate(&env);
+    let contract_id = env.register_contract(None, FactoryContract{});
+    let client = FactoryContractClient::new(&env, &contract_id);
+    let pair_wasm = pair_token_wasm(&env);
+    let token_0 = TokenClient::new(&env, &env.register_contract_wasm(None,WASM));
+    let token_1 = TokenClient::new(&env, &env.register_contract_wasm(None,WASM));
+    client.initialize(&fee_to_setter, &pair_wasm);
+
+    // Only the fee setter picks another tier than the default; anyone may create a default pair
+    assert!(client.try_create_pair_with_fee(&token_0.address, &token_1.address, &100).is_err());
+    assert_eq!(client.get_pair(&token_0.address, &token_1.address), None);
+    let pair = client.create_pair_with_fee(&token_0.address, &token_1.address, &DEFAULT_FEE_TIER);
+    assert_eq!(PairClient::new(&env, &pair).get_fee(), DEFAULT_FEE_TIER);
+}
+
+#[test]
+fn test_create_pair_with_fees() {
+    let env = Env::default();
+    env.mock_all_auths();
+    env.budget().reset_unlimited();
+
+    let fee_to_setter = Address::generate(&env);
+    let contract_id = env.register_contract(None, FactoryContract{});
+    let client = FactoryContractClient::new(&env, &contract_id);
+    let pair_wasm = pair_token_wasm(&env);
+    let token_0 = TokenClient::new(&env, &env.register_contract_wasm(None,WASM));
+    let token_1 = TokenClient::new(&env, &env.register_contract_wasm(None,WASM));
+    client.initialize(&fee_to_setter, &pair_wasm);
+
+    let (token_a, token_b) = if token_0.address < token_1.address {
+        (token_0.address.clone(), token_1.address.clone())
+    } else {
+        (token_1.address.clone(), token_0.address.clone())
+    };
+    // 0.3% on buys of token_a and 1% on sells
+    let pair = PairClient::new(&env, &client.create_pair_with_fees(&token_a, &token_b, &100, &30));
+    assert_eq!(pair.get_fee_in(&token_a), 100);
+    assert_eq!(pair.get_fee_in(&token_b), 30);
+
+    // Fees follow the order the tokens are given in
+    client.set_pair_directional_fees(&token_b, &token_a, &5, &1);
+    assert_eq!(pair.get_fee_in(&token_b), 5);
+    assert_eq!(pair.get_fee_in(&token_a), 1);
+
+    client.disable_pair_directional_fees(&token_a, &token_b);
+    assert_eq!(pair.get_fee_in(&token_a), DEFAULT_FEE_TIER);
+    assert_eq!(pair.get_fee_in(&token_b), DEFAULT_FEE_TIER);
+}
+
+#[test]
+fn test_create_pair_with_fees_unauthorized() {
+    let env = Env::default();
+    env.budget().reset_unlimited();
+
+    let fee_to_setter = Address::generate(&env);
+    let contract_id = env.register_contract(None, FactoryContract{});
+    let client = FactoryContractClient::new(&env, &contract_id);
+    let pair_wasm = pair_token_wasm(&env);
+    let token_0 = TokenClient::new(&env, &env.register_contract_wasm(None,WASM));
+    let token_1 = TokenClient::new(&env, &env.register_contract_wasm(None,WASM));
+    client.initialize(&fee_to_setter, &pair_wasm);
+
+    assert!(client.try_create_pair_with_fees(&token_0.address, &token_1.address, &100, &30).is_err());
+    assert_eq!(client.get_pair(&token_0.address, &token_1.address), None);
+}
+
+#[test]
+#[should_panic(expected = "12")]
+fn test_create_pair_with_fees_too_high() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let fee_to_setter = Address::generate(&env);
+    let contract_id = env.register_contract(None, FactoryContract{});
+    let client = FactoryContractClient::new(&env, &contract_id);
+    let pair_wasm = pair_token_wasm(&env);
+    client.initialize(&fee_to_setter, &pair_wasm);
+
+    client.create_pair_with_fees(&Address::generate(&env), &Address::generate(&env), &30, &101);
+}
+
+#[test]
+fn test_create_pair_with_creator_fee() {
+    let env = Env::default();
+    env.mock_all_auths();
+    env.budget().reset_unlimited();
+
+    let fee_to_setter = Address::generate(&env);
+    let contract_id = env.register_contract(None, FactoryContract{});
+    let client = FactoryContractClient::new(&env, &contract_id);
+    let pair_wasm = pair_token_wasm(&env);
+    let token_0 = TokenClient::new(&env, &env.register_contract_wasm(None,WASM));
+    let token_1 = TokenClient::new(&env, &env.register_contract_wasm(None,WASM));
+    client.initialize(&fee_to_setter, &pair_wasm);
+
+    let creator = Address::generate(&env);
+    let pair = PairClient::new(&env, &client.create_pair_with_creator_fee(&token_0.address, &token_1.address, &100, &creator, &2000));
+    assert_eq!(pair.get_fee(), 100);
+    assert_eq!(pair.get_creator_fee(), Some(pair::CreatorFee { recipient: creator, share: 2000 }));
+}
+
+#[test]
+fn test_create_pair_with_creator_fee_unauthorized() {
+    let env = Env::default();
+    env.budget().reset_unlimited();
+
+    let fee_to_setter = Address::generate(&env);
+    let contract_id = env.register_contract(None, FactoryContract{});
+    let client = FactoryContractClient::new(&env, &contract_id);
+    let pair_wasm = pair_token_wasm(&env);
+    let token_0 = TokenClient::new(&env, &env.register_contract_wasm(None,WASM));
+    let token_1 = TokenClient::new(&env, &env.register_contract_wasm(None,WASM));
+    client.initialize(&fee_to_setter, &pair_wasm);
+
+    // Anyone else naming themselves creator is turned away, and the pair is not created
+    let creator = Address::generate(&env);
+    assert!(client.try_create_pair_with_creator_fee(&token_0.address, &token_1.address, &30, &creator, &5000).is_err());
+    assert_eq!(client.get_pair(&token_0.address, &token_1.address), None);
+}
+
+#[test]
+#[should_panic(expected = "13")]
+fn test_create_pair_with_creator_fee_too_high() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let fee_to_setter = Address::generate(&env);
+    let contract_id = env.register_contract(None, FactoryContract{});
+    let client = FactoryContractClient::new(&env, &contract_id);
+    let pair_wasm = pair_token_wasm(&env);
+    client.initialize(&fee_to_setter, &pair_wasm);
+
+    client.create_pair_with_creator_fee(&Address::generate(&env), &Address::generate(&env), &30, &fee_to_setter, &5001);
+}
+
+#[test]
+#[should_panic(expected = "8")]
+fn test_create_pair_invalid_fee_tier() {
+    let env = Env::default();
+
+    env.mock_all_auths();
+
+    let fee_to_setter = Address::generate(&env);
+
+    let contract_id = env.register_contract(None, FactoryContract{});
+    let client = FactoryContractClient::new(&env, &contract_id);
+    let pair_wasm = pair_token_wasm(&env);  
+    let token_0 = TokenClient::new(&env, &env.register_contract_wasm(None,WASM));
+    let token_1 = TokenClient::new(&env, &env.register_contract_wasm(None,WASM));
+
+    client.initialize(&fee_to_setter, &pair_wasm);
+
+    // 0.25% is not one of the supported fee tiers
+    client.create_pair_with_fee(&token_0.address, &token_1.address, &25);
+}
+
 #[test]
 fn test_set_fee_to() {
     let env = Env::default();
@@ -180,3 +438,434 @@ fn test_set_fee_to_unauthorized() {
     // Unauthorized address tries to set fee recipient, should panic with Forbidden error
     client.set_fee_to(&Address::generate(&env));
 }
+
+#[test]
+fn test_set_fees_enabled() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let fee_to_setter = Address::generate(&env);
+    let contract_id = env.register_contract(None, FactoryContract{});
+    let client = FactoryContractClient::new(&env, &contract_id);
+    let pair_wasm = pair_token_wasm(&env);
+    client.initialize(&fee_to_setter, &pair_wasm);
+
+    client.set_fees_enabled(&true);
+    assert_eq!(client.fees_enabled(), true);
+
+    client.set_fees_enabled(&false);
+    assert_eq!(client.fees_enabled(), false);
+}
+
+#[test]
+#[should_panic]
+fn test_set_fees_enabled_unauthorized() {
+    let env = Env::default();
+
+    let fee_to_setter = Address::generate(&env);
+    let contract_id = env.register_contract(None, FactoryContract{});
+    let client = FactoryContractClient::new(&env, &contract_id);
+    let pair_wasm = pair_token_wasm(&env);
+    client.initialize(&fee_to_setter, &pair_wasm);
+
+    client.set_fees_enabled(&true);
+}
+
+#[test]
+fn test_set_protocol_fee_share() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let fee_to_setter = Address::generate(&env);
+    let contract_id = env.register_contract(None, FactoryContract{});
+    let client = FactoryContractClient::new(&env, &contract_id);
+    let pair_wasm = pair_token_wasm(&env);
+    client.initialize(&fee_to_setter, &pair_wasm);
+
+    client.set_protocol_fee_share(&2500);
+    assert_eq!(client.get_protocol_fee_share(), 2500);
+}
+
+#[test]
+#[should_panic(expected = "9")]
+fn test_set_protocol_fee_share_too_high() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let fee_to_setter = Address::generate(&env);
+    let contract_id = env.register_contract(None, FactoryContract{});
+    let client = FactoryContractClient::new(&env, &contract_id);
+    let pair_wasm = pair_token_wasm(&env);
+    client.initialize(&fee_to_setter, &pair_wasm);
+
+    client.set_protocol_fee_share(&5001);
+}
+
+#[test]
+fn test_set_min_initial_liquidity() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let fee_to_setter = Address::generate(&env);
+    let contract_id = env.register_contract(None, FactoryContract{});
+    let client = FactoryContractClient::new(&env, &contract_id);
+    let pair_wasm = pair_token_wasm(&env);
+    client.initialize(&fee_to_setter, &pair_wasm);
+
+    assert_eq!(client.get_min_initial_liquidity(), DEFAULT_MIN_INITIAL_LIQUIDITY);
+    client.set_min_initial_liquidity(&1_000_000);
+    assert_eq!(client.get_min_initial_liquidity(), 1_000_000);
+}
+
+#[test]
+#[should_panic(expected = "14")]
+fn test_set_min_initial_liquidity_negative() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let fee_to_setter = Address::generate(&env);
+    let contract_id = env.register_contract(None, FactoryContract{});
+    let client = FactoryContractClient::new(&env, &contract_id);
+    let pair_wasm = pair_token_wasm(&env);
+    client.initialize(&fee_to_setter, &pair_wasm);
+
+    client.set_min_initial_liquidity(&-1);
+}
+
+#[test]
+fn test_pause_pair() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let fee_to_setter = Address::generate(&env);
+    let contract_id = env.register_contract(None, FactoryContract{});
+    let client = FactoryContractClient::new(&env, &contract_id);
+    let pair_wasm = pair_token_wasm(&env);
+    let token_0 = TokenClient::new(&env, &env.register_contract_wasm(None,WASM));
+    let token_1 = TokenClient::new(&env, &env.register_contract_wasm(None,WASM));
+    soroban_sdk::contractimport!(file = "../token/target/wasm32-unknown-unknown/release/rntoken.wasm");
+    pub type TokenClient<'a> = Client<'a>;
+    token_0.initialize(&fee_to_setter, &7, &String::from_str(&env, "Token 0"), &String::from_str(&env, "TOKEN0"));
+    token_1.initialize(&fee_to_setter, &7, &String::from_str(&env, "Token 1"), &String::from_str(&env, "TOKEN1"));
+    client.initialize(&fee_to_setter, &pair_wasm);
+
+    let (token_a, token_b) = if token_0.address < token_1.address {
+        (token_0.address.clone(), token_1.address.clone())
+    } else {
+        (token_1.address.clone(), token_0.address.clone())
+    };
+    let pair = PairClient::new(&env, &client.create_new_pair(&token_a, &token_b));
+    assert_eq!(pair.is_paused(), false);
+
+    client.pause_pair(&token_a, &token_b);
+    assert_eq!(pair.is_paused(), true);
+
+    client.unpause_pair(&token_a, &token_b);
+    assert_eq!(pair.is_paused(), false);
+}
+
+#[test]
+fn test_pause_pair_created_in_reverse_order() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let fee_to_setter = Address::generate(&env);
+    let contract_id = env.register_contract(None, FactoryContract{});
+    let client = FactoryContractClient::new(&env, &contract_id);
+    let pair_wasm = pair_token_wasm(&env);
+    let token_0 = TokenClient::new(&env, &env.register_contract_wasm(None,WASM));
+    let token_1 = TokenClient::new(&env, &env.register_contract_wasm(None,WASM));
+    client.initialize(&fee_to_setter, &pair_wasm);
+
+    let (token_a, token_b) = if token_0.address < token_1.address {
+        (token_0.address.clone(), token_1.address.clone())
+    } else {
+        (token_1.address.clone(), token_0.address.clone())
+    };
+    // Created with the higher address first, the pair is still found in either order
+    let pair = PairClient::new(&env, &client.create_new_pair(&token_b, &token_a));
+    assert_eq!(client.get_pair(&token_a, &token_b), Some(pair.address.clone()));
+    assert_eq!(client.get_pair(&token_b, &token_a), Some(pair.address.clone()));
+    assert_eq!(client.try_create_new_pair(&token_a, &token_b), Err(Ok(RaumFiFactoryError::PairAlreadyExists)));
+
+    client.pause_pair(&token_b, &token_a);
+    assert_eq!(pair.is_paused(), true);
+}
+
+#[test]
+fn test_set_pair_lp_hold() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let fee_to_setter = Address::generate(&env);
+    let contract_id = env.register_contract(None, FactoryContract{});
+    let client = FactoryContractClient::new(&env, &contract_id);
+    let pair_wasm = pair_token_wasm(&env);
+    let token_0 = TokenClient::new(&env, &env.register_contract_wasm(None,WASM));
+    let token_1 = TokenClient::new(&env, &env.register_contract_wasm(None,WASM));
+    client.initialize(&fee_to_setter, &pair_wasm);
+
+    let (token_a, token_b) = if token_0.address < token_1.address {
+        (token_0.address.clone(), token_1.address.clone())
+    } else {
+        (token_1.address.clone(), token_0.address.clone())
+    };
+    let pair = PairClient::new(&env, &client.create_new_pair(&token_a, &token_b));
+
+    client.set_pair_lp_hold(&token_a, &token_b, &20, &100);
+    assert_eq!(pair.get_lp_hold(), Some(pair::LpHoldConfig { min_ledgers: 20, exit_fee: 100 }));
+
+    client.disable_pair_lp_hold(&token_b, &token_a);
+    assert_eq!(pair.get_lp_hold(), None);
+}
+
+#[test]
+fn test_set_pair_circuit_breaker() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let fee_to_setter = Address::generate(&env);
+    let contract_id = env.register_contract(None, FactoryContract{});
+    let client = FactoryContractClient::new(&env, &contract_id);
+    let pair_wasm = pair_token_wasm(&env);
+    let token_0 = TokenClient::new(&env, &env.register_contract_wasm(None,WASM));
+    let token_1 = TokenClient::new(&env, &env.register_contract_wasm(None,WASM));
+    client.initialize(&fee_to_setter, &pair_wasm);
+
+    let (token_a, token_b) = if token_0.address < token_1.address {
+        (token_0.address.clone(), token_1.address.clone())
+    } else {
+        (token_1.address.clone(), token_0.address.clone())
+    };
+    let pair = PairClient::new(&env, &client.create_new_pair(&token_a, &token_b));
+
+    client.set_pair_circuit_breaker(&token_a, &token_b, &500, &1_000, &3_600);
+    assert_eq!(pair.get_circuit_breaker(), Some(pair::CircuitBreakerConfig { max_ledger_move: 500, max_window_move: 1_000, window: 3_600 }));
+
+    client.disable_pair_circuit_breaker(&token_b, &token_a);
+    assert_eq!(pair.get_circuit_breaker(), None);
+}
+
+#[test]
+fn test_upgrade_pair() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let fee_to_setter = Address::generate(&env);
+    let contract_id = env.register_contract(None, FactoryContract{});
+    let client = FactoryContractClient::new(&env, &contract_id);
+    let pair_wasm = pair_token_wasm(&env);
+    let token_0 = TokenClient::new(&env, &env.register_contract_wasm(None,WASM));
+    let token_1 = TokenClient::new(&env, &env.register_contract_wasm(None,WASM));
+    client.initialize(&fee_to_setter, &pair_wasm);
+
+    let (token_a, token_b) = if token_0.address < token_1.address {
+        (token_0.address.clone(), token_1.address.clone())
+    } else {
+        (token_1.address.clone(), token_0.address.clone())
+    };
+    let pair = PairClient::new(&env, &client.create_new_pair(&token_a, &token_b));
+    client.set_pair_lp_hold(&token_a, &token_b, &20, &100);
+
+    client.upgrade_pair(&token_b, &token_a, &pair_wasm);
+    assert_eq!(env.auths()[0].0, fee_to_setter);
+    // The upgraded pair keeps its storage
+    assert_eq!(pair.get_lp_hold(), Some(pair::LpHoldConfig { min_ledgers: 20, exit_fee: 100 }));
+}
+
+#[test]
+#[should_panic(expected = "5")]
+fn test_pause_missing_pair() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let fee_to_setter = Address::generate(&env);
+    let contract_id = env.register_contract(None, FactoryContract{});
+    let client = FactoryContractClient::new(&env, &contract_id);
+    let pair_wasm = pair_token_wasm(&env);
+    client.initialize(&fee_to_setter, &pair_wasm);
+
+    client.pause_pair(&Address::generate(&env), &Address::generate(&env));
+}
+
+#[test]
+#[should_panic(expected = "10")]
+fn test_set_pair_dynamic_fee_invalid_bounds() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let fee_to_setter = Address::generate(&env);
+    let contract_id = env.register_contract(None, FactoryContract{});
+    let client = FactoryContractClient::new(&env, &contract_id);
+    let pair_wasm = pair_token_wasm(&env);
+    client.initialize(&fee_to_setter, &pair_wasm);
+
+    client.set_pair_dynamic_fee(&Address::generate(&env), &Address::generate(&env), &30, &500);
+}
+
+#[test]
+fn test_create_stable_pair() {
+    let env = Env::default();
+    env.mock_all_auths();
+    env.ledger().set_timestamp(1_000);
+
+    let fee_to_setter = Address::generate(&env);
+    let contract_id = env.register_contract(None, FactoryContract{});
+    let client = FactoryContractClient::new(&env, &contract_id);
+    let pair_wasm = pair_token_wasm(&env);
+    let token_0 = TokenClient::new(&env, &env.register_contract_wasm(None,WASM));
+    let token_1 = TokenClient::new(&env, &env.register_contract_wasm(None,WASM));
+    soroban_sdk::contractimport!(file = "../token/target/wasm32-unknown-unknown/release/rntoken.wasm");
+    pub type TokenClient<'a> = Client<'a>;
+    token_0.initialize(&fee_to_setter, &7, &String::from_str(&env, "Token 0"), &String::from_str(&env, "TOKEN0"));
+    token_1.initialize(&fee_to_setter, &7, &String::from_str(&env, "Token 1"), &String::from_str(&env, "TOKEN1"));
+    client.initialize(&fee_to_setter, &pair_wasm);
+    client.set_stable_pair_wasm_hash(&stable_pair_wasm(&env));
+
+    let stable = client.create_stable_pair(&token_0.address, &token_1.address, &100, &5);
+    assert_eq!(client.get_stable_pair(&token_1.address, &token_0.address), Some(stable.clone()));
+    // The constant-product pair of the same tokens is independent
+    assert_eq!(client.get_pair(&token_0.address, &token_1.address), None);
+
+    let stable_client = StablePairClient::new(&env, &stable);
+    assert_eq!(stable_client.get_amp(), 100);
+    assert_eq!(stable_client.get_fee(), 5);
+
+    env.ledger().set_timestamp(1_000 + 86_400);
+    client.ramp_stable_pair_a(&token_0.address, &token_1.address, &300, &(1_000 + 3 * 86_400));
+    env.ledger().set_timestamp(1_000 + 2 * 86_400);
+    assert_eq!(stable_client.get_amp(), 200);
+    client.stop_stable_pair_ramp(&token_0.address, &token_1.address);
+    env.ledger().set_timestamp(1_000 + 3 * 86_400);
+    assert_eq!(stable_client.get_amp(), 200);
+}
+
+#[test]
+#[should_panic(expected = "1")]
+fn test_create_stable_pair_without_wasm_hash() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let fee_to_setter = Address::generate(&env);
+    let contract_id = env.register_contract(None, FactoryContract{});
+    let client = FactoryContractClient::new(&env, &contract_id);
+    let pair_wasm = pair_token_wasm(&env);
+    client.initialize(&fee_to_setter, &pair_wasm);
+
+    client.create_stable_pair(&Address::generate(&env), &Address::generate(&env), &100, &5);
+}
+
+#[test]
+fn test_create_weighted_pool() {
+    let env = Env::default();
+    env.mock_all_auths();
+    env.budget().reset_unlimited();
+
+    let fee_to_setter = Address::generate(&env);
+    let contract_id = env.register_contract(None, FactoryContract{});
+    let client = FactoryContractClient::new(&env, &contract_id);
+    client.initialize(&fee_to_setter, &pair_token_wasm(&env));
+    client.set_weighted_pool_wasm_hash(&weighted_pool_wasm(&env));
+
+    let (token_a, token_b, token_c) = (Address::generate(&env), Address::generate(&env), Address::generate(&env));
+    let tokens = soroban_sdk::vec![&env, token_a.clone(), token_b.clone(), token_c.clone()];
+    let weights = soroban_sdk::vec![&env, 6000u32, 3000, 1000];
+    let pool = client.create_weighted_pool(&tokens, &weights, &30);
+    assert_eq!(client.get_weighted_pools(), soroban_sdk::vec![&env, pool.clone()]);
+
+    // The pool holds its tokens sorted by address, each keeping its weight
+    let pool_client = WeightedPoolClient::new(&env, &pool);
+    let pool_tokens = pool_client.get_tokens();
+    let pool_weights = pool_client.get_weights();
+    for (token, weight) in tokens.iter().zip(weights.iter()) {
+        let index = pool_tokens.first_index_of(&token).unwrap();
+        assert_eq!(pool_weights.get(index).unwrap(), weight);
+    }
+    assert_eq!(pool_client.get_fee(), 30);
+
+    // The same tokens with other weights make another pool
+    let weights = soroban_sdk::vec![&env, 4000u32, 3000, 3000];
+    client.create_weighted_pool(&tokens, &weights, &30);
+    assert_eq!(client.get_weighted_pools().len(), 2);
+}
+
+#[test]
+#[should_panic(expected = "Error(Contract, #7)")]
+fn test_create_weighted_pool_exists() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let fee_to_setter = Address::generate(&env);
+    let contract_id = env.register_contract(None, FactoryContract{});
+    let client = FactoryContractClient::new(&env, &contract_id);
+    client.initialize(&fee_to_setter, &pair_token_wasm(&env));
+    client.set_weighted_pool_wasm_hash(&weighted_pool_wasm(&env));
+
+    let (token_a, token_b) = (Address::generate(&env), Address::generate(&env));
+    client.create_weighted_pool(&soroban_sdk::vec![&env, token_a.clone(), token_b.clone()], &soroban_sdk::vec![&env, 8000, 2000], &30);
+    // Listing the tokens in another order describes the same pool
+    client.create_weighted_pool(&soroban_sdk::vec![&env, token_b, token_a], &soroban_sdk::vec![&env, 2000, 8000], &30);
+}
+
+#[test]
+fn test_create_concentrated_pool() {
+    let env = Env::default();
+    env.mock_all_auths();
+    env.budget().reset_unlimited();
+
+    let fee_to_setter = Address::generate(&env);
+    let contract_id = env.register_contract(None, FactoryContract{});
+    let client = FactoryContractClient::new(&env, &contract_id);
+    client.initialize(&fee_to_setter, &pair_token_wasm(&env));
+    client.set_concentrated_pool_wasm_hash(&concentrated_pool_wasm(&env));
+
+    let (token_a, token_b) = (Address::generate(&env), Address::generate(&env));
+    let sqrt_price_1 = soroban_sdk::U256::from_u128(&env, 1 << 96);
+    let pool = client.create_concentrated_pool(&token_a, &token_b, &30, &sqrt_price_1);
+    assert_eq!(client.get_concentrated_pool(&token_b, &token_a, &30), Some(pool.clone()));
+    assert_eq!(client.get_concentrated_pool(&token_a, &token_b, &5), None);
+
+    let pool_client = ConcentratedPoolClient::new(&env, &pool);
+    assert_eq!(pool_client.get_fee(), 30);
+    assert_eq!(pool_client.get_tick_spacing(), 60);
+    assert_eq!(pool_client.get_tick(), 0);
+
+    // Each fee tier gets its own pool, with its own tick spacing
+    let pool = client.create_concentrated_pool(&token_a, &token_b, &5, &sqrt_price_1);
+    assert_eq!(ConcentratedPoolClient::new(&env, &pool).get_tick_spacing(), 10);
+}
+
+#[test]
+#[should_panic(expected = "Error(Contract, #7)")]
+fn test_create_concentrated_pool_exists() {
+    let env = Env::default();
+    env.mock_all_auths();
+    env.budget().reset_unlimited();
+
+    let fee_to_setter = Address::generate(&env);
+    let contract_id = env.register_contract(None, FactoryContract{});
+    let client = FactoryContractClient::new(&env, &contract_id);
+    client.initialize(&fee_to_setter, &pair_token_wasm(&env));
+    client.set_concentrated_pool_wasm_hash(&concentrated_pool_wasm(&env));
+
+    let (token_a, token_b) = (Address::generate(&env), Address::generate(&env));
+    let sqrt_price_1 = soroban_sdk::U256::from_u128(&env, 1 << 96);
+    client.create_concentrated_pool(&token_a, &token_b, &30, &sqrt_price_1);
+    client.create_concentrated_pool(&token_b, &token_a, &30, &sqrt_price_1);
+}
+
+#[test]
+#[should_panic(expected = "Error(Contract, #8)")]
+fn test_create_concentrated_pool_invalid_fee() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let fee_to_setter = Address::generate(&env);
+    let contract_id = env.register_contract(None, FactoryContract{});
+    let client = FactoryContractClient::new(&env, &contract_id);
+    client.initialize(&fee_to_setter, &pair_token_wasm(&env));
+    client.set_concentrated_pool_wasm_hash(&concentrated_pool_wasm(&env));
+
+    let sqrt_price_1 = soroban_sdk::U256::from_u128(&env, 1 << 96);
+    client.create_concentrated_pool(&Address::generate(&env), &Address::generate(&env), &25, &sqrt_price_1);
+}
@@ -0,0 +1,206 @@
+//! Products and quotients of `i128` amounts with a 256-bit intermediate, shared by the
+//! pair and `raumfi_library`.
+//!
+//! Reserves, balances and supplies fit `i128`, but their products do not always: two
+//! 18-decimal tokens with supplies in the tens of billions already overflow it. These
+//! helpers keep the product in 256 bits and only fail when the final result does not
+//! fit `i128`. The crate has no dependencies, so that contracts linking it pay only for
+//! the few 256-bit operations below.
+
+#![no_std]
+
+use core::cmp::Ordering;
+
+#[cfg(test)]
+mod test;
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum MathError {
+    DivisionByZero,
+    /// The result does not fit `i128`
+    Overflow,
+    /// A square root or an unsigned product was asked of a negative amount
+    NegativeAmount,
+}
+
+/// Unsigned 256-bit integer, as its high and low 128 bits
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Ord, PartialOrd)]
+pub struct U256 {
+    hi: u128,
+    lo: u128,
+}
+
+impl U256 {
+    pub const fn from_words(hi: u128, lo: u128) -> Self {
+        U256 { hi, lo }
+    }
+
+    pub const fn hi(&self) -> u128 {
+        self.hi
+    }
+
+    pub const fn lo(&self) -> u128 {
+        self.lo
+    }
+
+    fn leading_zeros(&self) -> u32 {
+        if self.hi != 0 {
+            self.hi.leading_zeros()
+        } else {
+            128 + self.lo.leading_zeros()
+        }
+    }
+
+    /// Quotient and remainder of the division by `divisor`, unless the quotient does not
+    /// fit 128 bits
+    fn div_rem(&self, divisor: u128) -> Option<(u128, u128)> {
+        if divisor == 0 || self.hi >= divisor {
+            return None;
+        }
+        if self.hi == 0 {
+            return Some((self.lo / divisor, self.lo % divisor));
+        }
+        // Long division, one bit of the low word at a time; the remainder stays below
+        // the divisor, so the quotient fits 128 bits
+        let mut remainder = self.hi;
+        let mut quotient = 0u128;
+        for bit in (0..128).rev() {
+            let carry = remainder >> 127;
+            remainder = (remainder << 1) | ((self.lo >> bit) & 1);
+            quotient <<= 1;
+            if carry == 1 || remainder >= divisor {
+                remainder = remainder.wrapping_sub(divisor);
+                quotient |= 1;
+            }
+        }
+        Some((quotient, remainder))
+    }
+
+    /// Quotient of the division by a non-zero `divisor`, however many bits it takes
+    fn div_wide(&self, divisor: u128) -> Option<U256> {
+        let high = self.hi.checked_div(divisor)?;
+        let (low, _) = U256 { hi: self.hi % divisor, lo: self.lo }.div_rem(divisor)?;
+        Some(U256 { hi: high, lo: low })
+    }
+}
+
+/// Exact product of two `u128`
+fn mul_u128(a: u128, b: u128) -> U256 {
+    const MASK: u128 = u64::MAX as u128;
+    let (a1, a0) = (a >> 64, a & MASK);
+    let (b1, b0) = (b >> 64, b & MASK);
+    let low = a0 * b0;
+    let cross0 = a0 * b1;
+    let cross1 = a1 * b0;
+    let middle = (low >> 64) + (cross0 & MASK) + (cross1 & MASK);
+    U256 {
+        hi: a1 * b1 + (cross0 >> 64) + (cross1 >> 64) + (middle >> 64),
+        lo: (low & MASK) | (middle << 64),
+    }
+}
+
+/// Magnitude of `a * b`, and whether the product is negative
+fn signed_mul(a: i128, b: i128) -> (U256, bool) {
+    let product = mul_u128(a.unsigned_abs(), b.unsigned_abs());
+    let negative = (a < 0) != (b < 0) && product != U256::from_words(0, 0);
+    (product, negative)
+}
+
+/// Exact product of two non-negative amounts
+pub fn wide_mul(a: i128, b: i128) -> Result<U256, MathError> {
+    if a < 0 || b < 0 {
+        return Err(MathError::NegativeAmount);
+    }
+    Ok(mul_u128(a as u128, b as u128))
+}
+
+/// `a * b / denominator` rounded towards negative infinity, or towards positive infinity
+/// when `round_up`
+fn mul_div(a: i128, b: i128, denominator: i128, round_up: bool) -> Result<i128, MathError> {
+    if denominator == 0 {
+        return Err(MathError::DivisionByZero);
+    }
+    let (product, negative) = signed_mul(a, b);
+    let negative = negative != (denominator < 0);
+    let (quotient, remainder) = product.div_rem(denominator.unsigned_abs()).ok_or(MathError::Overflow)?;
+    // Truncating the magnitude rounds towards zero; away from zero is the other way
+    let quotient = if remainder != 0 && round_up != negative {
+        quotient.checked_add(1).ok_or(MathError::Overflow)?
+    } else {
+        quotient
+    };
+    if negative {
+        0i128.checked_sub_unsigned(quotient).ok_or(MathError::Overflow)
+    } else {
+        i128::try_from(quotient).map_err(|_| MathError::Overflow)
+    }
+}
+
+/// `floor(a * b / denominator)`
+pub fn mul_div_floor(a: i128, b: i128, denominator: i128) -> Result<i128, MathError> {
+    mul_div(a, b, denominator, false)
+}
+
+/// `ceil(a * b / denominator)`
+pub fn mul_div_ceil(a: i128, b: i128, denominator: i128) -> Result<i128, MathError> {
+    mul_div(a, b, denominator, true)
+}
+
+/// Compares `a * b` with `c * d`
+pub fn compare_products(a: i128, b: i128, c: i128, d: i128) -> Ordering {
+    let (left, left_negative) = signed_mul(a, b);
+    let (right, right_negative) = signed_mul(c, d);
+    match (left_negative, right_negative) {
+        (false, false) => left.cmp(&right),
+        (true, true) => right.cmp(&left),
+        (true, false) => Ordering::Less,
+        (false, true) => Ordering::Greater,
+    }
+}
+
+/// Number of fractional bits of a UQ64x64 price
+pub const PRICE_RESOLUTION: u32 = 64;
+
+/// `numerator / denominator` as a UQ64x64 price, `floor(numerator * 2^64 / denominator)`,
+/// such as the price of a reserve in units of the other. This is the encoding the pair
+/// accumulates its TWAP prices in.
+pub fn encode_price(numerator: i128, denominator: i128) -> Result<U256, MathError> {
+    if numerator < 0 || denominator < 0 {
+        return Err(MathError::NegativeAmount);
+    }
+    let numerator = numerator as u128;
+    let shifted = U256 { hi: numerator >> (128 - PRICE_RESOLUTION), lo: numerator << PRICE_RESOLUTION };
+    shifted.div_wide(denominator as u128).ok_or(MathError::DivisionByZero)
+}
+
+/// `floor(sqrt(value))`
+pub fn sqrt_u256(value: U256) -> u128 {
+    if value.hi == 0 && value.lo < 2 {
+        return value.lo;
+    }
+    // Newton's method from a power of two at or above the root decreases onto it
+    let bits = 256 - value.leading_zeros();
+    let mut root = match bits.div_ceil(2) {
+        128 => u128::MAX,
+        half => 1u128 << half,
+    };
+    loop {
+        // Once the root is reached the quotient may no longer fit 128 bits, and the
+        // next step would not be lower anyway
+        let Some((quotient, _)) = value.div_rem(root) else {
+            return root;
+        };
+        let next = (root >> 1) + (quotient >> 1) + (root & quotient & 1);
+        if next >= root {
+            return root;
+        }
+        root = next;
+    }
+}
+
+/// `floor(sqrt(a * b))` of two non-negative amounts, such as the geometric mean of two
+/// reserves
+pub fn sqrt_product(a: i128, b: i128) -> Result<i128, MathError> {
+    // The root is at most the larger of the two amounts
+    Ok(sqrt_u256(wide_mul(a, b)?) as i128)
+}
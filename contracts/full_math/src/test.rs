@@ -0,0 +1,58 @@
+#![cfg(test)]
+
+use core::cmp::Ordering;
+
+use crate::*;
+
+#[test]
+fn test_wide_mul() {
+    assert_eq!(wide_mul(i128::MAX, i128::MAX), Ok(U256::from_words(u128::MAX >> 2, 1)));
+    assert_eq!(wide_mul(u64::MAX as i128 + 1, u64::MAX as i128 + 1), Ok(U256::from_words(1, 0)));
+    assert_eq!(wide_mul(-1, 1), Err(MathError::NegativeAmount));
+}
+
+#[test]
+fn test_mul_div() {
+    let large: i128 = 1_000_000_000_000_000_000_000_000_000_000;
+    assert_eq!(mul_div_floor(large, large, 3 * large), Ok(333_333_333_333_333_333_333_333_333_333));
+    assert_eq!(mul_div_ceil(large, large, 3 * large), Ok(333_333_333_333_333_333_333_333_333_334));
+    assert_eq!(mul_div_floor(i128::MAX, i128::MAX, i128::MAX), Ok(i128::MAX));
+    assert_eq!(mul_div_floor(i128::MIN, 1, 1), Ok(i128::MIN));
+    assert_eq!(mul_div_floor(7, -1, 2), Ok(-4));
+    assert_eq!(mul_div_ceil(7, 1, -2), Ok(-3));
+    assert_eq!(mul_div_floor(-7, -1, 2), Ok(3));
+    assert_eq!(mul_div_floor(i128::MIN, -1, 1), Err(MathError::Overflow));
+    assert_eq!(mul_div_floor(large, large, 0), Err(MathError::DivisionByZero));
+}
+
+#[test]
+fn test_encode_price() {
+    assert_eq!(encode_price(1, 2), Ok(U256::from_words(0, 1 << 63)));
+    assert_eq!(encode_price(20_000, 10_000), Ok(U256::from_words(0, 2 << 64)));
+    assert_eq!(encode_price(i128::MAX, 1), Ok(U256::from_words(u64::MAX as u128 >> 1, u128::MAX << 64)));
+    assert_eq!(encode_price(0, 5), Ok(U256::from_words(0, 0)));
+    assert_eq!(encode_price(1, 0), Err(MathError::DivisionByZero));
+    assert_eq!(encode_price(1, -2), Err(MathError::NegativeAmount));
+    assert_eq!(encode_price(-1, 2), Err(MathError::NegativeAmount));
+}
+
+#[test]
+fn test_compare_products() {
+    assert_eq!(compare_products(i128::MAX, i128::MAX, i128::MAX - 1, i128::MAX), Ordering::Greater);
+    assert_eq!(compare_products(-2, 3, 1, -6), Ordering::Equal);
+    assert_eq!(compare_products(-2, 3, 0, 5), Ordering::Less);
+    assert_eq!(compare_products(-2, 3, -1, 5), Ordering::Less);
+}
+
+#[test]
+fn test_sqrt() {
+    assert_eq!(sqrt_u256(U256::from_words(0, 0)), 0);
+    assert_eq!(sqrt_u256(U256::from_words(0, 15)), 3);
+    assert_eq!(sqrt_u256(U256::from_words(1, 0)), 1 << 64);
+    assert_eq!(sqrt_u256(U256::from_words(u128::MAX, u128::MAX)), u128::MAX);
+    // (2^128 - 1)^2 = 2^256 - 2^129 + 1
+    assert_eq!(sqrt_u256(U256::from_words(u128::MAX - 1, 1)), u128::MAX);
+    assert_eq!(sqrt_u256(U256::from_words(u128::MAX - 1, 0)), u128::MAX - 1);
+    assert_eq!(sqrt_product(i128::MAX, i128::MAX), Ok(i128::MAX));
+    assert_eq!(sqrt_product(-1, 1), Err(MathError::NegativeAmount));
+}
@@ -22,5 +22,22 @@ pub enum RaumFiLibraryError {
     InsufficientAAmount = 15,
     Overflow = 16,
     DivisionByZero = 17,
+    InvalidWeight = 18,
+    MaxInRatio = 19,
+    MaxOutRatio = 20,
+    /// The tick is outside `MIN_TICK..=MAX_TICK`
+    InvalidTick = 21,
+    /// The sqrt price is outside `MIN_SQRT_PRICE..MAX_SQRT_PRICE`
+    InvalidSqrtPrice = 22,
 }
 
+
+impl From<raumfi_full_math::MathError> for RaumFiLibraryError {
+    fn from(error: raumfi_full_math::MathError) -> Self {
+        match error {
+            raumfi_full_math::MathError::DivisionByZero => RaumFiLibraryError::DivisionByZero,
+            raumfi_full_math::MathError::Overflow => RaumFiLibraryError::Overflow,
+            raumfi_full_math::MathError::NegativeAmount => RaumFiLibraryError::InsufficientAmount,
+        }
+    }
+}
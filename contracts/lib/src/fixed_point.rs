@@ -0,0 +1,126 @@
+//! Fixed-point prices with 64 fractional bits.
+//!
+//! A `UQ64x64` holds `value * 2^64` in a 256-bit integer, the encoding the pair
+//! accumulates its TWAP prices in. Prices scaled by `10^18` and divided as integers
+//! round the price of a lopsided pool down to a handful of significant digits, or to
+//! zero; 64 fractional bits keep about 19 digits whatever the magnitude of the price.
+
+use ethnum::U256;
+
+use crate::error::RaumFiLibraryError;
+use crate::sqrt_price_math::{from_host_u256, mul_div, to_host_u256, Rounding};
+
+/// Number of fractional bits
+pub const RESOLUTION: u32 = raumfi_full_math::PRICE_RESOLUTION;
+/// Largest number of decimals a decimal-adjusted value may have, since `10^38` is the
+/// largest power of ten that fits `u128`
+pub const MAX_DECIMALS: u32 = 38;
+
+const Q64: U256 = U256::from_words(0, 1 << 64);
+
+/// Unsigned fixed-point number with 64 fractional bits
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Ord, PartialOrd)]
+pub struct UQ64x64(U256);
+
+fn pow10(decimals: u32) -> Result<U256, RaumFiLibraryError> {
+    if decimals > MAX_DECIMALS {
+        return Err(RaumFiLibraryError::Overflow);
+    }
+    Ok(U256::new(10u128.pow(decimals)))
+}
+
+fn non_negative(value: i128) -> Result<U256, RaumFiLibraryError> {
+    if value < 0 {
+        return Err(RaumFiLibraryError::InsufficientAmount);
+    }
+    Ok(U256::new(value as u128))
+}
+
+impl UQ64x64 {
+    pub const ZERO: UQ64x64 = UQ64x64(U256::ZERO);
+    pub const ONE: UQ64x64 = UQ64x64(Q64);
+
+    /// Wraps the raw `value * 2^64`
+    pub const fn from_raw(raw: U256) -> Self {
+        UQ64x64(raw)
+    }
+
+    /// The raw `value * 2^64`
+    pub const fn raw(self) -> U256 {
+        self.0
+    }
+
+    pub fn from_host(raw: &soroban_sdk::U256) -> Self {
+        UQ64x64(from_host_u256(raw))
+    }
+
+    pub fn to_host(self, env: &soroban_sdk::Env) -> soroban_sdk::U256 {
+        to_host_u256(env, self.0)
+    }
+
+    /// `numerator / denominator`, rounded down, such as the price of a reserve in units
+    /// of the other
+    pub fn encode(numerator: i128, denominator: i128) -> Result<Self, RaumFiLibraryError> {
+        let price = raumfi_full_math::encode_price(numerator, denominator)?;
+        Ok(UQ64x64(U256::from_words(price.hi(), price.lo())))
+    }
+
+    /// Integer part of the value
+    pub fn decode(self) -> Result<i128, RaumFiLibraryError> {
+        i128::try_from(self.0 >> RESOLUTION).map_err(|_| RaumFiLibraryError::Overflow)
+    }
+
+    /// `amount * self`, rounded down
+    pub fn mul_amount(self, amount: i128) -> Result<i128, RaumFiLibraryError> {
+        let product = mul_div(non_negative(amount)?, self.0, Q64, Rounding::Down)?;
+        i128::try_from(product).map_err(|_| RaumFiLibraryError::Overflow)
+    }
+
+    /// `self * other`, rounded down
+    pub fn checked_mul(self, other: UQ64x64) -> Result<Self, RaumFiLibraryError> {
+        Ok(UQ64x64(mul_div(self.0, other.0, Q64, Rounding::Down)?))
+    }
+
+    /// `self / other`, rounded down
+    pub fn checked_div(self, other: UQ64x64) -> Result<Self, RaumFiLibraryError> {
+        Ok(UQ64x64(mul_div(self.0, Q64, other.0, Rounding::Down)?))
+    }
+
+    /// `1 / self`, rounded down
+    pub fn reciprocal(self) -> Result<Self, RaumFiLibraryError> {
+        UQ64x64::ONE.checked_div(self)
+    }
+
+    /// Encodes `value / 10^decimals`, such as an 18-decimal price from an external feed
+    pub fn from_decimal(value: i128, decimals: u32) -> Result<Self, RaumFiLibraryError> {
+        Ok(UQ64x64(mul_div(non_negative(value)?, Q64, pow10(decimals)?, Rounding::Down)?))
+    }
+
+    /// The value scaled by `10^decimals`, rounded down
+    pub fn to_decimal(self, decimals: u32) -> Result<i128, RaumFiLibraryError> {
+        let scaled = mul_div(self.0, pow10(decimals)?, Q64, Rounding::Down)?;
+        i128::try_from(scaled).map_err(|_| RaumFiLibraryError::Overflow)
+    }
+
+    /// Turns a price in the smallest units of two tokens into a price in whole tokens:
+    /// `self * 10^base_decimals / 10^quote_decimals`, where the price is in units of the
+    /// quote token per unit of the base token
+    pub fn adjust_decimals(self, base_decimals: u32, quote_decimals: u32) -> Result<Self, RaumFiLibraryError> {
+        Ok(UQ64x64(mul_div(self.0, pow10(base_decimals)?, pow10(quote_decimals)?, Rounding::Down)?))
+    }
+}
+
+/// Average price between two readings of a pair's cumulative price, taken
+/// `time_elapsed` seconds apart
+pub fn calculate_twap(
+    price_cumulative_start: UQ64x64,
+    price_cumulative_end: UQ64x64,
+    time_elapsed: u64,
+) -> Result<UQ64x64, RaumFiLibraryError> {
+    if time_elapsed == 0 {
+        return Err(RaumFiLibraryError::DivisionByZero);
+    }
+    // The accumulator only grows, so a difference is always the growth between readings
+    let growth = price_cumulative_end.raw().wrapping_sub(price_cumulative_start.raw());
+    Ok(UQ64x64::from_raw(growth / U256::from(time_elapsed)))
+}
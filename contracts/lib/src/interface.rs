@@ -1,4 +1,4 @@
-use soroban_sdk::{contractclient, contractspecfn, Address, Env, BytesN, Vec, Symbol};
+use soroban_sdk::{contractclient, contractspecfn, Address, Env, Vec};
 
 pub use crate::error::RaumFiLibraryError;
 pub struct Spec;
@@ -8,7 +8,7 @@ pub struct Spec;
 #[contractclient(name = "RaumFiLibraryClient")]
 
 /// Trait defining the interface for a RaumFi Library contract.
-pub trait RaumFiLibraryTrait {
+pub trait RaumFiLibraryInterface {
     /// Sort two token addresses
     fn sort_tokens( token_a: Address, token_b: Address) -> Result<(Address, Address), RaumFiLibraryError>;
     
@@ -19,13 +19,22 @@ pub trait RaumFiLibraryTrait {
     fn get_reserves(env: &Env, factory: Address, token_a: Address, token_b: Address) -> Result<(i128, i128), RaumFiLibraryError>;
     
     /// Quote the amount of output tokens for a given input amount and reserves
-    fn calculate_quote(env: &Env, amount_a: i128, reserve_a: i128, reserve_b: i128) -> Result<i128, RaumFiLibraryError>;
+    fn calculate_quote(amount_a: i128, reserve_a: i128, reserve_b: i128) -> Result<i128, RaumFiLibraryError>;
     
     /// Calculate the output amount for a swap
-    fn calculate_amount_out(env: &Env, amount_in: i128, reserve_in: i128, reserve_out: i128) -> Result<i128, RaumFiLibraryError>;
+    fn calculate_amount_out(amount_in: i128, reserve_in: i128, reserve_out: i128) -> Result<i128, RaumFiLibraryError>;
     
     /// Calculate the input amount for a desired output
-    fn calculate_amount_in(env: &Env, amount_out: i128, reserve_in: i128, reserve_out: i128) -> Result<i128, RaumFiLibraryError>;
+    fn calculate_amount_in(amount_out: i128, reserve_in: i128, reserve_out: i128) -> Result<i128, RaumFiLibraryError>;
+    
+    /// Fetch the swap fee the pair for two tokens charges on `token_a` sent in, in basis points
+    fn get_fee(env: &Env, factory: Address, token_a: Address, token_b: Address) -> Result<u32, RaumFiLibraryError>;
+    
+    /// Calculate the output amount for a swap through a pair charging `fee` basis points
+    fn calculate_amount_out_with_fee(amount_in: i128, reserve_in: i128, reserve_out: i128, fee: u32) -> Result<i128, RaumFiLibraryError>;
+    
+    /// Calculate the input amount for a desired output through a pair charging `fee` basis points
+    fn calculate_amount_in_with_fee(amount_out: i128, reserve_in: i128, reserve_out: i128, fee: u32) -> Result<i128, RaumFiLibraryError>;
     
     /// Calculate amounts out for a given input amount and path
     fn get_amounts_out(env: &Env, factory: Address, amount_in: i128, path: Vec<Address>) -> Result<Vec<i128>, RaumFiLibraryError>;
@@ -38,7 +47,6 @@ pub trait RaumFiLibraryTrait {
     
     /// Calculate optimal liquidity amounts
     fn optimal_liquidity(
-        env: &Env,
         amount_a_desired: i128,
         amount_b_desired: i128,
         amount_a_min: i128,
@@ -49,7 +57,6 @@ pub trait RaumFiLibraryTrait {
     
     /// Calculate price impact of a swap
     fn calculate_price_impact(
-        env: &Env,
         amount_in: i128,
         amount_out: i128,
         reserve_in: i128,
@@ -58,7 +65,6 @@ pub trait RaumFiLibraryTrait {
     
     /// Calculate liquidity to be minted
     fn calculate_liquidity_minted(
-        env: &Env,
         total_supply: i128,
         amount_a: i128,
         amount_b: i128,
@@ -68,7 +74,6 @@ pub trait RaumFiLibraryTrait {
     
     /// Calculate amounts to be returned when burning liquidity
     fn calculate_burn_amounts(
-        env: &Env,
         liquidity: i128,
         total_supply: i128,
         reserve_a: i128,
@@ -77,7 +82,6 @@ pub trait RaumFiLibraryTrait {
     
     /// Check if the constant product is maintained after a swap
     fn is_constant_product_maintained(
-        env: &Env,
         reserve_a: i128,
         reserve_b: i128,
         new_reserve_a: i128,
@@ -2,17 +2,23 @@
 
 use soroban_sdk::{
     contract, contractimpl,
-    Address, Env, Vec, 
+    Address, Env, Vec, U256,
 };
 
 mod library;
 mod error;
 mod interface;
+mod weighted_math;
+mod sqrt_price_math;
+mod fixed_point;
 #[cfg(test)]
 mod test;
 
 pub use library::*;
 pub use interface::*;
+pub use weighted_math::*;
+pub use sqrt_price_math::*;
+pub use fixed_point::*;
 
 pub trait RaumFiLibraryTrait {
     /// Sort two token addresses
@@ -25,13 +31,22 @@ pub trait RaumFiLibraryTrait {
     fn get_reserves(env: &Env, factory: Address, token_a: Address, token_b: Address) -> Result<(i128, i128), RaumFiLibraryError>;
     
     /// Quote the amount of output tokens for a given input amount and reserves
-    fn calculate_quote(env: &Env, amount_a: i128, reserve_a: i128, reserve_b: i128) -> Result<i128, RaumFiLibraryError>;
+    fn calculate_quote(amount_a: i128, reserve_a: i128, reserve_b: i128) -> Result<i128, RaumFiLibraryError>;
     
     /// Calculate the output amount for a swap
-    fn calculate_amount_out(env: &Env, amount_in: i128, reserve_in: i128, reserve_out: i128) -> Result<i128, RaumFiLibraryError>;
+    fn calculate_amount_out(amount_in: i128, reserve_in: i128, reserve_out: i128) -> Result<i128, RaumFiLibraryError>;
     
     /// Calculate the input amount for a desired output
-    fn calculate_amount_in(env: &Env, amount_out: i128, reserve_in: i128, reserve_out: i128) -> Result<i128, RaumFiLibraryError>;
+    fn calculate_amount_in(amount_out: i128, reserve_in: i128, reserve_out: i128) -> Result<i128, RaumFiLibraryError>;
+    
+    /// Fetch the swap fee the pair for two tokens charges on `token_a` sent in, in basis points
+    fn get_fee(env: &Env, factory: Address, token_a: Address, token_b: Address) -> Result<u32, RaumFiLibraryError>;
+    
+    /// Calculate the output amount for a swap through a pair charging `fee` basis points
+    fn calculate_amount_out_with_fee(amount_in: i128, reserve_in: i128, reserve_out: i128, fee: u32) -> Result<i128, RaumFiLibraryError>;
+    
+    /// Calculate the input amount for a desired output through a pair charging `fee` basis points
+    fn calculate_amount_in_with_fee(amount_out: i128, reserve_in: i128, reserve_out: i128, fee: u32) -> Result<i128, RaumFiLibraryError>;
     
     /// Calculate amounts out for a given input amount and path
     fn get_amounts_out(env: &Env, factory: Address, amount_in: i128, path: Vec<Address>) -> Result<Vec<i128>, RaumFiLibraryError>;
@@ -44,7 +59,6 @@ pub trait RaumFiLibraryTrait {
     
     /// Calculate optimal liquidity amounts
     fn optimal_liquidity(
-        env: &Env,
         amount_a_desired: i128,
         amount_b_desired: i128,
         amount_a_min: i128,
@@ -55,7 +69,6 @@ pub trait RaumFiLibraryTrait {
     
     /// Calculate price impact of a swap
     fn calculate_price_impact(
-        env: &Env,
         amount_in: i128,
         amount_out: i128,
         reserve_in: i128,
@@ -64,7 +77,6 @@ pub trait RaumFiLibraryTrait {
     
     /// Calculate liquidity to be minted
     fn calculate_liquidity_minted(
-        env: &Env,
         total_supply: i128,
         amount_a: i128,
         amount_b: i128,
@@ -74,7 +86,6 @@ pub trait RaumFiLibraryTrait {
     
     /// Calculate amounts to be returned when burning liquidity
     fn calculate_burn_amounts(
-        env: &Env,
         liquidity: i128,
         total_supply: i128,
         reserve_a: i128,
@@ -83,19 +94,135 @@ pub trait RaumFiLibraryTrait {
     
     /// Check if the constant product is maintained after a swap
     fn is_constant_product_maintained(
-        env: &Env,
         reserve_a: i128,
         reserve_b: i128,
         new_reserve_a: i128,
         new_reserve_b: i128,
     ) -> bool;
+
+    /// Calculate the invariant of a weighted pool, in token units
+    fn calculate_weighted_invariant(balances: Vec<i128>, weights: Vec<u32>) -> Result<i128, RaumFiLibraryError>;
+
+    /// Calculate the spot price of token in, in units of token out, scaled by 10^18
+    fn calculate_weighted_spot_price(
+        balance_in: i128,
+        weight_in: u32,
+        balance_out: i128,
+        weight_out: u32,
+    ) -> Result<i128, RaumFiLibraryError>;
+
+    /// Calculate the output amount for a weighted pool swap charging `fee` basis points
+    fn calculate_weighted_amount_out(
+        amount_in: i128,
+        balance_in: i128,
+        weight_in: u32,
+        balance_out: i128,
+        weight_out: u32,
+        fee: u32,
+    ) -> Result<i128, RaumFiLibraryError>;
+
+    /// Calculate the input amount for a desired output of a weighted pool swap charging `fee` basis points
+    fn calculate_weighted_amount_in(
+        amount_out: i128,
+        balance_in: i128,
+        weight_in: u32,
+        balance_out: i128,
+        weight_out: u32,
+        fee: u32,
+    ) -> Result<i128, RaumFiLibraryError>;
+
+    /// Calculate liquidity minted for depositing a single token into a weighted pool
+    fn calculate_weighted_single_join(
+        amount_in: i128,
+        balance_in: i128,
+        weight_in: u32,
+        total_supply: i128,
+        fee: u32,
+    ) -> Result<i128, RaumFiLibraryError>;
+
+    /// Calculate the amount of a single token returned when burning weighted pool liquidity
+    fn calculate_weighted_single_exit(
+        liquidity: i128,
+        balance_out: i128,
+        weight_out: u32,
+        total_supply: i128,
+        fee: u32,
+    ) -> Result<i128, RaumFiLibraryError>;
+
+    /// Calculate the Q64.96 sqrt price of a tick
+    fn get_sqrt_price_at_tick(env: &Env, tick: i32) -> Result<U256, RaumFiLibraryError>;
+
+    /// Calculate the greatest tick whose sqrt price is at most `sqrt_price`
+    fn get_tick_at_sqrt_price(sqrt_price: U256) -> Result<i32, RaumFiLibraryError>;
+
+    /// Calculate the sqrt price after swapping `amount_in` of token0 (`zero_for_one`) or of token1 into `liquidity`
+    fn next_sqrt_price_from_input(
+        env: &Env,
+        sqrt_price: U256,
+        liquidity: u128,
+        amount_in: U256,
+        zero_for_one: bool,
+    ) -> Result<U256, RaumFiLibraryError>;
+
+    /// Calculate the sqrt price after taking `amount_out` of token1 (`zero_for_one`) or of token0 out of `liquidity`
+    fn next_sqrt_price_from_output(
+        env: &Env,
+        sqrt_price: U256,
+        liquidity: u128,
+        amount_out: U256,
+        zero_for_one: bool,
+    ) -> Result<U256, RaumFiLibraryError>;
+
+    /// Calculate the amount of token0 that `liquidity` holds between two sqrt prices, rounded up if `round_up`
+    fn get_amount0_delta(
+        env: &Env,
+        sqrt_price_a: U256,
+        sqrt_price_b: U256,
+        liquidity: u128,
+        round_up: bool,
+    ) -> Result<U256, RaumFiLibraryError>;
+
+    /// Calculate the amount of token1 that `liquidity` holds between two sqrt prices, rounded up if `round_up`
+    fn get_amount1_delta(
+        env: &Env,
+        sqrt_price_a: U256,
+        sqrt_price_b: U256,
+        liquidity: u128,
+        round_up: bool,
+    ) -> Result<U256, RaumFiLibraryError>;
+
+    /// Calculate `floor(a * b / denominator)` with a 256-bit intermediate product
+    fn mul_div_floor(a: i128, b: i128, denominator: i128) -> Result<i128, RaumFiLibraryError>;
+
+    /// Calculate `ceil(a * b / denominator)` with a 256-bit intermediate product
+    fn mul_div_ceil(a: i128, b: i128, denominator: i128) -> Result<i128, RaumFiLibraryError>;
+
+    /// Compare `a * b` with `c * d`: -1 if lower, 0 if equal and 1 if greater
+    fn compare_products(a: i128, b: i128, c: i128, d: i128) -> i32;
+
+    /// Calculate `floor(sqrt(a * b))` of two non-negative amounts
+    fn sqrt_product(a: i128, b: i128) -> Result<i128, RaumFiLibraryError>;
+
+    /// Encode `numerator / denominator` as a UQ64x64 price
+    fn encode_price(env: &Env, numerator: i128, denominator: i128) -> Result<U256, RaumFiLibraryError>;
+
+    /// Convert a UQ64x64 price to a value scaled by `10^decimals`
+    fn price_to_decimal(price: U256, decimals: u32) -> Result<i128, RaumFiLibraryError>;
+
+    /// Calculate the UQ64x64 average price between two readings of a pair's cumulative price
+    fn calculate_twap(
+        env: &Env,
+        price_cumulative_start: U256,
+        price_cumulative_end: U256,
+        time_elapsed: u64,
+    ) -> Result<U256, RaumFiLibraryError>;
 }
 
 
 #[contract]
 pub struct RaumFiV2Library;
 
-#[contractimpl]
+#[cfg_attr(feature = "contract", contractimpl)]
 impl RaumFiLibraryTrait for RaumFiV2Library {
 /// Sort two token addresses
 fn sort_tokens( token_a: Address, token_b: Address) -> Result<(Address, Address), RaumFiLibraryError>{
@@ -113,18 +240,33 @@ fn get_reserves(env: &Env, factory: Address, token_a: Address, token_b: Address)
 }
 
 /// Quote the amount of output tokens for a given input amount and reserves
-fn calculate_quote(env: &Env, amount_a: i128, reserve_a: i128, reserve_b: i128) -> Result<i128, RaumFiLibraryError>{
-    calculate_quote(env, amount_a, reserve_a, reserve_b)
+fn calculate_quote(amount_a: i128, reserve_a: i128, reserve_b: i128) -> Result<i128, RaumFiLibraryError>{
+    calculate_quote(amount_a, reserve_a, reserve_b)
 }
 
 /// Calculate the output amount for a swap
-fn calculate_amount_out(env: &Env, amount_in: i128, reserve_in: i128, reserve_out: i128) -> Result<i128, RaumFiLibraryError>{
-    calculate_amount_out(env, amount_in, reserve_in, reserve_out)
+fn calculate_amount_out(amount_in: i128, reserve_in: i128, reserve_out: i128) -> Result<i128, RaumFiLibraryError>{
+    calculate_amount_out(amount_in, reserve_in, reserve_out)
 }
 
 /// Calculate the input amount for a desired output
-fn calculate_amount_in(env: &Env, amount_out: i128, reserve_in: i128, reserve_out: i128) -> Result<i128, RaumFiLibraryError>{
-    calculate_amount_in(env, amount_out, reserve_in, reserve_out)
+fn calculate_amount_in(amount_out: i128, reserve_in: i128, reserve_out: i128) -> Result<i128, RaumFiLibraryError>{
+    calculate_amount_in(amount_out, reserve_in, reserve_out)
+}
+
+/// Fetch the swap fee the pair for two tokens charges on `token_a` sent in, in basis points
+fn get_fee(env: &Env, factory: Address, token_a: Address, token_b: Address) -> Result<u32, RaumFiLibraryError>{
+    get_fee(env, factory, token_a, token_b)
+}
+
+/// Calculate the output amount for a swap through a pair charging `fee` basis points
+fn calculate_amount_out_with_fee(amount_in: i128, reserve_in: i128, reserve_out: i128, fee: u32) -> Result<i128, RaumFiLibraryError>{
+    calculate_amount_out_with_fee(amount_in, reserve_in, reserve_out, fee)
+}
+
+/// Calculate the input amount for a desired output through a pair charging `fee` basis points
+fn calculate_amount_in_with_fee(amount_out: i128, reserve_in: i128, reserve_out: i128, fee: u32) -> Result<i128, RaumFiLibraryError>{
+    calculate_amount_in_with_fee(amount_out, reserve_in, reserve_out, fee)
 }
 
 /// Calculate amounts out for a given input amount and path
@@ -144,7 +286,6 @@ fn calculate_k(reserve_a: i128, reserve_b: i128) -> Result<i128, RaumFiLibraryEr
 
 /// Calculate optimal liquidity amounts
 fn optimal_liquidity(
-    env: &Env,
     amount_a_desired: i128,
     amount_b_desired: i128,
     amount_a_min: i128,
@@ -152,51 +293,209 @@ fn optimal_liquidity(
     reserve_a: i128,
     reserve_b: i128,
 ) -> Result<(i128, i128), RaumFiLibraryError>{
-    optimal_liquidity(env, amount_a_desired, amount_b_desired, amount_a_min, amount_b_min, reserve_a, reserve_b)
+    optimal_liquidity(amount_a_desired, amount_b_desired, amount_a_min, amount_b_min, reserve_a, reserve_b)
 }
 
 /// Calculate price impact of a swap
 fn calculate_price_impact(
-    env: &Env,
     amount_in: i128,
     amount_out: i128,
     reserve_in: i128,
     reserve_out: i128,
 ) -> Result<i128, RaumFiLibraryError>{
-    calculate_price_impact(env, amount_in, amount_out, reserve_in, reserve_out)
+    calculate_price_impact(amount_in, amount_out, reserve_in, reserve_out)
 }
 
 /// Calculate liquidity to be minted
 fn calculate_liquidity_minted(
-    env: &Env,
     total_supply: i128,
     amount_a: i128,
     amount_b: i128,
     reserve_a: i128,
     reserve_b: i128,
 ) -> Result<i128, RaumFiLibraryError>{
-    calculate_liquidity_minted(env, total_supply, amount_a, amount_b, reserve_a, reserve_b)
+    calculate_liquidity_minted(total_supply, amount_a, amount_b, reserve_a, reserve_b)
 }
 
 /// Calculate amounts to be returned when burning liquidity
 fn calculate_burn_amounts(
-    env: &Env,
     liquidity: i128,
     total_supply: i128,
     reserve_a: i128,
     reserve_b: i128,
 ) -> Result<(i128, i128), RaumFiLibraryError>{
-    calculate_burn_amounts(env, liquidity, total_supply, reserve_a, reserve_b)
+    calculate_burn_amounts(liquidity, total_supply, reserve_a, reserve_b)
 }
 
 /// Check if the constant product is maintained after a swap
 fn is_constant_product_maintained(
-    env: &Env,
     reserve_a: i128,
     reserve_b: i128,
     new_reserve_a: i128,
     new_reserve_b: i128,
 ) -> bool{
-    is_constant_product_maintained(env, reserve_a, reserve_b, new_reserve_a, new_reserve_b)
+    is_constant_product_maintained(reserve_a, reserve_b, new_reserve_a, new_reserve_b)
+}
+
+/// Calculate the invariant of a weighted pool, in token units
+fn calculate_weighted_invariant(balances: Vec<i128>, weights: Vec<u32>) -> Result<i128, RaumFiLibraryError>{
+    calculate_weighted_invariant(balances, weights)
+}
+
+/// Calculate the spot price of token in, in units of token out, scaled by 10^18
+fn calculate_weighted_spot_price(
+    balance_in: i128,
+    weight_in: u32,
+    balance_out: i128,
+    weight_out: u32,
+) -> Result<i128, RaumFiLibraryError>{
+    calculate_weighted_spot_price(balance_in, weight_in, balance_out, weight_out)
+}
+
+/// Calculate the output amount for a weighted pool swap charging `fee` basis points
+fn calculate_weighted_amount_out(
+    amount_in: i128,
+    balance_in: i128,
+    weight_in: u32,
+    balance_out: i128,
+    weight_out: u32,
+    fee: u32,
+) -> Result<i128, RaumFiLibraryError>{
+    calculate_weighted_amount_out(amount_in, balance_in, weight_in, balance_out, weight_out, fee)
+}
+
+/// Calculate the input amount for a desired output of a weighted pool swap charging `fee` basis points
+fn calculate_weighted_amount_in(
+    amount_out: i128,
+    balance_in: i128,
+    weight_in: u32,
+    balance_out: i128,
+    weight_out: u32,
+    fee: u32,
+) -> Result<i128, RaumFiLibraryError>{
+    calculate_weighted_amount_in(amount_out, balance_in, weight_in, balance_out, weight_out, fee)
+}
+
+/// Calculate liquidity minted for depositing a single token into a weighted pool
+fn calculate_weighted_single_join(
+    amount_in: i128,
+    balance_in: i128,
+    weight_in: u32,
+    total_supply: i128,
+    fee: u32,
+) -> Result<i128, RaumFiLibraryError>{
+    calculate_weighted_single_join(amount_in, balance_in, weight_in, total_supply, fee)
+}
+
+/// Calculate the amount of a single token returned when burning weighted pool liquidity
+fn calculate_weighted_single_exit(
+    liquidity: i128,
+    balance_out: i128,
+    weight_out: u32,
+    total_supply: i128,
+    fee: u32,
+) -> Result<i128, RaumFiLibraryError>{
+    calculate_weighted_single_exit(liquidity, balance_out, weight_out, total_supply, fee)
+}
+
+/// Calculate the Q64.96 sqrt price of a tick
+fn get_sqrt_price_at_tick(env: &Env, tick: i32) -> Result<U256, RaumFiLibraryError>{
+    Ok(to_host_u256(env, get_sqrt_price_at_tick(tick)?))
+}
+
+/// Calculate the greatest tick whose sqrt price is at most `sqrt_price`
+fn get_tick_at_sqrt_price(sqrt_price: U256) -> Result<i32, RaumFiLibraryError>{
+    get_tick_at_sqrt_price(from_host_u256(&sqrt_price))
+}
+
+/// Calculate the sqrt price after swapping `amount_in` of token0 (`zero_for_one`) or of token1 into `liquidity`
+fn next_sqrt_price_from_input(
+    env: &Env,
+    sqrt_price: U256,
+    liquidity: u128,
+    amount_in: U256,
+    zero_for_one: bool,
+) -> Result<U256, RaumFiLibraryError>{
+    let next = calculate_next_sqrt_price_from_input(from_host_u256(&sqrt_price), liquidity, from_host_u256(&amount_in), zero_for_one)?;
+    Ok(to_host_u256(env, next))
+}
+
+/// Calculate the sqrt price after taking `amount_out` of token1 (`zero_for_one`) or of token0 out of `liquidity`
+fn next_sqrt_price_from_output(
+    env: &Env,
+    sqrt_price: U256,
+    liquidity: u128,
+    amount_out: U256,
+    zero_for_one: bool,
+) -> Result<U256, RaumFiLibraryError>{
+    let next = calculate_next_sqrt_price_from_output(from_host_u256(&sqrt_price), liquidity, from_host_u256(&amount_out), zero_for_one)?;
+    Ok(to_host_u256(env, next))
+}
+
+/// Calculate the amount of token0 that `liquidity` holds between two sqrt prices, rounded up if `round_up`
+fn get_amount0_delta(
+    env: &Env,
+    sqrt_price_a: U256,
+    sqrt_price_b: U256,
+    liquidity: u128,
+    round_up: bool,
+) -> Result<U256, RaumFiLibraryError>{
+    let rounding = if round_up { Rounding::Up } else { Rounding::Down };
+    let amount = get_amount0_delta(from_host_u256(&sqrt_price_a), from_host_u256(&sqrt_price_b), liquidity, rounding)?;
+    Ok(to_host_u256(env, amount))
+}
+
+/// Calculate the amount of token1 that `liquidity` holds between two sqrt prices, rounded up if `round_up`
+fn get_amount1_delta(
+    env: &Env,
+    sqrt_price_a: U256,
+    sqrt_price_b: U256,
+    liquidity: u128,
+    round_up: bool,
+) -> Result<U256, RaumFiLibraryError>{
+    let rounding = if round_up { Rounding::Up } else { Rounding::Down };
+    let amount = get_amount1_delta(from_host_u256(&sqrt_price_a), from_host_u256(&sqrt_price_b), liquidity, rounding)?;
+    Ok(to_host_u256(env, amount))
+}
+
+/// Calculate `floor(a * b / denominator)` with a 256-bit intermediate product
+fn mul_div_floor(a: i128, b: i128, denominator: i128) -> Result<i128, RaumFiLibraryError>{
+    Ok(raumfi_full_math::mul_div_floor(a, b, denominator)?)
+}
+
+/// Calculate `ceil(a * b / denominator)` with a 256-bit intermediate product
+fn mul_div_ceil(a: i128, b: i128, denominator: i128) -> Result<i128, RaumFiLibraryError>{
+    Ok(raumfi_full_math::mul_div_ceil(a, b, denominator)?)
+}
+
+/// Compare `a * b` with `c * d`: -1 if lower, 0 if equal and 1 if greater
+fn compare_products(a: i128, b: i128, c: i128, d: i128) -> i32{
+    raumfi_full_math::compare_products(a, b, c, d) as i32
+}
+
+/// Calculate `floor(sqrt(a * b))` of two non-negative amounts
+fn sqrt_product(a: i128, b: i128) -> Result<i128, RaumFiLibraryError>{
+    Ok(raumfi_full_math::sqrt_product(a, b)?)
+}
+
+/// Encode `numerator / denominator` as a UQ64x64 price
+fn encode_price(env: &Env, numerator: i128, denominator: i128) -> Result<U256, RaumFiLibraryError>{
+    Ok(UQ64x64::encode(numerator, denominator)?.to_host(env))
+}
+
+/// Convert a UQ64x64 price to a value scaled by `10^decimals`
+fn price_to_decimal(price: U256, decimals: u32) -> Result<i128, RaumFiLibraryError>{
+    UQ64x64::from_host(&price).to_decimal(decimals)
+}
+
+/// Calculate the UQ64x64 average price between two readings of a pair's cumulative price
+fn calculate_twap(
+    env: &Env,
+    price_cumulative_start: U256,
+    price_cumulative_end: U256,
+    time_elapsed: u64,
+) -> Result<U256, RaumFiLibraryError>{
+    let twap = calculate_twap(UQ64x64::from_host(&price_cumulative_start), UQ64x64::from_host(&price_cumulative_end), time_elapsed)?;
+    Ok(twap.to_host(env))
 }
 }
@@ -6,11 +6,16 @@
 
 // use num_integer::Roots;
 use soroban_sdk::{
-     contracttype, Address, Bytes,  Env,  Vec, xdr::ToXdr , log
+     contracttype, Address, Bytes,  Env,  Vec, xdr::ToXdr
 };
 
+use core::cmp::Ordering;
+
 use crate::error::RaumFiLibraryError;
-use num_integer::Roots; 
+use crate::fixed_point::UQ64x64;
+use raumfi_full_math::{compare_products, mul_div_ceil, mul_div_floor, sqrt_product};
+use crate::sqrt_price_math::{mul_div, Rounding};
+use ethnum::U256;
 
 
 mod pair {
@@ -20,11 +25,16 @@ mod pair {
 }
 use pair::Client as PairClient;
 
-#[contracttype]
+#[cfg_attr(feature = "contract", contracttype)]
 pub enum DataKey {
     Factory,
 }
 
+/// Swap fees are expressed in basis points of the input amount
+pub const FEE_DENOMINATOR: i128 = 10_000;
+/// Fee tier assumed when quoting without a pair (0.3%)
+pub const DEFAULT_FEE: u32 = 30;
+
 pub trait CheckedCeilingDiv {
     fn checked_ceiling_div(self, divisor: i128) -> Option<i128>;
 }
@@ -81,7 +91,7 @@ impl CheckedCeilingDiv for i128 {
         token_a: Address,
         token_b: Address,
     ) -> Result<(i128, i128), RaumFiLibraryError> {
-        let (token0, token1) = sort_tokens(token_a.clone(), token_b.clone())?;
+        let (token0, _) = sort_tokens(token_a.clone(), token_b.clone())?;
         let pair = pair_for(env, factory.clone(), token_a.clone(), token_b.clone())?;
         let token_pair = PairClient::new(env, &pair);
         let (reserve0, reserve1) = token_pair.get_reserves();
@@ -92,23 +102,32 @@ impl CheckedCeilingDiv for i128 {
         })
     }
 
+    // Fetch the swap fee, in basis points, the pair for two tokens currently charges on
+    // `token_a` sent in. For pairs in dynamic-fee mode this follows their recent volatility,
+    // and pairs with directional fees charge each direction its own.
+    pub fn get_fee(env: &Env, factory: Address, token_a: Address, token_b: Address) -> Result<u32, RaumFiLibraryError> {
+        let pair = pair_for(env, factory, token_a.clone(), token_b)?;
+        Ok(PairClient::new(env, &pair).get_fee_in(&token_a))
+    }
+
     // Calculate equivalent amount of the other asset
-    pub fn calculate_quote(env: &Env, amount_a: i128, reserve_a: i128, reserve_b: i128) -> Result<i128, RaumFiLibraryError> {
+    pub fn calculate_quote(amount_a: i128, reserve_a: i128, reserve_b: i128) -> Result<i128, RaumFiLibraryError> {
         if amount_a == 0 {
             return Err(RaumFiLibraryError::InsufficientAmount);
         }
         if reserve_a == 0 || reserve_b == 0 {
             return Err(RaumFiLibraryError::InsufficientLiquidity);
         }
-        Ok((amount_a as i128)
-            .checked_mul(reserve_b)
-            .ok_or(RaumFiLibraryError::Overflow)?
-            .checked_div(reserve_a)
-            .ok_or(RaumFiLibraryError::DivisionByZero)?)
+        Ok(mul_div_floor(amount_a, reserve_b, reserve_a)?)
     }
 
     // Calculate the maximum output amount of the other asset
-    pub fn calculate_amount_out(env: &Env, amount_in: i128, reserve_in: i128, reserve_out: i128) -> Result<i128, RaumFiLibraryError> {
+    pub fn calculate_amount_out(amount_in: i128, reserve_in: i128, reserve_out: i128) -> Result<i128, RaumFiLibraryError> {
+        calculate_amount_out_with_fee(amount_in, reserve_in, reserve_out, DEFAULT_FEE)
+    }
+
+    // Calculate the maximum output amount of the other asset for a pair charging `fee` basis points
+    pub fn calculate_amount_out_with_fee(amount_in: i128, reserve_in: i128, reserve_out: i128, fee: u32) -> Result<i128, RaumFiLibraryError> {
         if amount_in <= 0 {
             return Err(RaumFiLibraryError::InsufficientAmount);
         }
@@ -116,46 +135,45 @@ impl CheckedCeilingDiv for i128 {
             return Err(RaumFiLibraryError::InsufficientLiquidity);
         }
         
-        // Calculate fee (0.3% of amount_in)
-        let fee = (amount_in * 3).checked_div(1000).ok_or(RaumFiLibraryError::DivisionByZero)?;
-        let fee = if fee == 0 { 1 } else { fee };
-        
-        let amount_in_with_fee = amount_in.checked_sub(fee).ok_or(RaumFiLibraryError::Overflow)?;
-        let numerator = amount_in_with_fee.checked_mul(reserve_out).ok_or(RaumFiLibraryError::Overflow)?;
-        let denominator = reserve_in.checked_add(amount_in_with_fee).ok_or(RaumFiLibraryError::Overflow)?;
-        
-        Ok(numerator.checked_div(denominator).ok_or(RaumFiLibraryError::DivisionByZero)?)
+        // The largest output for which the pair's K check holds:
+        // amount_in * (D - fee) * reserve_out / (reserve_in * D + amount_in * (D - fee))
+        let amount_in_with_fee = amount_in
+            .checked_mul(FEE_DENOMINATOR - fee as i128)
+            .ok_or(RaumFiLibraryError::Overflow)?;
+        let denominator = reserve_in
+            .checked_mul(FEE_DENOMINATOR)
+            .and_then(|scaled| scaled.checked_add(amount_in_with_fee))
+            .ok_or(RaumFiLibraryError::Overflow)?;
+
+        Ok(mul_div_floor(amount_in_with_fee, reserve_out, denominator)?)
     }
 
     // Calculate the required input amount of the other asset
-    pub fn calculate_amount_in(env: &Env, amount_out: i128, reserve_in: i128, reserve_out: i128) -> Result<i128, RaumFiLibraryError> {
+    pub fn calculate_amount_in(amount_out: i128, reserve_in: i128, reserve_out: i128) -> Result<i128, RaumFiLibraryError> {
+        calculate_amount_in_with_fee(amount_out, reserve_in, reserve_out, DEFAULT_FEE)
+    }
+
+    // Calculate the required input amount of the other asset for a pair charging `fee` basis points
+    pub fn calculate_amount_in_with_fee(amount_out: i128, reserve_in: i128, reserve_out: i128, fee: u32) -> Result<i128, RaumFiLibraryError> {
         if amount_out <= 0 {
             return Err(RaumFiLibraryError::InsufficientAmount);
         }
-        if reserve_in <= 0 || reserve_out <= 0 {
+        // The pair cannot pay out its whole reserve or more
+        if reserve_in <= 0 || reserve_out <= 0 || amount_out >= reserve_out {
             return Err(RaumFiLibraryError::InsufficientLiquidity);
         }
 
-        let numerator = reserve_in
-            .checked_mul(amount_out)
-            .ok_or(RaumFiLibraryError::Overflow)?
-            .checked_mul(1000)
+        let amount_out_scaled = amount_out
+            .checked_mul(FEE_DENOMINATOR)
             .ok_or(RaumFiLibraryError::Overflow)?;
         let denominator = reserve_out
             .checked_sub(amount_out)
             .ok_or(RaumFiLibraryError::Overflow)?
-            .checked_mul(997)
+            .checked_mul(FEE_DENOMINATOR - fee as i128)
             .ok_or(RaumFiLibraryError::Overflow)?;
-        let division_result = numerator
-            .checked_ceiling_div(denominator)
-            .ok_or(RaumFiLibraryError::DivisionByZero)?.checked_add(1)
-            .ok_or(RaumFiLibraryError::Overflow)?;
-        Ok(division_result)
-        // log!(&env, "division_result: {}", division_result , "numerator: " , numerator , "denominator: " , denominator);
-        // let result = if division_result == 0 { division_result + 1 } else { division_result };
-        // result
-        //     .checked_add(1)
-        //     .ok_or(RaumFiLibraryError::Overflow)
+        // The smallest input for which the pair's K check holds:
+        // reserve_in * D * amount_out / ((reserve_out - amount_out) * (D - fee))
+        Ok(mul_div_ceil(reserve_in, amount_out_scaled, denominator)?)
     }
 
     // Perform chained getAmountOut calculations on any number of pairs
@@ -173,8 +191,9 @@ impl CheckedCeilingDiv for i128 {
         for i in 0..path.len() - 1 {
             let (reserve_in, reserve_out) =
                 get_reserves(env, factory.clone(), path.get(i).unwrap(), path.get(i + 1).unwrap())?;
+            let fee = get_fee(env, factory.clone(), path.get(i).unwrap(), path.get(i + 1).unwrap())?;
             let amount_out =
-                calculate_amount_out(env, amounts.get(i).unwrap(), reserve_in, reserve_out)?;
+                calculate_amount_out_with_fee(amounts.get(i).unwrap(), reserve_in, reserve_out, fee)?;
             amounts.push_back(amount_out);
         }
         Ok(amounts)
@@ -195,8 +214,9 @@ impl CheckedCeilingDiv for i128 {
         for i in (1..path.len()).rev() {
             let (reserve_in, reserve_out) =
                 get_reserves(env, factory.clone(), path.get(i - 1).unwrap(), path.get(i).unwrap())?;
+            let fee = get_fee(env, factory.clone(), path.get(i - 1).unwrap(), path.get(i).unwrap())?;
             let amount_in =
-                calculate_amount_in(env, amounts.get(0).unwrap(), reserve_in, reserve_out)?;
+                calculate_amount_in_with_fee(amounts.get(0).unwrap(), reserve_in, reserve_out, fee)?;
             amounts.push_front(amount_in);
         }
         Ok(amounts)
@@ -208,7 +228,6 @@ impl CheckedCeilingDiv for i128 {
     }
 
     pub fn optimal_liquidity(
-        env: &Env,
         amount_a_desired: i128,
         amount_b_desired: i128,
         amount_a_min: i128,
@@ -220,7 +239,7 @@ impl CheckedCeilingDiv for i128 {
             return Ok((amount_a_desired, amount_b_desired));
         }
     
-        let amount_b_optimal = calculate_quote(env, amount_a_desired, reserve_a, reserve_b)?;
+        let amount_b_optimal = calculate_quote(amount_a_desired, reserve_a, reserve_b)?;
         if amount_b_optimal <= amount_b_desired {
             if amount_b_optimal < amount_b_min {
                 return Err(RaumFiLibraryError::InsufficientBAmount);
@@ -228,7 +247,7 @@ impl CheckedCeilingDiv for i128 {
             return Ok((amount_a_desired, amount_b_optimal));
         }
     
-        let amount_a_optimal = calculate_quote(env, amount_b_desired, reserve_b, reserve_a)?;
+        let amount_a_optimal = calculate_quote(amount_b_desired, reserve_b, reserve_a)?;
         assert!(amount_a_optimal <= amount_a_desired);
         if amount_a_optimal < amount_a_min {
             return Err(RaumFiLibraryError::InsufficientAAmount);
@@ -238,27 +257,32 @@ impl CheckedCeilingDiv for i128 {
     
     // Calculate the price impact of a trade
     pub fn calculate_price_impact(
-        env: &Env,
         amount_in: i128,
         amount_out: i128,
         reserve_in: i128,
         reserve_out: i128,
     ) -> Result<i128, RaumFiLibraryError> {
-        let mid_price = calculate_quote(env, 10u128.pow(18) as i128, reserve_in, reserve_out)?;
-        let execution_price = calculate_quote(env, 10u128.pow(18) as i128, amount_in, amount_out)?;
-        let price_impact = mid_price
-            .checked_sub(execution_price)
-            .ok_or(RaumFiLibraryError::Overflow)?
-            .checked_mul(10000)
-            .ok_or(RaumFiLibraryError::Overflow)?
-            .checked_div(mid_price)
-            .ok_or(RaumFiLibraryError::DivisionByZero)?;
-        Ok(price_impact)
+        if amount_in <= 0 || amount_out <= 0 {
+            return Err(RaumFiLibraryError::InsufficientAmount);
+        }
+        if reserve_in <= 0 || reserve_out <= 0 {
+            return Err(RaumFiLibraryError::InsufficientLiquidity);
+        }
+        let mid_price = UQ64x64::encode(reserve_out, reserve_in)?.raw();
+        let execution_price = UQ64x64::encode(amount_out, amount_in)?.raw();
+        // In basis points of the mid price, negative when the trade beats it
+        let difference = mid_price.abs_diff(execution_price);
+        let price_impact = i128::try_from(mul_div(difference, U256::new(10000), mid_price, Rounding::Down)?)
+            .map_err(|_| RaumFiLibraryError::Overflow)?;
+        if execution_price > mid_price {
+            Ok(-price_impact)
+        } else {
+            Ok(price_impact)
+        }
     }
     
     // Calculate the liquidity minted from depositing tokens
     pub fn calculate_liquidity_minted(
-        env: &Env,
         total_supply: i128,
         amount_a: i128,
         amount_b: i128,
@@ -267,19 +291,11 @@ impl CheckedCeilingDiv for i128 {
     ) -> Result<i128, RaumFiLibraryError> {
         let mut liquidity: i128;
         if total_supply == 0 {
-            liquidity = (amount_a.checked_mul(amount_b).ok_or(RaumFiLibraryError::Overflow)?).sqrt();
+            liquidity = sqrt_product(amount_a, amount_b)?;
             liquidity = liquidity.checked_sub(1000).ok_or(RaumFiLibraryError::Overflow)?; // Minimum liquidity
         } else {
-            let liquidity_a = amount_a
-                .checked_mul(total_supply)
-                .ok_or(RaumFiLibraryError::Overflow)?
-                .checked_div(reserve_a)
-                .ok_or(RaumFiLibraryError::DivisionByZero)?;
-            let liquidity_b = amount_b
-                .checked_mul(total_supply)
-                .ok_or(RaumFiLibraryError::Overflow)?
-                .checked_div(reserve_b)
-                .ok_or(RaumFiLibraryError::DivisionByZero)?;
+            let liquidity_a = mul_div_floor(amount_a, total_supply, reserve_a)?;
+            let liquidity_b = mul_div_floor(amount_b, total_supply, reserve_b)?;
             liquidity = liquidity_a.min(liquidity_b);
         }
         if liquidity <= 0 {
@@ -290,7 +306,6 @@ impl CheckedCeilingDiv for i128 {
     
     // Calculate the amount of tokens received when burning liquidity
     pub fn calculate_burn_amounts(
-        env: &Env,
         liquidity: i128,
         total_supply: i128,
         reserve_a: i128,
@@ -299,16 +314,8 @@ impl CheckedCeilingDiv for i128 {
         if liquidity > total_supply {
             return Err(RaumFiLibraryError::InsufficientTotalSupply);
         }
-        let amount_a = liquidity
-            .checked_mul(reserve_a)
-            .ok_or(RaumFiLibraryError::Overflow)?
-            .checked_div(total_supply)
-            .ok_or(RaumFiLibraryError::DivisionByZero)?;
-        let amount_b = liquidity
-            .checked_mul(reserve_b)
-            .ok_or(RaumFiLibraryError::Overflow)?
-            .checked_div(total_supply)
-            .ok_or(RaumFiLibraryError::DivisionByZero)?;
+        let amount_a = mul_div_floor(liquidity, reserve_a, total_supply)?;
+        let amount_b = mul_div_floor(liquidity, reserve_b, total_supply)?;
         if amount_a <= 0 || amount_b <= 0 {
             return Err(RaumFiLibraryError::InsufficientLiquidityBurned);
         }
@@ -316,22 +323,12 @@ impl CheckedCeilingDiv for i128 {
     }
     
     pub fn is_constant_product_maintained(
-        env: &Env,
         reserve_a: i128,
         reserve_b: i128,
         new_reserve_a: i128,
         new_reserve_b: i128,
     ) -> bool {
-        if let (Ok(k_before), Ok(k_after)) = (
-            calculate_k(reserve_a, reserve_b),
-            calculate_k(new_reserve_a, new_reserve_b)
-        ) {
-            log!(env, "k_before: {}", k_before);
-            log!(env, "k_after: {}", k_after);
-            k_after >= k_before
-        } else {
-            false // Return false if there's an error in calculation
-        }
+        compare_products(new_reserve_a, new_reserve_b, reserve_a, reserve_b) != Ordering::Less
     }
 
 
@@ -0,0 +1,318 @@
+//! Sqrt-price and tick math of concentrated liquidity pools, after Uniswap V3:
+//!
+//! `sqrt_price = sqrt(1.0001^tick) * 2^96`
+//!
+//! Prices are the square root of `token1 / token0` in Q64.96 fixed point. Multiplying a
+//! Q64.96 price by a liquidity needs up to 512 bits before dividing back down, more than
+//! the host `U256` offers, so the math runs on ethnum's `U256` inside the contract. Every
+//! function that divides takes the direction it rounds in, so that callers round in favor
+//! of the pool.
+
+use ethnum::{I256, U256};
+
+use crate::error::RaumFiLibraryError;
+
+/// Direction in which a division rounds
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum Rounding {
+    Down,
+    Up,
+}
+
+pub const MIN_TICK: i32 = -887_272;
+pub const MAX_TICK: i32 = 887_272;
+/// `get_sqrt_price_at_tick(MIN_TICK)`
+pub const MIN_SQRT_PRICE: U256 = U256::from_words(0, 4_295_128_739);
+/// `get_sqrt_price_at_tick(MAX_TICK)`
+pub const MAX_SQRT_PRICE: U256 = U256::from_words(0xfffd8963, 0xefd1fc6a506488495d951d5263988d26);
+pub const Q96: U256 = U256::from_words(0, 1 << 96);
+pub const Q128: U256 = U256::from_words(1, 0);
+
+/// `2^128 / sqrt(1.0001)^(2^i)` for the bits `i` of an absolute tick
+const TICK_FACTORS: [u128; 20] = [
+    0xfffcb933bd6fad37aa2d162d1a594001,
+    0xfff97272373d413259a46990580e213a,
+    0xfff2e50f5f656932ef12357cf3c7fdcc,
+    0xffe5caca7e10e4e61c3624eaa0941cd0,
+    0xffcb9843d60f6159c9db58835c926644,
+    0xff973b41fa98c081472e6896dfb254c0,
+    0xff2ea16466c96a3843ec78b326b52861,
+    0xfe5dee046a99a2a811c461f1969c3053,
+    0xfcbe86c7900a88aedcffc83b479aa3a4,
+    0xf987a7253ac413176f2b074cf7815e54,
+    0xf3392b0822b70005940c7a398e4b70f3,
+    0xe7159475a2c29b7443b29c7fa6e889d9,
+    0xd097f3bdfd2022b8845ad8f792aa5825,
+    0xa9f746462d870fdf8a65dc1f90e061e5,
+    0x70d869a156d2a1b890bb3df62baf32f7,
+    0x31be135f97d08fd981231505542fcfa6,
+    0x9aa508b5b7a84e1c677de54f3e99bc9,
+    0x5d6af8dedb81196699c329225ee604,
+    0x2216e584f5fa1ea926041bedfe98,
+    0x48a170391f7dc42444e8fa2,
+];
+
+/// 512-bit product of `a` and `b`, as its high and low words
+fn full_mul(a: U256, b: U256) -> (U256, U256) {
+    let (a1, a0) = a.into_words();
+    let (b1, b0) = b.into_words();
+    let (mid, mid_carry) = (U256::new(a0) * U256::new(b1)).overflowing_add(U256::new(a1) * U256::new(b0));
+    let (lo, lo_carry) = (U256::new(a0) * U256::new(b0)).overflowing_add(mid << 128);
+    let mut hi = U256::new(a1) * U256::new(b1) + (mid >> 128);
+    if mid_carry {
+        hi += Q128;
+    }
+    if lo_carry {
+        hi += U256::ONE;
+    }
+    (hi, lo)
+}
+
+/// Quotient and remainder of `a * b / denominator`, or `None` if the denominator is zero
+/// or the quotient does not fit 256 bits
+fn mul_div_rem(a: U256, b: U256, denominator: U256) -> Option<(U256, U256)> {
+    if denominator == U256::ZERO {
+        return None;
+    }
+    let (hi, lo) = full_mul(a, b);
+    if hi == U256::ZERO {
+        return Some((lo / denominator, lo % denominator));
+    }
+    if hi >= denominator {
+        return None;
+    }
+
+    // Long division of the 512-bit product, bringing down one bit of the low word at a time
+    let mut remainder = hi;
+    let mut quotient = U256::ZERO;
+    for i in (0..256u32).rev() {
+        let carry = remainder >> 255 != U256::ZERO;
+        remainder = (remainder << 1) | ((lo >> i) & U256::ONE);
+        quotient <<= 1;
+        if carry || remainder >= denominator {
+            remainder = remainder.wrapping_sub(denominator);
+            quotient |= U256::ONE;
+        }
+    }
+    Some((quotient, remainder))
+}
+
+/// `a * b / denominator` rounded in the given direction, without overflow of the
+/// intermediate product
+pub fn mul_div(a: U256, b: U256, denominator: U256, rounding: Rounding) -> Result<U256, RaumFiLibraryError> {
+    if denominator == U256::ZERO {
+        return Err(RaumFiLibraryError::DivisionByZero);
+    }
+    let (quotient, remainder) = mul_div_rem(a, b, denominator).ok_or(RaumFiLibraryError::Overflow)?;
+    if rounding == Rounding::Down || remainder == U256::ZERO {
+        Ok(quotient)
+    } else {
+        quotient.checked_add(U256::ONE).ok_or(RaumFiLibraryError::Overflow)
+    }
+}
+
+fn div(a: U256, b: U256, rounding: Rounding) -> U256 {
+    let quotient = a / b;
+    if rounding == Rounding::Down || a % b == U256::ZERO {
+        quotient
+    } else {
+        quotient + U256::ONE
+    }
+}
+
+/// Returns the Q64.96 sqrt price `sqrt(1.0001^tick)`
+pub fn get_sqrt_price_at_tick(tick: i32) -> Result<U256, RaumFiLibraryError> {
+    if !(MIN_TICK..=MAX_TICK).contains(&tick) {
+        return Err(RaumFiLibraryError::InvalidTick);
+    }
+    let abs_tick = tick.unsigned_abs();
+
+    // Q128.128 ratio of 1.0001^(-|tick| / 2), one factor per set bit of the tick
+    let mut ratio = Q128;
+    for (bit, factor) in TICK_FACTORS.iter().enumerate() {
+        if abs_tick & (1 << bit) != 0 {
+            ratio = (ratio * U256::new(*factor)) >> 128;
+        }
+    }
+    if tick > 0 {
+        ratio = U256::MAX / ratio;
+    }
+
+    // Down to Q64.96, rounding up so that the tick of the result is `tick` again
+    let sqrt_price = ratio >> 32;
+    if ratio & U256::from(u32::MAX) == U256::ZERO {
+        Ok(sqrt_price)
+    } else {
+        Ok(sqrt_price + U256::ONE)
+    }
+}
+
+/// Returns the greatest tick whose sqrt price is at most `sqrt_price`
+pub fn get_tick_at_sqrt_price(sqrt_price: U256) -> Result<i32, RaumFiLibraryError> {
+    if sqrt_price < MIN_SQRT_PRICE || sqrt_price >= MAX_SQRT_PRICE {
+        return Err(RaumFiLibraryError::InvalidSqrtPrice);
+    }
+    let ratio: U256 = sqrt_price << 32;
+
+    // log2 of the Q128.128 ratio in Q64.64: the integer part from the most significant bit,
+    // then 14 fractional bits by repeated squaring
+    let msb = 255 - ratio.leading_zeros();
+    let mut r = if msb >= 128 { ratio >> (msb - 127) } else { ratio << (127 - msb) };
+    let mut log_2: I256 = I256::from(msb as i32 - 128) << 64;
+    for bit in (50..64u32).rev() {
+        r = (r * r) >> 127;
+        let f: U256 = r >> 128;
+        log_2 |= f.as_i256() << bit;
+        r >>= f.as_u32();
+    }
+
+    // log_sqrt(1.0001)(ratio) in Q128.128, bracketed by the error bounds of the approximation
+    let log_sqrt10001: I256 = log_2 * I256::new(255_738_958_999_603_826_347_141);
+    let tick_low: I256 = (log_sqrt10001 - I256::new(3_402_992_956_809_132_418_596_140_100_660_247_210)) >> 128;
+    let tick_high: I256 = (log_sqrt10001 + U256::new(291_339_464_771_989_622_907_027_621_153_398_088_495).as_i256()) >> 128;
+    let (tick_low, tick_high) = (tick_low.as_i32(), tick_high.as_i32());
+
+    if tick_low == tick_high || get_sqrt_price_at_tick(tick_high)? > sqrt_price {
+        Ok(tick_low)
+    } else {
+        Ok(tick_high)
+    }
+}
+
+/// Price after adding or removing `amount` of token0, rounded up so that the pool never
+/// gives out more token1 than the exact price implies
+fn get_next_sqrt_price_from_amount0_rounding_up(
+    sqrt_price: U256,
+    liquidity: u128,
+    amount: U256,
+    add: bool,
+) -> Result<U256, RaumFiLibraryError> {
+    if amount == U256::ZERO {
+        return Ok(sqrt_price);
+    }
+    let numerator: U256 = U256::new(liquidity) << 96;
+    let product = amount.checked_mul(sqrt_price);
+
+    if add {
+        if let Some(denominator) = product.and_then(|product| numerator.checked_add(product)) {
+            return mul_div(numerator, sqrt_price, denominator, Rounding::Up);
+        }
+        let denominator = (numerator / sqrt_price).checked_add(amount).ok_or(RaumFiLibraryError::Overflow)?;
+        Ok(div(numerator, denominator, Rounding::Up))
+    } else {
+        let product = product.ok_or(RaumFiLibraryError::InsufficientLiquidity)?;
+        if numerator <= product {
+            return Err(RaumFiLibraryError::InsufficientLiquidity);
+        }
+        mul_div(numerator, sqrt_price, numerator - product, Rounding::Up)
+    }
+}
+
+/// Price after adding or removing `amount` of token1, rounded down so that the pool never
+/// gives out more token0 than the exact price implies
+fn get_next_sqrt_price_from_amount1_rounding_down(
+    sqrt_price: U256,
+    liquidity: u128,
+    amount: U256,
+    add: bool,
+) -> Result<U256, RaumFiLibraryError> {
+    let liquidity = U256::new(liquidity);
+    if add {
+        let quotient = mul_div(amount, Q96, liquidity, Rounding::Down)?;
+        let next = sqrt_price.checked_add(quotient).ok_or(RaumFiLibraryError::Overflow)?;
+        if next >> 160 != U256::ZERO {
+            return Err(RaumFiLibraryError::Overflow);
+        }
+        Ok(next)
+    } else {
+        let quotient = mul_div(amount, Q96, liquidity, Rounding::Up)?;
+        if sqrt_price <= quotient {
+            return Err(RaumFiLibraryError::InsufficientLiquidity);
+        }
+        Ok(sqrt_price - quotient)
+    }
+}
+
+/// Price after swapping `amount_in` of token0 (`zero_for_one`) or of token1 into `liquidity`
+pub fn calculate_next_sqrt_price_from_input(
+    sqrt_price: U256,
+    liquidity: u128,
+    amount_in: U256,
+    zero_for_one: bool,
+) -> Result<U256, RaumFiLibraryError> {
+    if sqrt_price == U256::ZERO || liquidity == 0 {
+        return Err(RaumFiLibraryError::InsufficientLiquidity);
+    }
+    if zero_for_one {
+        get_next_sqrt_price_from_amount0_rounding_up(sqrt_price, liquidity, amount_in, true)
+    } else {
+        get_next_sqrt_price_from_amount1_rounding_down(sqrt_price, liquidity, amount_in, true)
+    }
+}
+
+/// Price after taking `amount_out` of token1 (`zero_for_one`) or of token0 out of `liquidity`
+pub fn calculate_next_sqrt_price_from_output(
+    sqrt_price: U256,
+    liquidity: u128,
+    amount_out: U256,
+    zero_for_one: bool,
+) -> Result<U256, RaumFiLibraryError> {
+    if sqrt_price == U256::ZERO || liquidity == 0 {
+        return Err(RaumFiLibraryError::InsufficientLiquidity);
+    }
+    if zero_for_one {
+        get_next_sqrt_price_from_amount1_rounding_down(sqrt_price, liquidity, amount_out, false)
+    } else {
+        get_next_sqrt_price_from_amount0_rounding_up(sqrt_price, liquidity, amount_out, false)
+    }
+}
+
+fn sorted(sqrt_price_a: U256, sqrt_price_b: U256) -> (U256, U256) {
+    if sqrt_price_a > sqrt_price_b {
+        (sqrt_price_b, sqrt_price_a)
+    } else {
+        (sqrt_price_a, sqrt_price_b)
+    }
+}
+
+/// Amount of token0 that `liquidity` holds between two prices:
+/// `liquidity * (sqrt_upper - sqrt_lower) / (sqrt_upper * sqrt_lower)`
+pub fn get_amount0_delta(
+    sqrt_price_a: U256,
+    sqrt_price_b: U256,
+    liquidity: u128,
+    rounding: Rounding,
+) -> Result<U256, RaumFiLibraryError> {
+    let (sqrt_lower, sqrt_upper) = sorted(sqrt_price_a, sqrt_price_b);
+    if sqrt_lower == U256::ZERO {
+        return Err(RaumFiLibraryError::InvalidSqrtPrice);
+    }
+    let numerator: U256 = U256::new(liquidity) << 96;
+    let amount = mul_div(numerator, sqrt_upper - sqrt_lower, sqrt_upper, rounding)?;
+    Ok(div(amount, sqrt_lower, rounding))
+}
+
+/// Amount of token1 that `liquidity` holds between two prices:
+/// `liquidity * (sqrt_upper - sqrt_lower)`
+pub fn get_amount1_delta(
+    sqrt_price_a: U256,
+    sqrt_price_b: U256,
+    liquidity: u128,
+    rounding: Rounding,
+) -> Result<U256, RaumFiLibraryError> {
+    let (sqrt_lower, sqrt_upper) = sorted(sqrt_price_a, sqrt_price_b);
+    mul_div(U256::new(liquidity), sqrt_upper - sqrt_lower, Q96, rounding)
+}
+
+/// Converts a `U256` stored by a contract into the `U256` the math works on
+pub fn from_host_u256(value: &soroban_sdk::U256) -> U256 {
+    let mut bytes = [0u8; 32];
+    value.to_be_bytes().copy_into_slice(&mut bytes);
+    U256::from_be_bytes(bytes)
+}
+
+/// Converts a result of the math into a `U256` a contract can store or return
+pub fn to_host_u256(env: &soroban_sdk::Env, value: U256) -> soroban_sdk::U256 {
+    let (hi, lo) = value.into_words();
+    soroban_sdk::U256::from_parts(env, (hi >> 64) as u64, hi as u64, (lo >> 64) as u64, lo as u64)
+}
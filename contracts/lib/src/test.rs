@@ -3,7 +3,7 @@
 use super::*;
 use soroban_sdk::testutils::{Address as _, Ledger, LedgerInfo};
 use soroban_sdk::xdr::ToXdr;
-use soroban_sdk::{log, symbol_short, token, vec, Address, BytesN, Env , Bytes};
+use soroban_sdk::{log, symbol_short, token, vec, Address, BytesN, Env , Bytes, U256};
 use crate::RaumFiLibraryClient;
 use crate::RaumFiV2Library;
 
@@ -23,6 +23,8 @@ fn pair_token_wasm(e: &Env) -> BytesN<32> {
     soroban_sdk::contractimport!(
         file = "D:/RaumFiV2/RaumFiV2/target/wasm32-unknown-unknown/release/pair.wasm"
     );
+    // Tests upload and register the pair's wasm more than once, which outgrows the default budget
+    e.budget().reset_unlimited();
     e.deployer().upload_contract_wasm(WASM)
 }
 
@@ -45,7 +47,7 @@ fn create_pair_contract<'a>(e: &Env, factory: &Address, token_a: &Address, token
     let salt_hash = e.crypto().sha256(&salt);
     let pair_address = e.deployer().with_address(factory.clone(), salt_hash).deployed_address();
     let pair = PairClient::new(e, &pair_address);
-    pair.initialize(token_a, token_b, factory);
+    pair.initialize(token_a, token_b, factory, &30);
     pair
 }
 
@@ -137,7 +139,8 @@ fn test_get_amount_out() {
 
     let amount_out = client.calculate_amount_out(&amount_in, &reserve_in, &reserve_out);
 
-    assert_eq!(amount_out, 1);
+    // 3 * 9970 * 100 / (100 * 10000 + 3 * 9970), the most the pair lets out
+    assert_eq!(amount_out, 2);
 }
 
 #[test]
@@ -155,7 +158,41 @@ fn test_get_amount_in() {
     let reserve_out: i128 = 100;
     let amount_in = client.calculate_amount_in(&amount_out, &reserve_in, &reserve_out);
 
-    assert_eq!(amount_in, 3);
+    // ceil(100 * 10000 * 1 / (99 * 9970)), the least the pair takes in
+    assert_eq!(amount_in, 2);
+}
+
+#[test]
+fn test_get_amount_out_and_in_with_fee() {
+    let env = Env::default();
+    let client = create_test_contract(&env);
+
+    let reserve_in: i128 = 1_000_000;
+    let reserve_out: i128 = 1_000_000;
+
+    // A lower fee tier quotes more output for the same input
+    assert_eq!(client.calculate_amount_out_with_fee(&10_000, &reserve_in, &reserve_out, &5), 9896);
+    assert_eq!(client.calculate_amount_out_with_fee(&10_000, &reserve_in, &reserve_out, &100), 9802);
+
+    // ...and requires less input for the same output
+    assert_eq!(client.calculate_amount_in_with_fee(&9000, &reserve_in, &reserve_out, &5), 9087);
+    assert_eq!(client.calculate_amount_in_with_fee(&9000, &reserve_in, &reserve_out, &100), 9174);
+
+    // No input buys the whole reserve or more
+    assert_eq!(
+        client.try_calculate_amount_in_with_fee(&reserve_out, &reserve_in, &reserve_out, &5),
+        Err(Ok(RaumFiLibraryError::InsufficientLiquidity))
+    );
+    assert_eq!(
+        client.try_calculate_amount_in_with_fee(&(reserve_out + 1), &reserve_in, &reserve_out, &5),
+        Err(Ok(RaumFiLibraryError::InsufficientLiquidity))
+    );
+
+    // The fee-less variants quote at the default 0.3% tier
+    assert_eq!(
+        client.calculate_amount_out(&10_000, &reserve_in, &reserve_out),
+        client.calculate_amount_out_with_fee(&10_000, &reserve_in, &reserve_out, &DEFAULT_FEE)
+    );
 }
 
 //Work after router is created
@@ -174,7 +211,7 @@ fn test_get_amount_in() {
 //     // Create the pair contract using the existing function
 //     let pair_address = pair::register_pair_contract(&env);
 //     let pair_client = pair::PairClient::new(&env, &pair_address);
-//     pair_client.initialize(&token_a_client.address, &token_b_client.address, &factory_client.address);
+//     pair_client.initialize(&token_a_client.address, &token_b_client.address, &factory_client.address, &30);
 
 //     // Initialize the pair contract with some liquidity
 //     let initial_liquidity = 10_000i128;
@@ -238,7 +275,7 @@ fn test_calculate_k() {
     // Create the pair contract using the existing function
     let pair_address = pair::register_pair_contract(&env);
     let pair_client = pair::PairClient::new(&env, &pair_address);
-    pair_client.initialize(&token_a_client.address, &token_b_client.address, &factory_client.address);
+    pair_client.initialize(&token_a_client.address, &token_b_client.address, &factory_client.address, &30);
 
     let reserve_a:i128 = 1000;
     let reserve_b:i128 = 2000;
@@ -263,7 +300,7 @@ fn test_optimal_liquidity() {
     // Create the pair contract using the existing function
     let pair_address = pair::register_pair_contract(&env);
     let pair_client = pair::PairClient::new(&env, &pair_address);
-    pair_client.initialize(&token_a_client.address, &token_b_client.address, &factory_client.address);
+    pair_client.initialize(&token_a_client.address, &token_b_client.address, &factory_client.address, &30);
     let amount_a_desired = 100;
     let amount_b_desired = 200;
     let amount_a_min = 90;
@@ -299,7 +336,7 @@ fn test_calculate_price_impact() {
     // Create the pair contract using the existing function
     let pair_address = pair::register_pair_contract(&env);
     let pair_client = pair::PairClient::new(&env, &pair_address);
-    pair_client.initialize(&token_a_client.address, &token_b_client.address, &factory_client.address);
+    pair_client.initialize(&token_a_client.address, &token_b_client.address, &factory_client.address, &30);
     let amount_in = 100;
     let amount_out = 189;
     let reserve_in = 10000;
@@ -325,7 +362,7 @@ fn test_calculate_liquidity_minted() {
     // Create the pair contract using the existing function
     let pair_address = pair::register_pair_contract(&env);
     let pair_client = pair::PairClient::new(&env, &pair_address);
-    pair_client.initialize(&token_a_client.address, &token_b_client.address, &factory_client.address);
+    pair_client.initialize(&token_a_client.address, &token_b_client.address, &factory_client.address, &30);
     let total_supply = 1000;
     let amount_a = 100;
     let amount_b = 200;
@@ -352,7 +389,7 @@ fn test_calculate_burn_amounts() {
     // Create the pair contract using the existing function
     let pair_address = pair::register_pair_contract(&env);
     let pair_client = pair::PairClient::new(&env, &pair_address);
-    pair_client.initialize(&token_a_client.address, &token_b_client.address, &factory_client.address);
+    pair_client.initialize(&token_a_client.address, &token_b_client.address, &factory_client.address, &30);
     let liquidity = 100;
     let total_supply = 1000;
     let reserve_a = 1000;
@@ -380,7 +417,7 @@ fn test_is_constant_product_maintained() {
     // Create the pair contract using the existing function
     let pair_address = pair::register_pair_contract(&env);
     let pair_client = pair::PairClient::new(&env, &pair_address);
-    pair_client.initialize(&token_a_client.address, &token_b_client.address, &factory_client.address);
+    pair_client.initialize(&token_a_client.address, &token_b_client.address, &factory_client.address, &30);
     let reserve_a = 1000;
     let reserve_b = 2000;
     let new_reserve_a = 1100;
@@ -396,3 +433,340 @@ fn test_is_constant_product_maintained() {
 }
 
 
+
+#[test]
+fn test_calculate_weighted_spot_price() {
+    let env = Env::default();
+    let client = create_test_contract(&env);
+
+    // Balances in proportion to the weights price the tokens equally
+    assert_eq!(client.calculate_weighted_spot_price(&800_000, &8000, &200_000, &2000), ONE);
+    // A 50/50 pool prices like a constant product pair
+    assert_eq!(client.calculate_weighted_spot_price(&1_000_000, &5000, &2_000_000, &5000), 2 * ONE);
+}
+
+#[test]
+fn test_calculate_weighted_amount_out_and_in() {
+    let env = Env::default();
+    let client = create_test_contract(&env);
+
+    // A fee-less 50/50 pool quotes like a constant product pair, rounded down
+    assert_eq!(client.calculate_weighted_amount_out(&10_000, &1_000_000, &5000, &1_000_000, &5000, &0), 9900);
+
+    // Selling the 20% token into an 80/20 pool moves its price four times as much
+    assert_eq!(client.calculate_weighted_amount_out(&10_000, &1_000_000, &2000, &1_000_000, &8000, &30), 2477);
+    // Exact value 10040.28, rounded up in the pool's favor
+    assert_eq!(client.calculate_weighted_amount_in(&2_487, &1_000_000, &2000, &1_000_000, &8000, &30), 10042);
+
+    // Swaps are capped at 30% of the balances
+    assert_eq!(
+        client.try_calculate_weighted_amount_out(&300_001, &1_000_000, &5000, &1_000_000, &5000, &30),
+        Err(Ok(RaumFiLibraryError::MaxInRatio))
+    );
+    assert_eq!(
+        client.try_calculate_weighted_amount_in(&300_001, &1_000_000, &5000, &1_000_000, &5000, &30),
+        Err(Ok(RaumFiLibraryError::MaxOutRatio))
+    );
+}
+
+#[test]
+fn test_calculate_weighted_invariant() {
+    let env = Env::default();
+    let client = create_test_contract(&env);
+
+    let weights = vec![&env, 8000u32, 1000, 1000];
+    // Exact value 1_025_671_083.19
+    assert_eq!(
+        client.calculate_weighted_invariant(&vec![&env, 1_600_000_000i128, 100_000_000, 300_000_000], &weights),
+        1_025_671_083
+    );
+
+    // Weights must be at least 1% and sum to 100%
+    assert_eq!(
+        client.try_calculate_weighted_invariant(&vec![&env, 1_000i128, 1_000], &vec![&env, 5000u32, 4000]),
+        Err(Ok(RaumFiLibraryError::InvalidWeight))
+    );
+    assert_eq!(
+        client.try_calculate_weighted_invariant(&vec![&env, 1_000i128, 1_000], &vec![&env, 9950u32, 50]),
+        Err(Ok(RaumFiLibraryError::InvalidWeight))
+    );
+}
+
+#[test]
+fn test_calculate_weighted_single_join_and_exit() {
+    let env = Env::default();
+    let client = create_test_contract(&env);
+
+    // 80% of a 10% deposit into the 80% token is proportional; the rest pays the fee.
+    // Exact value 79_183.25
+    assert_eq!(client.calculate_weighted_single_join(&100_000, &1_000_000, &8000, &1_000_000, &30), 79_183);
+
+    // Exact value 123_322.59, rounded down
+    assert_eq!(client.calculate_weighted_single_exit(&100_000, &1_000_000, &8000, &1_000_000, &30), 123_321);
+
+    // A single-asset exit may not burn more than 30% of the supply
+    assert_eq!(
+        client.try_calculate_weighted_single_exit(&300_001, &1_000_000, &8000, &1_000_000, &30),
+        Err(Ok(RaumFiLibraryError::MaxOutRatio))
+    );
+}
+
+fn u256(env: &Env, value: &str) -> U256 {
+    to_host_u256(env, value.parse().unwrap())
+}
+
+#[test]
+fn test_get_sqrt_price_at_tick() {
+    let env = Env::default();
+    let client = create_test_contract(&env);
+
+    let vectors = [
+        (MIN_TICK, "4295128739"),
+        (MIN_TICK + 1, "4295343490"),
+        (-500_000, "1101692437043807371"),
+        (-100_000, "533968626430936354154228408"),
+        (-50_000, "6504256538020985011912221507"),
+        (-1000, "75364347830767020784054125655"),
+        (-60, "78990846045029531151608375686"),
+        (-1, "79224201403219477170569942574"),
+        (0, "79228162514264337593543950336"),
+        (1, "79232123823359799118286999568"),
+        (60, "79466191966197645195421774833"),
+        (1000, "83290069058676223003182343270"),
+        (50_000, "965075977353221155028623082916"),
+        (100_000, "11755562826496067164730007768450"),
+        (500_000, "5697689776495288729098254600827762987878"),
+        (MAX_TICK - 1, "1461373636630004318706518188784493106690254656249"),
+        (MAX_TICK, "1461446703485210103287273052203988822378723970342"),
+    ];
+    for (tick, sqrt_price) in vectors {
+        assert_eq!(client.get_sqrt_price_at_tick(&tick), u256(&env, sqrt_price));
+    }
+
+    assert_eq!(client.try_get_sqrt_price_at_tick(&(MIN_TICK - 1)), Err(Ok(RaumFiLibraryError::InvalidTick)));
+    assert_eq!(client.try_get_sqrt_price_at_tick(&(MAX_TICK + 1)), Err(Ok(RaumFiLibraryError::InvalidTick)));
+}
+
+#[test]
+fn test_get_tick_at_sqrt_price() {
+    let env = Env::default();
+    let client = create_test_contract(&env);
+
+    assert_eq!(client.get_tick_at_sqrt_price(&to_host_u256(&env, MIN_SQRT_PRICE)), MIN_TICK);
+    assert_eq!(client.get_tick_at_sqrt_price(&to_host_u256(&env, MAX_SQRT_PRICE - 1)), MAX_TICK - 1);
+    assert_eq!(client.get_tick_at_sqrt_price(&u256(&env, "79228162514264337593543950336")), 0);
+    assert_eq!(client.get_tick_at_sqrt_price(&u256(&env, "79228162514264337593543950335")), -1);
+
+    assert_eq!(
+        client.try_get_tick_at_sqrt_price(&to_host_u256(&env, MIN_SQRT_PRICE - 1)),
+        Err(Ok(RaumFiLibraryError::InvalidSqrtPrice))
+    );
+    assert_eq!(
+        client.try_get_tick_at_sqrt_price(&to_host_u256(&env, MAX_SQRT_PRICE)),
+        Err(Ok(RaumFiLibraryError::InvalidSqrtPrice))
+    );
+
+    // Every tick maps back to itself, and the price just below it to the tick before
+    let mut tick = MIN_TICK;
+    while tick <= MAX_TICK {
+        let sqrt_price = get_sqrt_price_at_tick(tick).unwrap();
+        assert_eq!(get_tick_at_sqrt_price(sqrt_price).unwrap(), tick);
+        if tick > MIN_TICK {
+            assert_eq!(get_tick_at_sqrt_price(sqrt_price - 1).unwrap(), tick - 1);
+        }
+        tick += 997;
+    }
+}
+
+#[test]
+fn test_calculate_next_sqrt_price() {
+    let env = Env::default();
+    let client = create_test_contract(&env);
+
+    let price_one = U256::from_u128(&env, 1 << 96);
+    let one = 1_000_000_000_000_000_000u128;
+    let tenth = U256::from_u128(&env, one / 10);
+
+    // Adding token1 rounds the price down, adding token0 rounds it up
+    assert_eq!(client.next_sqrt_price_from_input(&price_one, &one, &tenth, &false), u256(&env, "87150978765690771352898345369"));
+    assert_eq!(client.next_sqrt_price_from_input(&price_one, &one, &tenth, &true), u256(&env, "72025602285694852357767227579"));
+    assert_eq!(client.next_sqrt_price_from_input(&price_one, &1_000_000, &U256::from_u32(&env, 1000), &true), u256(&env, "79149013500763574019524425911"));
+    assert_eq!(client.next_sqrt_price_from_input(&price_one, &1_000_000, &U256::from_u32(&env, 1000), &false), u256(&env, "79307390676778601931137494286"));
+    // An input whose product with the price overflows falls back to the less precise formula
+    assert_eq!(
+        client.next_sqrt_price_from_input(&price_one, &(10 * one), &U256::from_u128(&env, 1 << 100), &true),
+        U256::from_u128(&env, 624_999_999_995_069_620)
+    );
+    assert_eq!(client.next_sqrt_price_from_input(&price_one, &1, &to_host_u256(&env, ethnum::U256::MAX / 2), &true), U256::from_u32(&env, 1));
+    assert_eq!(client.next_sqrt_price_from_input(&price_one, &one, &U256::from_u32(&env, 0), &true), price_one);
+    assert_eq!(
+        client.try_next_sqrt_price_from_input(&price_one, &0, &tenth, &true),
+        Err(Ok(RaumFiLibraryError::InsufficientLiquidity))
+    );
+
+    // Taking out token1 rounds the price down, taking out token0 rounds it up
+    assert_eq!(client.next_sqrt_price_from_output(&price_one, &one, &tenth, &true), u256(&env, "71305346262837903834189555302"));
+    assert_eq!(client.next_sqrt_price_from_output(&price_one, &one, &tenth, &false), u256(&env, "88031291682515930659493278152"));
+    assert_eq!(client.next_sqrt_price_from_output(&price_one, &1_000_000, &U256::from_u32(&env, 1000), &true), u256(&env, "79148934351750073255950406385"));
+    assert_eq!(client.next_sqrt_price_from_output(&price_one, &1_000_000, &U256::from_u32(&env, 1000), &false), u256(&env, "79307469984248586179723674011"));
+
+    // At a price of 256^2 and a liquidity of 1024 the virtual reserves are 4 token0 and
+    // 262144 token1, which can never be taken out entirely
+    let price = U256::from_u128(&env, 256 << 96);
+    assert_eq!(client.next_sqrt_price_from_output(&price, &1024, &U256::from_u32(&env, 262_143), &true), u256(&env, "77371252455336267181195264"));
+    assert_eq!(
+        client.try_next_sqrt_price_from_output(&price, &1024, &U256::from_u32(&env, 262_144), &true),
+        Err(Ok(RaumFiLibraryError::InsufficientLiquidity))
+    );
+    assert_eq!(
+        client.try_next_sqrt_price_from_output(&price, &1024, &U256::from_u32(&env, 4), &false),
+        Err(Ok(RaumFiLibraryError::InsufficientLiquidity))
+    );
+}
+
+#[test]
+fn test_get_amount_deltas() {
+    let env = Env::default();
+    let client = create_test_contract(&env);
+
+    let price_one = U256::from_u128(&env, 1 << 96);
+    // sqrt(1.21)
+    let price_121 = u256(&env, "87150978765690771352898345369");
+    let one = 1_000_000_000_000_000_000u128;
+
+    // Exact values 0.0909... token0 and 0.1 token1, rounded in the requested direction
+    assert_eq!(client.get_amount0_delta(&price_one, &price_121, &one, &true), U256::from_u128(&env, 90_909_090_909_090_910));
+    assert_eq!(client.get_amount0_delta(&price_one, &price_121, &one, &false), U256::from_u128(&env, 90_909_090_909_090_909));
+    assert_eq!(client.get_amount1_delta(&price_one, &price_121, &one, &true), U256::from_u128(&env, 100_000_000_000_000_000));
+    assert_eq!(client.get_amount1_delta(&price_one, &price_121, &one, &false), U256::from_u128(&env, 99_999_999_999_999_999));
+
+    // The order of the prices does not matter
+    assert_eq!(client.get_amount0_delta(&price_121, &price_one, &one, &true), U256::from_u128(&env, 90_909_090_909_090_910));
+    assert_eq!(client.get_amount1_delta(&price_121, &price_one, &one, &false), U256::from_u128(&env, 99_999_999_999_999_999));
+
+    assert_eq!(client.get_amount0_delta(&price_one, &price_one, &one, &true), U256::from_u32(&env, 0));
+    assert_eq!(client.get_amount1_delta(&price_one, &price_121, &0, &true), U256::from_u32(&env, 0));
+    assert_eq!(
+        client.try_get_amount0_delta(&U256::from_u32(&env, 0), &price_one, &one, &true),
+        Err(Ok(RaumFiLibraryError::InvalidSqrtPrice))
+    );
+
+    // Rounding up never gives less than rounding down, and differs from it by at most one
+    let mut tick = -800_000;
+    while tick <= 800_000 {
+        let lower = get_sqrt_price_at_tick(tick).unwrap();
+        let upper = get_sqrt_price_at_tick(tick + 6_000).unwrap();
+        for liquidity in [1u128, 1_000_000, one, u64::MAX as u128] {
+            let down = get_amount0_delta(lower, upper, liquidity, Rounding::Down).unwrap();
+            let up = get_amount0_delta(lower, upper, liquidity, Rounding::Up).unwrap();
+            assert!(up >= down && up - down <= 1);
+            let down = get_amount1_delta(lower, upper, liquidity, Rounding::Down).unwrap();
+            let up = get_amount1_delta(lower, upper, liquidity, Rounding::Up).unwrap();
+            assert!(up >= down && up - down <= 1);
+        }
+        tick += 40_000;
+    }
+}
+
+#[test]
+fn test_full_math() {
+    let env = Env::default();
+    let client = create_test_contract(&env);
+
+    let large: i128 = 1_000_000_000_000_000_000_000_000_000_000;
+    // 10^60 / 3 * 10^-30 keeps a result that fits i128 from an intermediate that does not
+    assert_eq!(client.mul_div_floor(&large, &large, &(3 * large)), 333_333_333_333_333_333_333_333_333_333);
+    assert_eq!(client.mul_div_ceil(&large, &large, &(3 * large)), 333_333_333_333_333_333_333_333_333_334);
+    assert_eq!(client.mul_div_floor(&6, &7, &2), 21);
+    assert_eq!(client.mul_div_ceil(&6, &7, &2), 21);
+    // Negative quotients round towards negative and positive infinity
+    assert_eq!(client.mul_div_floor(&-7, &1, &2), -4);
+    assert_eq!(client.mul_div_ceil(&-7, &1, &2), -3);
+    assert_eq!(client.try_mul_div_floor(&large, &large, &0), Err(Ok(RaumFiLibraryError::DivisionByZero)));
+    assert_eq!(client.try_mul_div_floor(&large, &large, &1), Err(Ok(RaumFiLibraryError::Overflow)));
+
+    assert_eq!(client.compare_products(&large, &large, &(large + 1), &(large - 1)), 1);
+    assert_eq!(client.compare_products(&large, &2, &(2 * large), &1), 0);
+    assert_eq!(client.compare_products(&(large - 1), &(large + 1), &large, &large), -1);
+
+    assert_eq!(client.sqrt_product(&large, &(2 * large)), 1_414_213_562_373_095_048_801_688_724_209);
+    assert_eq!(client.sqrt_product(&i128::MAX, &i128::MAX), i128::MAX);
+    assert_eq!(client.sqrt_product(&15, &1), 3);
+    assert_eq!(client.sqrt_product(&16, &1), 4);
+    assert_eq!(client.sqrt_product(&0, &large), 0);
+    assert_eq!(client.try_sqrt_product(&-1, &1), Err(Ok(RaumFiLibraryError::InsufficientAmount)));
+
+    // Products of reserves beyond i128 no longer overflow
+    assert!(client.is_constant_product_maintained(&large, &large, &(large + 1), &large));
+    assert_eq!(
+        client.calculate_liquidity_minted(&0, &large, &(2 * large), &0, &0),
+        1_414_213_562_373_095_048_801_688_723_209
+    );
+}
+
+#[test]
+fn test_calculate_price_impact_precision() {
+    let env = Env::default();
+    let client = create_test_contract(&env);
+
+    assert_eq!(client.calculate_price_impact(&100, &189, &10000, &20000), 550);
+    // A price of 4.29 * 10^-16 only keeps three digits when scaled by 10^18, which read
+    // 654 bps for this trade. Exact value 666.67 bps
+    assert_eq!(client.calculate_price_impact(&2_500_000_000_000_000, &1, &7_000_000_000_000_000, &3), 666);
+    // A trade executing above the mid price has a negative impact
+    assert_eq!(client.calculate_price_impact(&100, &225, &10000, &20000), -1250);
+    assert_eq!(
+        client.try_calculate_price_impact(&0, &189, &10000, &20000),
+        Err(Ok(RaumFiLibraryError::InsufficientAmount))
+    );
+    assert_eq!(
+        client.try_calculate_price_impact(&100, &189, &0, &20000),
+        Err(Ok(RaumFiLibraryError::InsufficientLiquidity))
+    );
+}
+
+#[test]
+fn test_uq64x64() {
+    let half = UQ64x64::encode(1, 2).unwrap();
+    assert_eq!(half.raw(), ethnum::U256::ONE << 63);
+    assert_eq!(half.to_decimal(18).unwrap(), 500_000_000_000_000_000);
+    assert_eq!(UQ64x64::from_decimal(500_000_000_000_000_000, 18).unwrap(), half);
+    assert_eq!(UQ64x64::encode(7, 2).unwrap().decode().unwrap(), 3);
+
+    // 3/2 * 2/3 loses the last bit of 2/3, rounded down
+    let product = UQ64x64::encode(3, 2).unwrap().checked_mul(UQ64x64::encode(2, 3).unwrap()).unwrap();
+    assert_eq!(product.raw(), ethnum::U256::new(u64::MAX as u128));
+    assert_eq!(UQ64x64::encode(3, 2).unwrap().checked_div(half).unwrap(), UQ64x64::encode(3, 1).unwrap());
+    assert_eq!(half.reciprocal().unwrap(), UQ64x64::encode(2, 1).unwrap());
+    assert_eq!(UQ64x64::encode(1, 3).unwrap().reciprocal().unwrap().to_decimal(18).unwrap(), 3_000_000_000_000_000_000);
+    assert_eq!(UQ64x64::encode(3, 1).unwrap().mul_amount(1_000_001).unwrap(), 3_000_003);
+    assert_eq!(half.mul_amount(3).unwrap(), 1);
+
+    // 0.06 units of a 6-decimal token per unit of an 18-decimal token is 0.06 * 10^-12
+    // per whole token: with 18 decimals, 59999 rather than 60000 after two roundings
+    let price = UQ64x64::encode(6, 100).unwrap().adjust_decimals(6, 18).unwrap();
+    assert_eq!(price.to_decimal(18).unwrap(), 59_999);
+
+    assert_eq!(UQ64x64::ZERO.reciprocal(), Err(RaumFiLibraryError::DivisionByZero));
+    assert_eq!(UQ64x64::encode(1, 0), Err(RaumFiLibraryError::DivisionByZero));
+    assert_eq!(UQ64x64::encode(-1, 2), Err(RaumFiLibraryError::InsufficientAmount));
+    assert_eq!(half.to_decimal(MAX_DECIMALS + 1), Err(RaumFiLibraryError::Overflow));
+    assert_eq!(UQ64x64::from_raw(ethnum::U256::MAX).decode(), Err(RaumFiLibraryError::Overflow));
+}
+
+#[test]
+fn test_encode_price_and_twap() {
+    let env = Env::default();
+    let client = create_test_contract(&env);
+
+    let price_2 = client.encode_price(&20000, &10000);
+    assert_eq!(price_2, U256::from_u128(&env, 2 << 64));
+    assert_eq!(client.price_to_decimal(&price_2, &7), 20_000_000);
+
+    // A price of 2 for 10 seconds, then 3 for 30 seconds, averages 2.75
+    let start = U256::from_u128(&env, 123 << 64);
+    let end = start.add(&price_2.mul(&U256::from_u32(&env, 10))).add(&client.encode_price(&3, &1).mul(&U256::from_u32(&env, 30)));
+    let twap = client.calculate_twap(&start, &end, &40);
+    assert_eq!(client.price_to_decimal(&twap, &2), 275);
+    assert_eq!(client.try_calculate_twap(&start, &end, &0), Err(Ok(RaumFiLibraryError::DivisionByZero)));
+}
@@ -0,0 +1,353 @@
+//! Weighted pool math, after Balancer's weighted invariant:
+//!
+//! `V = prod(B_i ^ w_i)` with the normalized weights `w_i` summing to one
+//!
+//! Ratios and powers are 18-decimal fixed point numbers. Weights are expressed in
+//! basis points and must sum to `WEIGHT_DENOMINATOR`. Every result is rounded in
+//! favor of the pool.
+
+use soroban_sdk::Vec;
+
+use crate::error::RaumFiLibraryError;
+use crate::library::FEE_DENOMINATOR;
+
+/// 1.0 in 18-decimal fixed point
+pub const ONE: i128 = 1_000_000_000_000_000_000;
+/// Weights are expressed in basis points of the whole pool
+pub const WEIGHT_DENOMINATOR: u32 = 10_000;
+/// Smallest weight a token may have in a pool (1%)
+pub const MIN_WEIGHT: u32 = 100;
+/// A swap may add at most 30% of the input token's balance...
+pub const MAX_IN_RATIO: i128 = 300_000_000_000_000_000;
+/// ...and take at most 30% of the output token's balance
+pub const MAX_OUT_RATIO: i128 = 300_000_000_000_000_000;
+/// A single-asset exit may burn at most 30% of the LP supply
+pub const MIN_INVARIANT_RATIO: i128 = 700_000_000_000_000_000;
+
+/// ln(2) in 18-decimal fixed point
+const LN_2: i128 = 693_147_180_559_945_309;
+/// Bound on the relative error of `pow`, added or removed to round it in the pool's favor
+const MAX_POW_RELATIVE_ERROR: i128 = 10_000;
+
+fn mul_down(a: i128, b: i128) -> Result<i128, RaumFiLibraryError> {
+    // Split `a` so that neither partial product overflows for large exponents
+    let high = (a / ONE).checked_mul(b).ok_or(RaumFiLibraryError::Overflow)?;
+    let low = (a % ONE).checked_mul(b).ok_or(RaumFiLibraryError::Overflow)? / ONE;
+    high.checked_add(low).ok_or(RaumFiLibraryError::Overflow)
+}
+
+fn mul_up(a: i128, b: i128) -> Result<i128, RaumFiLibraryError> {
+    let product = a.checked_mul(b).ok_or(RaumFiLibraryError::Overflow)?;
+    if product == 0 {
+        return Ok(0);
+    }
+    Ok((product - 1) / ONE + 1)
+}
+
+fn div_down(a: i128, b: i128) -> Result<i128, RaumFiLibraryError> {
+    if b == 0 {
+        return Err(RaumFiLibraryError::DivisionByZero);
+    }
+    Ok(a.checked_mul(ONE).ok_or(RaumFiLibraryError::Overflow)? / b)
+}
+
+fn div_up(a: i128, b: i128) -> Result<i128, RaumFiLibraryError> {
+    if b == 0 {
+        return Err(RaumFiLibraryError::DivisionByZero);
+    }
+    if a == 0 {
+        return Ok(0);
+    }
+    Ok((a.checked_mul(ONE).ok_or(RaumFiLibraryError::Overflow)? - 1) / b + 1)
+}
+
+fn complement(x: i128) -> i128 {
+    if x < ONE { ONE - x } else { 0 }
+}
+
+fn normalized_weight(weight: u32) -> i128 {
+    weight as i128 * ONE / WEIGHT_DENOMINATOR as i128
+}
+
+fn fee_ratio(fee: u32) -> i128 {
+    fee as i128 * ONE / FEE_DENOMINATOR
+}
+
+/// Natural logarithm of a positive fixed point number
+fn ln(x: i128) -> Result<i128, RaumFiLibraryError> {
+    if x <= 0 {
+        return Err(RaumFiLibraryError::InsufficientAmount);
+    }
+    // Bring x into [1, 2) so that ln(x) = k * ln(2) + ln(m)
+    let mut m = x;
+    let mut k: i128 = 0;
+    while m >= 2 * ONE {
+        m /= 2;
+        k += 1;
+    }
+    while m < ONE {
+        m *= 2;
+        k -= 1;
+    }
+
+    // ln(m) = 2 * atanh(z) = 2 * (z + z^3 / 3 + z^5 / 5 + ...) with z = (m - 1) / (m + 1) < 1/3
+    let z = (m - ONE) * ONE / (m + ONE);
+    let z_squared = z * z / ONE;
+    let mut term = z;
+    let mut sum = z;
+    let mut n = 3;
+    loop {
+        term = term * z_squared / ONE;
+        if term == 0 {
+            break;
+        }
+        sum += term / n;
+        n += 2;
+    }
+    Ok(k * LN_2 + 2 * sum)
+}
+
+/// Exponential of a fixed point number
+fn exp(x: i128) -> Result<i128, RaumFiLibraryError> {
+    // x = k * ln(2) + r with 0 <= r < ln(2), so that e^x = 2^k * e^r
+    let k = x.div_euclid(LN_2);
+    let r = x.rem_euclid(LN_2);
+
+    let mut term = ONE;
+    let mut sum = ONE;
+    let mut n = 1;
+    loop {
+        term = term * r / ONE / n;
+        if term == 0 {
+            break;
+        }
+        sum += term;
+        n += 1;
+    }
+
+    if k >= 0 {
+        if k > 126 {
+            return Err(RaumFiLibraryError::Overflow);
+        }
+        sum.checked_mul(1i128 << k).ok_or(RaumFiLibraryError::Overflow)
+    } else if k < -126 {
+        Ok(0)
+    } else {
+        Ok(sum >> -k)
+    }
+}
+
+fn pow(x: i128, y: i128) -> Result<i128, RaumFiLibraryError> {
+    if y == 0 {
+        return Ok(ONE);
+    }
+    exp(mul_down(ln(x)?, y)?)
+}
+
+fn pow_up(x: i128, y: i128) -> Result<i128, RaumFiLibraryError> {
+    let raw = pow(x, y)?;
+    Ok(raw + mul_up(raw, MAX_POW_RELATIVE_ERROR)? + 1)
+}
+
+fn pow_down(x: i128, y: i128) -> Result<i128, RaumFiLibraryError> {
+    let raw = pow(x, y)?;
+    Ok((raw - mul_up(raw, MAX_POW_RELATIVE_ERROR)? - 1).max(0))
+}
+
+/// Checks that a pool has between 2 and 8 weights, each at least `MIN_WEIGHT`,
+/// summing to `WEIGHT_DENOMINATOR`
+pub fn validate_weights(weights: &Vec<u32>) -> Result<(), RaumFiLibraryError> {
+    if weights.len() < 2 || weights.len() > 8 {
+        return Err(RaumFiLibraryError::InvalidWeight);
+    }
+    let mut total: u32 = 0;
+    for weight in weights.iter() {
+        if weight < MIN_WEIGHT {
+            return Err(RaumFiLibraryError::InvalidWeight);
+        }
+        total = total.checked_add(weight).ok_or(RaumFiLibraryError::InvalidWeight)?;
+    }
+    if total != WEIGHT_DENOMINATOR {
+        return Err(RaumFiLibraryError::InvalidWeight);
+    }
+    Ok(())
+}
+
+// Calculate the weighted invariant prod(balance_i ^ weight_i), in token units
+pub fn calculate_weighted_invariant(balances: Vec<i128>, weights: Vec<u32>) -> Result<i128, RaumFiLibraryError> {
+    validate_weights(&weights)?;
+    if balances.len() != weights.len() {
+        return Err(RaumFiLibraryError::InvalidWeight);
+    }
+    // Summing logarithms keeps the intermediate product from overflowing
+    let mut ln_invariant: i128 = 0;
+    for (balance, weight) in balances.iter().zip(weights.iter()) {
+        if balance <= 0 {
+            return Err(RaumFiLibraryError::InsufficientLiquidity);
+        }
+        let ln_balance = ln(balance.checked_mul(ONE).ok_or(RaumFiLibraryError::Overflow)?)?;
+        ln_invariant += mul_down(ln_balance, normalized_weight(weight))?;
+    }
+    Ok(exp(ln_invariant)? / ONE)
+}
+
+// Calculate the spot price of token in, in units of token out, as an 18-decimal fixed point number
+pub fn calculate_weighted_spot_price(
+    balance_in: i128,
+    weight_in: u32,
+    balance_out: i128,
+    weight_out: u32,
+) -> Result<i128, RaumFiLibraryError> {
+    if balance_in <= 0 || balance_out <= 0 {
+        return Err(RaumFiLibraryError::InsufficientLiquidity);
+    }
+    if weight_in == 0 || weight_out == 0 {
+        return Err(RaumFiLibraryError::InvalidWeight);
+    }
+    let numerator = balance_out.checked_mul(weight_in as i128).ok_or(RaumFiLibraryError::Overflow)?;
+    let denominator = balance_in.checked_mul(weight_out as i128).ok_or(RaumFiLibraryError::Overflow)?;
+    div_down(numerator, denominator)
+}
+
+// Calculate the output of a weighted pool swap charging `fee` basis points of the input
+pub fn calculate_weighted_amount_out(
+    amount_in: i128,
+    balance_in: i128,
+    weight_in: u32,
+    balance_out: i128,
+    weight_out: u32,
+    fee: u32,
+) -> Result<i128, RaumFiLibraryError> {
+    if amount_in <= 0 {
+        return Err(RaumFiLibraryError::InsufficientAmount);
+    }
+    if balance_in <= 0 || balance_out <= 0 {
+        return Err(RaumFiLibraryError::InsufficientLiquidity);
+    }
+    if weight_in == 0 || weight_out == 0 {
+        return Err(RaumFiLibraryError::InvalidWeight);
+    }
+    if amount_in > mul_down(balance_in, MAX_IN_RATIO)? {
+        return Err(RaumFiLibraryError::MaxInRatio);
+    }
+
+    // The fee is taken from the input, rounded up
+    let fee_amount = mul_up(amount_in, fee_ratio(fee))?;
+    let amount_in_after_fee = amount_in - fee_amount;
+
+    // amount_out = balance_out * (1 - (balance_in / (balance_in + amount_in)) ^ (weight_in / weight_out))
+    let base = div_up(balance_in, balance_in + amount_in_after_fee)?;
+    let exponent = div_down(weight_in as i128, weight_out as i128)?;
+    let power = pow_up(base, exponent)?;
+    mul_down(balance_out, complement(power))
+}
+
+// Calculate the input a weighted pool swap charging `fee` basis points needs for `amount_out`
+pub fn calculate_weighted_amount_in(
+    amount_out: i128,
+    balance_in: i128,
+    weight_in: u32,
+    balance_out: i128,
+    weight_out: u32,
+    fee: u32,
+) -> Result<i128, RaumFiLibraryError> {
+    if amount_out <= 0 {
+        return Err(RaumFiLibraryError::InsufficientAmount);
+    }
+    if balance_in <= 0 || balance_out <= 0 {
+        return Err(RaumFiLibraryError::InsufficientLiquidity);
+    }
+    if weight_in == 0 || weight_out == 0 {
+        return Err(RaumFiLibraryError::InvalidWeight);
+    }
+    if amount_out > mul_down(balance_out, MAX_OUT_RATIO)? {
+        return Err(RaumFiLibraryError::MaxOutRatio);
+    }
+
+    // amount_in = balance_in * ((balance_out / (balance_out - amount_out)) ^ (weight_out / weight_in) - 1)
+    let base = div_up(balance_out, balance_out - amount_out)?;
+    let exponent = div_up(weight_out as i128, weight_in as i128)?;
+    let power = pow_up(base, exponent)?;
+    let amount_in_after_fee = mul_up(balance_in, power - ONE)?;
+
+    // Gross the input up by the fee, rounded up
+    let numerator = amount_in_after_fee
+        .checked_mul(FEE_DENOMINATOR)
+        .ok_or(RaumFiLibraryError::Overflow)?;
+    let denominator = FEE_DENOMINATOR - fee as i128;
+    Ok((numerator + denominator - 1) / denominator)
+}
+
+// Calculate the LP tokens minted for depositing `amount_in` of a single token. The part of
+// the deposit beyond a proportional join pays the swap fee.
+pub fn calculate_weighted_single_join(
+    amount_in: i128,
+    balance_in: i128,
+    weight_in: u32,
+    total_supply: i128,
+    fee: u32,
+) -> Result<i128, RaumFiLibraryError> {
+    if amount_in <= 0 {
+        return Err(RaumFiLibraryError::InsufficientAmount);
+    }
+    if balance_in <= 0 || total_supply <= 0 {
+        return Err(RaumFiLibraryError::InsufficientLiquidity);
+    }
+    if amount_in > mul_down(balance_in, MAX_IN_RATIO)? {
+        return Err(RaumFiLibraryError::MaxInRatio);
+    }
+
+    let weight = normalized_weight(weight_in);
+    let balance_ratio = div_down(balance_in + amount_in, balance_in)?;
+    // Growth of the invariant had the deposit been spread proportionally over all tokens
+    let invariant_ratio_without_fee = mul_down(balance_ratio, weight)? + complement(weight);
+
+    let amount_in_without_fee = if balance_ratio > invariant_ratio_without_fee {
+        let non_taxable = mul_down(balance_in, invariant_ratio_without_fee - ONE)?;
+        let taxable = amount_in - non_taxable;
+        non_taxable + mul_down(taxable, complement(fee_ratio(fee)))?
+    } else {
+        amount_in
+    };
+
+    let balance_ratio = div_down(balance_in + amount_in_without_fee, balance_in)?;
+    let invariant_ratio = pow_down(balance_ratio, weight)?;
+    if invariant_ratio <= ONE {
+        return Ok(0);
+    }
+    mul_down(total_supply, invariant_ratio - ONE)
+}
+
+// Calculate the amount of a single token paid out for burning `liquidity`. The part of the
+// exit beyond a proportional exit pays the swap fee.
+pub fn calculate_weighted_single_exit(
+    liquidity: i128,
+    balance_out: i128,
+    weight_out: u32,
+    total_supply: i128,
+    fee: u32,
+) -> Result<i128, RaumFiLibraryError> {
+    if liquidity <= 0 {
+        return Err(RaumFiLibraryError::InsufficientAmount);
+    }
+    if liquidity > total_supply {
+        return Err(RaumFiLibraryError::InsufficientTotalSupply);
+    }
+    if balance_out <= 0 {
+        return Err(RaumFiLibraryError::InsufficientLiquidity);
+    }
+
+    let invariant_ratio = div_up(total_supply - liquidity, total_supply)?;
+    if invariant_ratio < MIN_INVARIANT_RATIO {
+        return Err(RaumFiLibraryError::MaxOutRatio);
+    }
+
+    // amount_out = balance_out * (1 - invariant_ratio ^ (1 / weight_out))
+    let weight = normalized_weight(weight_out);
+    let balance_ratio = pow_up(invariant_ratio, div_down(ONE, weight)?)?;
+    let amount_out_without_fee = mul_down(balance_out, complement(balance_ratio))?;
+
+    let taxable = mul_up(amount_out_without_fee, complement(weight))?;
+    let non_taxable = amount_out_without_fee - taxable;
+    Ok(non_taxable + mul_down(taxable, complement(fee_ratio(fee)))?)
+}
@@ -0,0 +1,220 @@
+//! Balances, allowances and supply of the LP token that every RaumFiDex pool issues
+//! for its own shares.
+//!
+//! The pair, the stable pair and the weighted pool each are their own SEP-41 token. The
+//! storage layout, the TTL handling and the events are the same for all of them; what
+//! differs is the metadata, written once at initialization, and the error codes, which
+//! each pool keeps in its own error enum. Both are supplied by the pool: the metadata
+//! through [`LpToken::write_metadata`] and the errors through [`LpTokenConfig`].
+
+#![no_std]
+
+use core::marker::PhantomData;
+
+use soroban_sdk::{contracttype, panic_with_error, symbol_short, Address, Env, IntoVal, Symbol, Val};
+use soroban_token_sdk::{metadata::TokenMetadata, TokenUtils};
+
+#[cfg(test)]
+mod test;
+
+const DAY_IN_LEDGERS: u32 = 17280;
+const PERSISTENT_BUMP_AMOUNT: u32 = 30 * DAY_IN_LEDGERS;
+const PERSISTENT_LIFETIME_THRESHOLD: u32 = PERSISTENT_BUMP_AMOUNT - DAY_IN_LEDGERS;
+
+/// Decimals of every pool's LP token
+pub const LP_TOKEN_DECIMALS: u32 = 7;
+
+pub const TOTAL_SUPPLY_KEY: Symbol = symbol_short!("totspply");
+
+#[derive(Clone)]
+#[contracttype]
+pub struct AllowanceDataKey {
+    pub from: Address,
+    pub spender: Address,
+}
+
+#[derive(Clone)]
+#[contracttype]
+pub enum LpTokenDataKey {
+    Balance(Address),
+    Allowance(AllowanceDataKey),
+}
+
+#[derive(Clone, Default)]
+#[contracttype]
+pub struct AllowanceValue {
+    pub amount: i128,
+    pub expiration_ledger: u32,
+}
+
+/// What a pool plugs into [`LpToken`]: the errors it raises, and where an older version
+/// of the pool may have left balances and allowances.
+pub trait LpTokenConfig {
+    type Error: Copy + Into<soroban_sdk::Error>;
+
+    const INSUFFICIENT_BALANCE: Self::Error;
+    const INSUFFICIENT_ALLOWANCE: Self::Error;
+    const NEGATIVE_NOT_ALLOWED: Self::Error;
+    const PAST_TIME_NOT_ALLOWED: Self::Error;
+
+    /// Balance of `id` kept outside persistent storage by an older version of the pool,
+    /// read when persistent storage has none
+    fn legacy_balance(_e: &Env, _id: &Address) -> Option<i128> {
+        None
+    }
+
+    /// Drops the legacy balance of `id` once persistent storage holds it
+    fn remove_legacy_balance(_e: &Env, _id: &Address) {}
+
+    /// Drops a legacy allowance superseded by a new approval
+    fn remove_legacy_allowance(_e: &Env, _from: &Address, _spender: &Address) {}
+}
+
+/// The LP token of a pool configured by `C`. Balances live in persistent storage and
+/// allowances in temporary storage.
+pub struct LpToken<C: LpTokenConfig>(PhantomData<C>);
+
+impl<C: LpTokenConfig> LpToken<C> {
+    fn bump_persistent<K: IntoVal<Env, Val>>(e: &Env, key: &K) {
+        e.storage()
+            .persistent()
+            .extend_ttl(key, PERSISTENT_LIFETIME_THRESHOLD, PERSISTENT_BUMP_AMOUNT);
+    }
+
+    pub fn check_nonnegative_amount(e: &Env, amount: i128) {
+        if amount < 0 {
+            panic_with_error!(e, C::NEGATIVE_NOT_ALLOWED);
+        }
+    }
+
+    pub fn write_metadata(e: &Env, metadata: TokenMetadata) {
+        TokenUtils::new(e).metadata().set_metadata(&metadata);
+    }
+
+    pub fn read_metadata(e: &Env) -> TokenMetadata {
+        TokenUtils::new(e).metadata().get_metadata()
+    }
+
+    pub fn total_supply(e: &Env) -> i128 {
+        e.storage().instance().get(&TOTAL_SUPPLY_KEY).unwrap_or(0)
+    }
+
+    fn set_total_supply(e: &Env, amount: i128) {
+        e.storage().instance().set(&TOTAL_SUPPLY_KEY, &amount);
+    }
+
+    pub fn balance(e: &Env, id: &Address) -> i128 {
+        let key = LpTokenDataKey::Balance(id.clone());
+        match e.storage().persistent().get::<_, i128>(&key) {
+            Some(balance) => {
+                Self::bump_persistent(e, &key);
+                balance
+            }
+            None => C::legacy_balance(e, id).unwrap_or(0),
+        }
+    }
+
+    fn set_balance(e: &Env, id: &Address, amount: i128) {
+        let key = LpTokenDataKey::Balance(id.clone());
+        e.storage().persistent().set(&key, &amount);
+        Self::bump_persistent(e, &key);
+        if C::legacy_balance(e, id).is_some() {
+            C::remove_legacy_balance(e, id);
+        }
+    }
+
+    /// Moves a legacy balance of `id` into persistent storage. Returns false when there
+    /// was nothing to move.
+    pub fn migrate_balance(e: &Env, id: &Address) -> bool {
+        match C::legacy_balance(e, id) {
+            Some(balance) => {
+                Self::set_balance(e, id, balance);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Restores a total supply that an older pool left negative, having subtracted minted
+    /// LP from it and added burned LP back, to the sum of the balances. Returns false when
+    /// the supply was not negative.
+    pub fn migrate_total_supply(e: &Env) -> bool {
+        let total_supply = Self::total_supply(e);
+        if total_supply >= 0 {
+            return false;
+        }
+        Self::set_total_supply(e, total_supply.checked_neg().expect("Integer overflow occurred"));
+        true
+    }
+
+    pub fn allowance(e: &Env, from: &Address, spender: &Address) -> AllowanceValue {
+        let key = LpTokenDataKey::Allowance(AllowanceDataKey { from: from.clone(), spender: spender.clone() });
+        match e.storage().temporary().get::<_, AllowanceValue>(&key) {
+            Some(allowance) if allowance.expiration_ledger < e.ledger().sequence() => AllowanceValue {
+                amount: 0,
+                expiration_ledger: allowance.expiration_ledger,
+            },
+            Some(allowance) => allowance,
+            None => AllowanceValue::default(),
+        }
+    }
+
+    pub fn set_allowance(e: &Env, from: &Address, spender: &Address, amount: i128, expiration_ledger: u32) {
+        if amount > 0 && expiration_ledger < e.ledger().sequence() {
+            panic_with_error!(e, C::PAST_TIME_NOT_ALLOWED);
+        }
+        let key = LpTokenDataKey::Allowance(AllowanceDataKey { from: from.clone(), spender: spender.clone() });
+        e.storage().temporary().set(&key, &AllowanceValue { amount, expiration_ledger });
+        if amount > 0 {
+            let live_for = expiration_ledger - e.ledger().sequence();
+            e.storage().temporary().extend_ttl(&key, live_for, live_for);
+        }
+        C::remove_legacy_allowance(e, from, spender);
+    }
+
+    /// Sets the allowance of `spender` over the LP tokens of `from`, and emits the
+    /// SEP-41 approve event
+    pub fn approve(e: &Env, from: &Address, spender: &Address, amount: i128, expiration_ledger: u32) {
+        Self::check_nonnegative_amount(e, amount);
+        Self::set_allowance(e, from, spender, amount, expiration_ledger);
+        TokenUtils::new(e).events().approve(from.clone(), spender.clone(), amount, expiration_ledger);
+    }
+
+    pub fn spend_allowance(e: &Env, from: &Address, spender: &Address, amount: i128) {
+        let allowance = Self::allowance(e, from, spender);
+        if allowance.amount < amount {
+            panic_with_error!(e, C::INSUFFICIENT_ALLOWANCE);
+        }
+        if amount > 0 {
+            Self::set_allowance(e, from, spender, allowance.amount - amount, allowance.expiration_ledger);
+        }
+    }
+
+    pub fn mint(e: &Env, to: &Address, amount: i128) {
+        let balance = Self::balance(e, to);
+        Self::set_balance(e, to, balance.checked_add(amount).expect("Integer overflow occurred"));
+        Self::set_total_supply(e, Self::total_supply(e).checked_add(amount).expect("Integer overflow occurred"));
+        TokenUtils::new(e).events().mint(e.current_contract_address(), to.clone(), amount);
+    }
+
+    pub fn burn(e: &Env, from: &Address, amount: i128) {
+        let balance = Self::balance(e, from);
+        if balance < amount {
+            panic_with_error!(e, C::INSUFFICIENT_BALANCE);
+        }
+        Self::set_balance(e, from, balance - amount);
+        Self::set_total_supply(e, Self::total_supply(e) - amount);
+        TokenUtils::new(e).events().burn(from.clone(), amount);
+    }
+
+    pub fn transfer(e: &Env, from: &Address, to: &Address, amount: i128) {
+        let from_balance = Self::balance(e, from);
+        if from_balance < amount {
+            panic_with_error!(e, C::INSUFFICIENT_BALANCE);
+        }
+        Self::set_balance(e, from, from_balance - amount);
+        let to_balance = Self::balance(e, to);
+        Self::set_balance(e, to, to_balance + amount);
+        TokenUtils::new(e).events().transfer(from.clone(), to.clone(), amount);
+    }
+}
@@ -0,0 +1,110 @@
+#![cfg(test)]
+extern crate std;
+
+use soroban_sdk::{contract, contracterror, testutils::Address as _, Address, Env};
+
+use crate::*;
+
+#[contracterror]
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+#[repr(u32)]
+enum TestError {
+    InsufficientBalance = 1,
+    InsufficientAllowance = 2,
+    NegativeNotAllowed = 3,
+    PastTimeNotAllowed = 4,
+}
+
+struct TestLp;
+
+impl LpTokenConfig for TestLp {
+    type Error = TestError;
+
+    const INSUFFICIENT_BALANCE: TestError = TestError::InsufficientBalance;
+    const INSUFFICIENT_ALLOWANCE: TestError = TestError::InsufficientAllowance;
+    const NEGATIVE_NOT_ALLOWED: TestError = TestError::NegativeNotAllowed;
+    const PAST_TIME_NOT_ALLOWED: TestError = TestError::PastTimeNotAllowed;
+}
+
+type TestToken = LpToken<TestLp>;
+
+#[contract]
+struct TestPool;
+
+#[test]
+fn test_mint_transfer_burn() {
+    let env = Env::default();
+    let pool = env.register_contract(None, TestPool);
+    let user_1 = Address::generate(&env);
+    let user_2 = Address::generate(&env);
+
+    env.as_contract(&pool, || {
+        TestToken::mint(&env, &user_1, 1_000);
+        TestToken::transfer(&env, &user_1, &user_2, 400);
+        TestToken::burn(&env, &user_2, 100);
+
+        assert_eq!(TestToken::balance(&env, &user_1), 600);
+        assert_eq!(TestToken::balance(&env, &user_2), 300);
+        assert_eq!(TestToken::total_supply(&env), 900);
+        assert!(env.storage().persistent().has(&LpTokenDataKey::Balance(user_1.clone())));
+    });
+}
+
+#[test]
+fn test_migrate_total_supply() {
+    let env = Env::default();
+    let pool = env.register_contract(None, TestPool);
+
+    env.as_contract(&pool, || {
+        env.storage().instance().set(&TOTAL_SUPPLY_KEY, &-900_i128);
+        assert!(TestToken::migrate_total_supply(&env));
+        assert_eq!(TestToken::total_supply(&env), 900);
+        assert!(!TestToken::migrate_total_supply(&env));
+        assert_eq!(TestToken::total_supply(&env), 900);
+    });
+}
+
+#[test]
+fn test_spend_allowance() {
+    let env = Env::default();
+    let pool = env.register_contract(None, TestPool);
+    let owner = Address::generate(&env);
+    let spender = Address::generate(&env);
+
+    env.as_contract(&pool, || {
+        TestToken::approve(&env, &owner, &spender, 500, 200);
+        TestToken::spend_allowance(&env, &owner, &spender, 200);
+
+        let allowance = TestToken::allowance(&env, &owner, &spender);
+        assert_eq!(allowance.amount, 300);
+        assert_eq!(allowance.expiration_ledger, 200);
+    });
+}
+
+#[test]
+#[should_panic(expected = "Error(Contract, #1)")]
+fn test_transfer_insufficient_balance() {
+    let env = Env::default();
+    let pool = env.register_contract(None, TestPool);
+    let user_1 = Address::generate(&env);
+    let user_2 = Address::generate(&env);
+
+    env.as_contract(&pool, || {
+        TestToken::mint(&env, &user_1, 100);
+        TestToken::transfer(&env, &user_1, &user_2, 101);
+    });
+}
+
+#[test]
+#[should_panic(expected = "Error(Contract, #2)")]
+fn test_spend_allowance_insufficient() {
+    let env = Env::default();
+    let pool = env.register_contract(None, TestPool);
+    let owner = Address::generate(&env);
+    let spender = Address::generate(&env);
+
+    env.as_contract(&pool, || {
+        TestToken::approve(&env, &owner, &spender, 100, 200);
+        TestToken::spend_allowance(&env, &owner, &spender, 101);
+    });
+}
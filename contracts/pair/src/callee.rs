@@ -0,0 +1,10 @@
+use soroban_sdk::{contractclient, Address, Bytes, Env};
+
+/// Interface that a contract must implement to receive flash swaps from a RaumFi pair.
+#[contractclient(name = "RaumFiCalleeClient")]
+pub trait RaumFiCalleeTrait {
+    /// Called by the pair after the outputs of `swap_with_data` have been transferred to the
+    /// callee. Before returning, the callee must pay the pair back enough input for the
+    /// constant product (net of fees) to hold, or the whole swap reverts.
+    fn raumfi_call(env: Env, sender: Address, amount0_out: i128, amount1_out: i128, data: Bytes);
+}
@@ -0,0 +1,126 @@
+//! Price circuit breaker, against oracle manipulation and the tail of a token exploit.
+//!
+//! Swaps may move the spot price at most `max_ledger_move` within a ledger, measured
+//! from the price before its first swap, and at most `max_window_move` from any price it
+//! stood at within the last `window` seconds. The window slides: it keeps the price
+//! before the first swap of each eighth of it, and forgets each once it is a window old.
+//! A swap crossing the ledger bound is rejected. The swap crossing the window bound goes
+//! through, but pauses the pair and emits `CircuitBreak` until the factory resumes it.
+
+use soroban_sdk::{contracttype, Env, Symbol, Vec, U256};
+
+use crate::error::RaumFiPairError;
+use crate::pair::DataKey;
+
+/// Basis points in which price moves are measured
+const BPS: u32 = 10_000;
+/// Slices of the window that each keep a reference price
+const WINDOW_SLICES: u64 = 8;
+
+/// Price bounds of a pair's circuit breaker
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct CircuitBreakerConfig {
+    /// Largest move of the spot price within a ledger, in basis points
+    pub max_ledger_move: u32,
+    /// Largest move of the spot price within `window`, in basis points
+    pub max_window_move: u32,
+    /// Length of the window, in seconds
+    pub window: u64,
+}
+
+/// UQ64x64 price of token0 in token1 at `timestamp`
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct PricePoint {
+    pub price: U256,
+    pub timestamp: u64,
+}
+
+/// Prices the circuit breaker measures moves from
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct CircuitBreakerState {
+    /// UQ64x64 price of token0 in token1 before the first swap of `ledger`
+    pub ledger_price: U256,
+    pub ledger: u32,
+    /// Price before the first swap of each slice of the window, oldest first
+    pub window_prices: Vec<PricePoint>,
+}
+
+pub fn read_config(env: &Env) -> Option<CircuitBreakerConfig> {
+    env.storage().instance().get(&DataKey::CircuitBreaker)
+}
+
+/// Forgets the reference prices, so that moves are measured afresh from the next swap
+pub fn reset(env: &Env) {
+    env.storage().instance().remove(&DataKey::CircuitBreakerState);
+}
+
+/// Move from `from` to `to`, in basis points of `from`
+fn price_move(env: &Env, from: &U256, to: &U256) -> u32 {
+    let diff = if to > from { to.sub(from) } else { from.sub(to) };
+    diff.mul(&U256::from_u32(env, BPS))
+        .div(from)
+        .to_u128()
+        .map_or(u32::MAX, |change| change.min(u32::MAX as u128) as u32)
+}
+
+/// Checks a swap, or a sync, taking the spot price from `price_before` to `price_after`.
+/// Rejects it when it moves the price of the current ledger too far, and pauses the pair
+/// when it moves the price at some point of the window too far.
+pub fn check_swap(env: &Env, price_before: &U256, price_after: &U256) -> Result<(), RaumFiPairError> {
+    let Some(config) = read_config(env) else {
+        return Ok(());
+    };
+    if *price_before == U256::from_u32(env, 0) {
+        return Ok(());
+    }
+
+    let ledger = env.ledger().sequence();
+    let now = env.ledger().timestamp();
+    let mut state: CircuitBreakerState = env.storage().instance().get(&DataKey::CircuitBreakerState).unwrap_or(CircuitBreakerState {
+        ledger_price: price_before.clone(),
+        ledger,
+        window_prices: Vec::new(env),
+    });
+    if state.ledger != ledger {
+        state.ledger_price = price_before.clone();
+        state.ledger = ledger;
+    }
+    while let Some(oldest) = state.window_prices.first() {
+        if oldest.timestamp.saturating_add(config.window) > now {
+            break;
+        }
+        state.window_prices.pop_front();
+    }
+    let slice = (config.window / WINDOW_SLICES).max(1);
+    if state.window_prices.last().map_or(true, |latest| latest.timestamp / slice != now / slice) {
+        state.window_prices.push_back(PricePoint { price: price_before.clone(), timestamp: now });
+    }
+
+    if price_move(env, &state.ledger_price, price_after) > config.max_ledger_move {
+        return Err(RaumFiPairError::PriceMoveTooLarge);
+    }
+    for point in state.window_prices.iter() {
+        let window_move = price_move(env, &point.price, price_after);
+        if window_move > config.max_window_move {
+            trip(env, &point.price, price_after, window_move);
+            return Ok(());
+        }
+    }
+
+    env.storage().instance().set(&DataKey::CircuitBreakerState, &state);
+    Ok(())
+}
+
+/// Pauses the pair after its price moved `window_move` from `reference` to `price`
+fn trip(env: &Env, reference: &U256, price: &U256, window_move: u32) {
+    env.storage().instance().set(&DataKey::Paused, &true);
+    reset(env);
+
+    env.events().publish(
+        (Symbol::new(env, "RaumFiPair"), Symbol::new(env, "CircuitBreak")),
+        (reference.clone(), price.clone(), window_move)
+    );
+}
@@ -0,0 +1,63 @@
+use soroban_sdk::{contracttype, Env, U256};
+
+use crate::pair::DataKey;
+
+/// Basis points in which price moves and fees are measured
+const BPS: u32 = 10_000;
+/// Number of ledger observations the volatility average is smoothed over
+pub const VOLATILITY_EMA_WINDOW: u32 = 8;
+
+/// Fee bounds of a pair in dynamic-fee mode, in basis points
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct DynamicFeeConfig {
+    pub min_fee: u32,
+    pub max_fee: u32,
+}
+
+/// Exponential moving average of the per-ledger spot price move, and the
+/// price observed at the first swap of the last ledger it was updated in
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct VolatilityState {
+    /// Average price move between observations, in basis points
+    pub ema: u32,
+    /// UQ64x64 price of token0 in token1
+    pub price: U256,
+    pub ledger: u32,
+}
+
+pub fn read_config(env: &Env) -> Option<DynamicFeeConfig> {
+    env.storage().instance().get(&DataKey::DynamicFee)
+}
+
+/// Returns the volatility state as it stands for the current ledger, folding in the
+/// move from the last observation to `price` once per ledger. Nothing is written.
+pub fn current_volatility(env: &Env, price: &U256) -> VolatilityState {
+    let ledger = env.ledger().sequence();
+    let last: Option<VolatilityState> = env.storage().instance().get(&DataKey::Volatility);
+
+    match last {
+        Some(last) if last.ledger == ledger => last,
+        Some(last) if last.price > U256::from_u32(env, 0) => {
+            let diff = if *price > last.price { price.sub(&last.price) } else { last.price.sub(price) };
+            let change = diff
+                .mul(&U256::from_u32(env, BPS))
+                .div(&last.price)
+                .to_u128()
+                .map_or(BPS, |change| change.min(BPS as u128) as u32);
+            let ema = (last.ema * (VOLATILITY_EMA_WINDOW - 1) + change) / VOLATILITY_EMA_WINDOW;
+            VolatilityState { ema, price: price.clone(), ledger }
+        }
+        _ => VolatilityState { ema: 0, price: price.clone(), ledger },
+    }
+}
+
+pub fn write_volatility(env: &Env, state: &VolatilityState) {
+    env.storage().instance().set(&DataKey::Volatility, state);
+}
+
+/// The fee grows one basis point per basis point of average price move, within the bounds
+pub fn fee_for(config: &DynamicFeeConfig, volatility: &VolatilityState) -> u32 {
+    config.min_fee.saturating_add(volatility.ema).min(config.max_fee)
+}
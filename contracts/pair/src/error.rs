@@ -22,5 +22,24 @@ pub enum RaumFiPairError {
     PairTokenError = 13,
     InvalidAmount = 14,
     InsufficientLiquidityBurned = 15,
+    InvalidFee = 16,
+    NotInitialized = 17,
+    InvalidToken = 21,
+    /// LP was sent to be burned before its hold period was over
+    HoldPeriodActive = 22,
+    InvalidHoldPeriod = 23,
+    /// The first mint of a pair must exceed MINIMUM_LIQUIDITY by the factory's minimum
+    InsufficientInitialLiquidity = 24,
+    /// The swap moves the spot price further than the circuit breaker allows in a ledger
+    PriceMoveTooLarge = 25,
+    InvalidCircuitBreaker = 26,
 }
 
+
+impl From<raumfi_full_math::MathError> for RaumFiPairError {
+    fn from(_: raumfi_full_math::MathError) -> Self {
+        // Amounts reaching the math are non-negative and divisors non-zero, so any
+        // failure is a result that does not fit
+        RaumFiPairError::Overflow
+    }
+}
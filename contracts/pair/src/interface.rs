@@ -43,5 +43,9 @@ pub trait RaumFiFactoryTrait {
 
     fn set_fees_enabled(e: Env, is_enabled: bool)-> Result<(), FactoryError>;
 
+    fn get_protocol_fee_share(e: Env) -> Result<u32, FactoryError>;
+
+    fn get_min_initial_liquidity(e: Env) -> Result<i128, FactoryError>;
+
     fn create_pair(e: Env, token_a: Address, token_b: Address) -> Result<Address, FactoryError>;
 }
@@ -5,12 +5,19 @@ pub mod pair;
 mod pair_token;
 mod interface;
 mod factory_error;
+mod callee;
+mod dynamic_fee;
+mod lp_hold;
+mod circuit_breaker;
 
 pub use pair_token::PairToken;
 pub use pair_token::PairTokenStorage;
-pub use pair_token::events::PairTokenEvents;
 pub use pair_token::error::PairTokenError;
 pub use pair::RaumFiPairClient;
+pub use callee::{RaumFiCalleeTrait, RaumFiCalleeClient};
+pub use dynamic_fee::{DynamicFeeConfig, VolatilityState};
+pub use lp_hold::LpHoldConfig;
+pub use circuit_breaker::{CircuitBreakerConfig, CircuitBreakerState, PricePoint};
 
 #[cfg(test)]
 mod test;
@@ -0,0 +1,83 @@
+//! Minimum hold period of LP positions, against just-in-time liquidity: minting right
+//! before a large swap and burning right after it to capture most of its fee.
+//!
+//! The pair records the ledger in which each position last grew. LP only reaches
+//! `withdraw` by being sent to the pair first, so the hold period is enforced on that
+//! transfer: within it the transfer is rejected, or charged an exit fee that is burned
+//! for the remaining LPs.
+
+use soroban_sdk::{contracttype, panic_with_error, Address, Env, Symbol};
+
+use crate::error::RaumFiPairError;
+use raumfi_full_math::mul_div_ceil;
+use crate::pair::DataKey;
+use crate::pair_token::PairToken;
+
+/// Basis points in which the exit fee is measured
+const BPS: i128 = 10_000;
+
+/// Hold period of the LP positions of a pair
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct LpHoldConfig {
+    /// Ledgers that must pass after a position last grew before its LP can be burned
+    pub min_ledgers: u32,
+    /// Share of LP burned early that is destroyed for the remaining LPs, in basis points.
+    /// Zero rejects early burns instead.
+    pub exit_fee: u32,
+}
+
+pub fn read_config(env: &Env) -> Option<LpHoldConfig> {
+    env.storage().instance().get(&DataKey::LpHold)
+}
+
+/// Ledger in which the position of `holder` last grew, kept until its hold period is over
+pub fn last_growth(env: &Env, holder: &Address) -> Option<u32> {
+    env.storage().temporary().get(&DataKey::LastGrowth(holder.clone()))
+}
+
+fn write_last_growth(env: &Env, holder: &Address, ledger: u32, config: &LpHoldConfig) {
+    let key = DataKey::LastGrowth(holder.clone());
+    env.storage().temporary().set(&key, &ledger);
+    // The entry only has to outlive the hold period
+    env.storage().temporary().extend_ttl(&key, config.min_ledgers, config.min_ledgers);
+}
+
+/// Records that the position of `holder` grew in the current ledger
+pub fn record_growth(env: &Env, holder: &Address) {
+    if let Some(config) = read_config(env) {
+        write_last_growth(env, holder, env.ledger().sequence(), &config);
+    }
+}
+
+/// Applies the hold period of `from` to `amount` of LP it moved to `to`. LP sent to the
+/// pair itself is about to be burned and pays for an unfinished hold period; LP sent
+/// anywhere else takes the hold period along.
+pub fn on_transfer(env: &Env, from: &Address, to: &Address, amount: i128) {
+    let (Some(config), Some(grown)) = (read_config(env), last_growth(env, from)) else {
+        return;
+    };
+    if env.ledger().sequence() >= grown.saturating_add(config.min_ledgers) {
+        return;
+    }
+
+    if *to != env.current_contract_address() {
+        if last_growth(env, to).is_none_or(|to_grown| to_grown < grown) {
+            write_last_growth(env, to, grown, &config);
+        }
+        return;
+    }
+    if config.exit_fee == 0 {
+        panic_with_error!(env, RaumFiPairError::HoldPeriodActive);
+    }
+
+    // Rounded up so that splitting a burn into small transfers does not dodge the fee
+    let fee = mul_div_ceil(amount, config.exit_fee as i128, BPS).unwrap_or_else(|error| panic_with_error!(env, RaumFiPairError::from(error)));
+    if fee > 0 {
+        PairToken::burn_token(env, env.current_contract_address(), fee).unwrap_or_else(|error| panic_with_error!(env, error));
+        env.events().publish(
+            (Symbol::new(env, "RaumFiPair"), Symbol::new(env, "ExitFee")),
+            (from.clone(), fee)
+        );
+    }
+}
@@ -1,19 +1,34 @@
 #![no_std]
 
+use core::cmp::Ordering;
 use core::panic;
 
+use soroban_sdk::token::Interface as _;
 use soroban_sdk::{
-    contract, contractimpl, contractmeta, contracttype, log, token, Address, Env, Symbol, Vec
+    contract, contractimpl, contractmeta, contracttype, log, token, Address, Bytes, BytesN, Env, String, Symbol, Vec, U256
 };
 
-use crate::pair_token::token_storage::BALANCES_KEY;
+use crate::pair_token::bump::{bump_instance, DAY_IN_LEDGERS};
 use crate::pair_token::{PairToken, PairTokenStorage};
-use num_integer::Roots; 
 use crate::error::RaumFiPairError;
+use raumfi_full_math::{self as full_math, compare_products, mul_div_ceil, mul_div_floor, sqrt_product, sqrt_u256, wide_mul};
 
 use crate::interface::RaumFiFactoryClient;
+use crate::callee::RaumFiCalleeClient;
+use crate::dynamic_fee::{self, DynamicFeeConfig};
+use crate::lp_hold::{self, LpHoldConfig};
+use crate::circuit_breaker::{self, CircuitBreakerConfig};
 
 const MINIMUM_LIQUIDITY: i128 = 1000;
+/// Fewest LP units a mint into a seeded pair may yield. Rounding a mint down costs the
+/// depositor less than one unit, so at most 1% of the deposit at this size.
+const MIN_MINT_LIQUIDITY: i128 = 100;
+/// Swap fees are expressed in basis points of the input amount
+const FEE_DENOMINATOR: i128 = 10_000;
+/// Longest LP hold period, about a day of ledgers
+const MAX_LP_HOLD_LEDGERS: u32 = DAY_IN_LEDGERS;
+/// Fee tier of pairs initialized before fee tiers existed (0.3%)
+const DEFAULT_FEE: u32 = 30;
 
 #[contracttype]
 #[derive(Clone)]
@@ -28,6 +43,98 @@ const MINIMUM_LIQUIDITY: i128 = 1000;
     Price1CumulativeLast,
     KLast,
     Unlocked,
+    Fee,
+    Paused,
+    Stats,
+    DynamicFee,
+    Volatility,
+    DirectionalFees,
+    CreatorFee,
+    LpHold,
+    /// Ledger in which an LP position last grew, in temporary storage
+    LastGrowth(Address),
+    CircuitBreaker,
+    CircuitBreakerState,
+}
+
+/// Swap fees by direction, in basis points of the input amount, for pairs listed with
+/// different fees on buys and sells of a token
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct DirectionalFees {
+    /// Fee on token0 sent in, swapping token0 for token1
+    pub fee0_in: u32,
+    /// Fee on token1 sent in, swapping token1 for token0
+    pub fee1_in: u32,
+}
+
+/// Recipient of a share of the LP fee growth chosen by whoever created the pair through
+/// the factory, minted alongside the protocol's share
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct CreatorFee {
+    pub recipient: Address,
+    /// Share of the LP fee growth, in basis points
+    pub share: u32,
+}
+
+/// Snapshot of the pair's accounting, reported by `check_invariants`
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct PairInvariants {
+    pub total_supply: i128,
+    /// LP held by the pair itself, never below MINIMUM_LIQUIDITY once seeded
+    pub locked_liquidity: i128,
+    pub reserve0: i128,
+    pub reserve1: i128,
+    pub balance0: i128,
+    pub balance1: i128,
+    pub k: U256,
+    /// The token balances cover the reserves and, once liquidity exists,
+    /// the minimum liquidity is still locked in the pair
+    pub holds: bool,
+}
+
+/// Pair state returned by `get_pair_info` in a single call
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct PairInfo {
+    pub token0: Address,
+    pub token1: Address,
+    pub factory: Address,
+    pub reserve0: i128,
+    pub reserve1: i128,
+    pub total_supply: i128,
+    /// Product of the reserves after the last liquidity event while protocol fees were on
+    pub k_last: U256,
+    /// Swap fee in basis points
+    pub fee: u32,
+    /// Fee charged on token0 sent in, which differs from `fee` for pairs with directional fees
+    pub fee0_in: u32,
+    /// Fee charged on token1 sent in
+    pub fee1_in: u32,
+    pub block_timestamp_last: u64,
+    /// True while a guarded call is in progress
+    pub locked: bool,
+    pub paused: bool,
+}
+
+/// Running totals kept by the pair since it was created, returned by `get_stats`
+#[contracttype]
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct PairStats {
+    /// Token0 swapped through the pair, in and out
+    pub volume0: i128,
+    /// Token1 swapped through the pair, in and out
+    pub volume1: i128,
+    /// Swap fees charged on token0 inputs and left in the pool for LPs
+    pub lp_fees0: i128,
+    /// Swap fees charged on token1 inputs and left in the pool for LPs
+    pub lp_fees1: i128,
+    /// LP tokens minted to the protocol fee recipient
+    pub protocol_fees_minted: i128,
+    /// LP tokens minted to the creator fee recipient
+    pub creator_fees_minted: i128,
 }
 
  enum IdenticalPairError {
@@ -56,15 +163,44 @@ contractmeta!(
 
 
  pub trait RaumFiPairTrait{
-    fn initialize(env: Env, token0: Address, token1: Address, factory: Address) -> Result<(), RaumFiPairError>;
+    fn initialize(env: Env, token0: Address, token1: Address, factory: Address, fee: u32) -> Result<(), RaumFiPairError>;
     fn get_reserves(env: Env) -> (i128, i128);
+    fn get_fee(env: Env) -> u32;
+    fn get_fee_in(env: Env, token_in: Address) -> Result<u32, RaumFiPairError>;
+    fn get_amount_out(env: Env, token_in: Address, amount_in: i128) -> Result<i128, RaumFiPairError>;
+    fn get_amount_in(env: Env, token_out: Address, amount_out: i128) -> Result<i128, RaumFiPairError>;
+    fn get_reserves_with_timestamp(env: Env) -> (i128, i128, u64);
+    fn get_price_cumulative_last(env: Env) -> (U256, U256);
     fn mint(env: Env, to: Address) -> Result<i128, RaumFiPairError >;
-    fn mint_fee(env: Env, reserve0: i128, reserve1: i128) -> Result<bool, RaumFiPairError>;
     fn get_balance(env: Env, token_key: DataKey) -> i128;
     fn get_user_balance(env: Env, user_address: Address) -> i128;
     fn check_locked(env: &Env) -> Result<(), RaumFiPairError>;
     fn swap(env: Env, amount0_out: i128, amount1_out: i128, to: Address) -> Result<(), RaumFiPairError>;
-    fn burn(env: Env, to: Address) -> Result<(i128, i128), RaumFiPairError>;
+    fn swap_with_data(env: Env, sender: Address, amount0_out: i128, amount1_out: i128, to: Address, data: Bytes) -> Result<(), RaumFiPairError>;
+    fn withdraw(env: Env, to: Address) -> Result<(i128, i128), RaumFiPairError>;
+    fn skim(env: Env, to: Address) -> Result<(), RaumFiPairError>;
+    fn sync(env: Env) -> Result<(), RaumFiPairError>;
+    fn migrate_balances(env: Env, holders: Vec<Address>) -> u32;
+    fn upgrade(env: Env, new_wasm_hash: BytesN<32>) -> Result<(), RaumFiPairError>;
+    fn check_invariants(env: Env) -> PairInvariants;
+    fn pause(env: Env) -> Result<(), RaumFiPairError>;
+    fn unpause(env: Env) -> Result<(), RaumFiPairError>;
+    fn is_paused(env: Env) -> bool;
+    fn get_pair_info(env: Env) -> Result<PairInfo, RaumFiPairError>;
+    fn get_stats(env: Env) -> PairStats;
+    fn set_dynamic_fee(env: Env, config: Option<DynamicFeeConfig>) -> Result<(), RaumFiPairError>;
+    fn get_dynamic_fee(env: Env) -> Option<DynamicFeeConfig>;
+    fn get_volatility(env: Env) -> u32;
+    fn set_directional_fees(env: Env, fees: Option<DirectionalFees>) -> Result<(), RaumFiPairError>;
+    fn get_directional_fees(env: Env) -> Option<DirectionalFees>;
+    fn set_creator_fee(env: Env, creator_fee: CreatorFee) -> Result<(), RaumFiPairError>;
+    fn transfer_creator_fee(env: Env, recipient: Address) -> Result<(), RaumFiPairError>;
+    fn get_creator_fee(env: Env) -> Option<CreatorFee>;
+    fn set_lp_hold(env: Env, config: Option<LpHoldConfig>) -> Result<(), RaumFiPairError>;
+    fn get_lp_hold(env: Env) -> Option<LpHoldConfig>;
+    fn get_lp_hold_end(env: Env, holder: Address) -> u32;
+    fn set_circuit_breaker(env: Env, config: Option<CircuitBreakerConfig>) -> Result<(), RaumFiPairError>;
+    fn get_circuit_breaker(env: Env) -> Option<CircuitBreakerConfig>;
 }
 
 #[contract]
@@ -72,15 +208,20 @@ pub struct RaumFiPair;
 
 #[contractimpl]
 impl RaumFiPairTrait for RaumFiPair {
-    fn initialize(env: Env, token0: Address, token1: Address, factory: Address) -> Result<(), RaumFiPairError> {
+    fn initialize(env: Env, token0: Address, token1: Address, factory: Address, fee: u32) -> Result<(), RaumFiPairError> {
         if env.storage().instance().has(&DataKey::Factory) {
             return Err(RaumFiPairError::AlreadyInitialized);
         }
+        if fee as i128 >= FEE_DENOMINATOR {
+            return Err(RaumFiPairError::InvalidFee);
+        }
         
         env.storage().instance().set(&DataKey::Factory, &factory);
         env.storage().instance().set(&DataKey::Token0, &token0);
         env.storage().instance().set(&DataKey::Token1, &token1);
         env.storage().instance().set(&DataKey::Unlocked, &true);
+        env.storage().instance().set(&DataKey::Fee, &fee);
+        PairToken::write_metadata(&env, &token0, &token1);
 
         Ok(())
     }
@@ -92,13 +233,96 @@ impl RaumFiPairTrait for RaumFiPair {
         (reserve0, reserve1)
     }
 
+    /// Returns the swap fee of the pair in basis points. In dynamic-fee mode this is the
+    /// fee the next swap in the current ledger will pay. Pairs with directional fees
+    /// charge those instead, see `get_fee_in`.
+    fn get_fee(env: Env) -> u32 {
+        let (reserve0, reserve1) = Self::get_reserves(env.clone());
+        match dynamic_fee::read_config(&env) {
+            Some(config) if reserve0 > 0 && reserve1 > 0 => match encode_price(&env, reserve1, reserve0) {
+                Ok(price) => dynamic_fee::fee_for(&config, &dynamic_fee::current_volatility(&env, &price)),
+                Err(_) => env.storage().instance().get(&DataKey::Fee).unwrap_or(DEFAULT_FEE),
+            },
+            _ => env.storage().instance().get(&DataKey::Fee).unwrap_or(DEFAULT_FEE),
+        }
+    }
+
+    /// Returns the fee, in basis points, the next swap pays on `token_in` sent in
+    fn get_fee_in(env: Env, token_in: Address) -> Result<u32, RaumFiPairError> {
+        let (fee0_in, fee1_in) = fees_in(&env);
+        Ok(if is_token0(&env, &token_in)? { fee0_in } else { fee1_in })
+    }
+
+    /// Quotes the most of the other token that `swap` pays out for `amount_in` of `token_in`,
+    /// at the pair's current fee and reserves
+    fn get_amount_out(env: Env, token_in: Address, amount_in: i128) -> Result<i128, RaumFiPairError> {
+        if amount_in <= 0 {
+            return Err(RaumFiPairError::InsufficientInputAmount);
+        }
+        let (reserve_in, reserve_out, fee, _) = oriented_reserves(&env, &token_in)?;
+        let fee = fee as i128;
+
+        // Largest output for which the K check of `swap` holds:
+        // reserve_out * amount_in * (D - fee) / (reserve_in * D + amount_in * (D - fee))
+        let amount_in_with_fee = amount_in.checked_mul(FEE_DENOMINATOR - fee).ok_or(RaumFiPairError::Overflow)?;
+        let denominator = reserve_in
+            .checked_mul(FEE_DENOMINATOR)
+            .and_then(|scaled| scaled.checked_add(amount_in_with_fee))
+            .ok_or(RaumFiPairError::Overflow)?;
+        let amount_out = mul_div_floor(reserve_out, amount_in_with_fee, denominator)?;
+        if amount_out <= 0 {
+            return Err(RaumFiPairError::InsufficientOutputAmount);
+        }
+        Ok(amount_out)
+    }
+
+    /// Quotes the least of the other token that `swap` accepts for `amount_out` of `token_out`,
+    /// at the pair's current fee and reserves
+    fn get_amount_in(env: Env, token_out: Address, amount_out: i128) -> Result<i128, RaumFiPairError> {
+        if amount_out <= 0 {
+            return Err(RaumFiPairError::InsufficientOutputAmount);
+        }
+        let (reserve_out, reserve_in, _, fee) = oriented_reserves(&env, &token_out)?;
+        if amount_out >= reserve_out {
+            return Err(RaumFiPairError::InsufficientLiquidity);
+        }
+        let fee = fee as i128;
+
+        // Smallest input for which the K check of `swap` holds:
+        // reserve_in * D * amount_out / ((reserve_out - amount_out) * (D - fee))
+        let reserve_in_scaled = reserve_in.checked_mul(FEE_DENOMINATOR).ok_or(RaumFiPairError::Overflow)?;
+        let denominator = (reserve_out - amount_out).checked_mul(FEE_DENOMINATOR - fee).ok_or(RaumFiPairError::Overflow)?;
+        Ok(mul_div_ceil(reserve_in_scaled, amount_out, denominator)?)
+    }
+
+    fn get_reserves_with_timestamp(env: Env) -> (i128, i128, u64) {
+        let (reserve0, reserve1) = Self::get_reserves(env.clone());
+        let block_timestamp_last: u64 = env.storage().instance().get(&DataKey::BlockTimestampLast).unwrap_or(0);
+
+        (reserve0, reserve1, block_timestamp_last)
+    }
+
+    /// Returns the time-weighted UQ64x64 price accumulators of token0 and token1,
+    /// as of the last reserve update.
+    fn get_price_cumulative_last(env: Env) -> (U256, U256) {
+        let price0_cumulative_last: U256 = env.storage().instance().get(&DataKey::Price0CumulativeLast).unwrap_or(U256::from_u32(&env, 0));
+        let price1_cumulative_last: U256 = env.storage().instance().get(&DataKey::Price1CumulativeLast).unwrap_or(U256::from_u32(&env, 0));
+
+        (price0_cumulative_last, price1_cumulative_last)
+    }
+
     fn get_user_balance(env: Env, user_address: Address) -> i128 {
-        let balance = env.storage().instance().get::<_, i128>(&(&BALANCES_KEY, &user_address)).unwrap();
-        balance
+        PairTokenStorage::balance(&env, &user_address)
     }
 
      fn mint(env: Env, to: Address) -> Result<i128, RaumFiPairError > {
         Self::check_locked(&env)?;
+        check_not_paused(&env)?;
+        // LP minted to the pair itself would be redeemable by the next `withdraw` without
+        // ever passing through the LP hold period
+        if to == env.current_contract_address() {
+            return Err(RaumFiPairError::InvalidTo);
+        }
         let _guard = Guard::new(&env);
         let (reserve0, reserve1) = Self::get_reserves(env.clone());
         
@@ -116,18 +340,32 @@ impl RaumFiPairTrait for RaumFiPair {
             return Err(RaumFiPairError::InvalidAmount);
         }
 
-        let fee_on = Self::mint_fee(env.clone(), reserve0, reserve1)?;
-        let total_supply = PairTokenStorage::get_total_supply(&env);
+        let fee_on = mint_fee(&env, reserve0, reserve1)?;
+        let total_supply = PairTokenStorage::total_supply(&env);
         
         let liquidity = if total_supply == 0 {
-            let initial_liquidity =(amount0.checked_mul(amount1).unwrap()).sqrt() - MINIMUM_LIQUIDITY;
+            // The locked MINIMUM_LIQUIDITY makes inflating the price of a share by donation
+            // cost the donor far more than it can take from later LPs, as long as the first
+            // LP holds a meaningful position next to it
+            let root_k = sqrt_product(amount0, amount1)?;
+            let factory: Address = env.storage().instance().get(&DataKey::Factory).ok_or(RaumFiPairError::NotInitialized)?;
+            let min_initial_liquidity = RaumFiFactoryClient::new(&env, &factory).get_min_initial_liquidity();
+            if root_k <= MINIMUM_LIQUIDITY || root_k - MINIMUM_LIQUIDITY < min_initial_liquidity {
+                return Err(RaumFiPairError::InsufficientInitialLiquidity);
+            }
             PairToken::mint_token(&env, env.current_contract_address().clone(), MINIMUM_LIQUIDITY);
 
-            initial_liquidity
+            root_k - MINIMUM_LIQUIDITY
         } else {
-            let liquidity0 = (amount0.checked_mul(total_supply).unwrap()).checked_div(reserve0).unwrap();
-            let liquidity1 = (amount1.checked_mul(total_supply).unwrap()).checked_div(reserve1).unwrap();
-            liquidity0.min(liquidity1)
+            let liquidity0 = mul_div_floor(amount0, total_supply, reserve0)?;
+            let liquidity1 = mul_div_floor(amount1, total_supply, reserve1)?;
+            // A share price inflated by donations makes small mints fail here rather than
+            // rounding most of them away to the existing LPs
+            let liquidity = liquidity0.min(liquidity1);
+            if liquidity < MIN_MINT_LIQUIDITY {
+                return Err(RaumFiPairError::InsufficientLiquidityMinted);
+            }
+            liquidity
         };
         
 
@@ -137,12 +375,12 @@ impl RaumFiPairTrait for RaumFiPair {
         
         
         PairToken::mint_token(&env, to.clone(), liquidity);
+        lp_hold::record_growth(&env, &to);
 
-        update(&env, balance0, balance1)?;
+        update(&env, balance0, balance1, reserve0, reserve1)?;
 
         if fee_on {
-            let k_last = reserve0 as i128 * reserve1 as i128;
-            env.storage().instance().set(&DataKey::KLast, &k_last);
+            write_k_last(&env, balance0, balance1)?;
         }
 
         env.events().publish(
@@ -155,35 +393,6 @@ impl RaumFiPairTrait for RaumFiPair {
     }
 
 
-    fn mint_fee(env: Env, reserve0: i128, reserve1: i128) -> Result<bool, RaumFiPairError> {
-        let factory: Address = env.storage().instance().get(&DataKey::Factory).unwrap();
-        let factory_client = RaumFiFactoryClient::new(&env, &factory);
-        let fee_on = true;
-        let k_last: i128 = env.storage().instance().get(&DataKey::KLast).unwrap_or(0);
-        let fee_to = factory_client.get_fee_to();   
-
-        if fee_on {
-            if k_last != 0 {
-                let root_k = ((reserve0.checked_mul(reserve1).unwrap()).sqrt()) as i128;
-                let root_k_last = k_last.sqrt() as i128;
-                if root_k > root_k_last {
-                    let total_supply = PairTokenStorage::get_total_supply(&env);
-                    let numerator = (root_k.saturating_sub(root_k_last)).saturating_mul(total_supply);
-                    let denominator = root_k.saturating_mul(5).saturating_add(root_k_last);
-                    let liquidity = numerator.saturating_div(denominator);
-                    if liquidity != 0 {
-                        PairToken::mint_token(&env, fee_to.clone(), liquidity);
-                    }
-                }
-            }
-        } else if !k_last == 0 {
-            env.storage().instance().set(&DataKey::KLast,  &0i128);
-        }
-
-        Ok(fee_on)
-    }
-
-
     fn get_balance(env: Env, token_key: DataKey) -> i128 {
         let token: Address = env.storage().instance().get(&token_key).unwrap();
         let token_client = token::Client::new(&env, &token);
@@ -199,106 +408,676 @@ impl RaumFiPairTrait for RaumFiPair {
     }
 
     fn swap(env: Env, amount0_out: i128, amount1_out: i128, to: Address) -> Result<(), RaumFiPairError> {
+        swap_logic(&env, amount0_out, amount1_out, to, None)
+    }
+
+    /// Swaps like `swap`, but when `data` is non-empty the outputs are sent optimistically and
+    /// `raumfi_call` is invoked on `to` before the invariant is checked, so the output can be
+    /// borrowed and repaid (flash swap) within the same invocation.
+    fn swap_with_data(env: Env, sender: Address, amount0_out: i128, amount1_out: i128, to: Address, data: Bytes) -> Result<(), RaumFiPairError> {
+        sender.require_auth();
+        let flash = if data.is_empty() { None } else { Some((sender, data)) };
+        swap_logic(&env, amount0_out, amount1_out, to, flash)
+    }
+
+    /// Burns the LP tokens held by the pair and sends the underlying tokens to `to`.
+    /// Named `withdraw` because `burn` is the SEP-41 entrypoint of the LP token itself.
+    fn withdraw(env: Env, to: Address) -> Result<(i128, i128), RaumFiPairError> {
         Self::check_locked(&env)?;
         let _guard = Guard::new(&env);
 
-        if amount0_out == 0 && amount1_out == 0 {
-            return Err(RaumFiPairError::InsufficientOutputAmount);
+        let token0: Address = env.storage().instance().get(&DataKey::Token0).unwrap();
+        let token1: Address = env.storage().instance().get(&DataKey::Token1).unwrap();
+        let (reserve0, reserve1) = Self::get_reserves(env.clone());
+
+        let balance0 = Self::get_balance(env.clone(), DataKey::Token0);
+        let balance1 = Self::get_balance(env.clone(), DataKey::Token1);
+        // The pair's own LP balance holds the locked MINIMUM_LIQUIDITY; only what was
+        // sent on top of it is redeemable
+        let liquidity = PairTokenStorage::balance(&env, &env.current_contract_address())
+            .checked_sub(MINIMUM_LIQUIDITY)
+            .ok_or(RaumFiPairError::InsufficientLiquidityBurned)?;
+
+        let fee_on = mint_fee(&env, reserve0, reserve1)?;
+        let total_supply = PairTokenStorage::total_supply(&env);
+        let amount0 = mul_div_floor(balance0, liquidity, total_supply)?;
+        let amount1 = mul_div_floor(balance1, liquidity, total_supply)?;
+
+        if amount0 <= 0 || amount1 <= 0 {
+            return Err(RaumFiPairError::InsufficientLiquidityBurned);
         }
 
-        let (reserve0, reserve1) = Self::get_reserves(env.clone());
+        PairToken::burn_token(&env, env.current_contract_address(), liquidity)
+            .map_err(|_| RaumFiPairError::PairTokenError)?;
 
-        if amount0_out > reserve0 || amount1_out > reserve1 {
-            return Err(RaumFiPairError::InsufficientLiquidity);
+        token::Client::new(&env, &token0).transfer(&env.current_contract_address(), &to, &amount0);
+        token::Client::new(&env, &token1).transfer(&env.current_contract_address(), &to, &amount1);
+
+        let balance0 = Self::get_balance(env.clone(), DataKey::Token0);
+        let balance1 = Self::get_balance(env.clone(), DataKey::Token1);
+
+        update(&env, balance0, balance1, reserve0, reserve1)?;
+
+        if fee_on {
+            write_k_last(&env, balance0, balance1)?;
         }
 
+        env.events().publish(
+            (Symbol::new(&env, "RaumFiPair"), Symbol::new(&env, "Burn")),
+            (to, amount0, amount1)
+        );
+
+        Ok((amount0, amount1))
+    }
+
+    /// Sends any token balance held above the reserves to `to`
+    fn skim(env: Env, to: Address) -> Result<(), RaumFiPairError> {
+        Self::check_locked(&env)?;
+        let _guard = Guard::new(&env);
+
         let token0: Address = env.storage().instance().get(&DataKey::Token0).unwrap();
         let token1: Address = env.storage().instance().get(&DataKey::Token1).unwrap();
+        let (reserve0, reserve1) = Self::get_reserves(env.clone());
 
-        if to == token0 || to == token1 {
-            return Err(RaumFiPairError::InvalidTo);
-        }
+        let amount0 = Self::get_balance(env.clone(), DataKey::Token0).saturating_sub(reserve0).max(0);
+        let amount1 = Self::get_balance(env.clone(), DataKey::Token1).saturating_sub(reserve1).max(0);
 
-        if amount0_out > 0 {
-            token::Client::new(&env, &token0).transfer(&env.current_contract_address(), &to, &amount0_out);
+        if amount0 > 0 {
+            token::Client::new(&env, &token0).transfer(&env.current_contract_address(), &to, &amount0);
         }
-        if amount1_out > 0 {
-            token::Client::new(&env, &token1).transfer(&env.current_contract_address(), &to, &amount1_out);
+        if amount1 > 0 {
+            token::Client::new(&env, &token1).transfer(&env.current_contract_address(), &to, &amount1);
         }
 
+        env.events().publish(
+            (Symbol::new(&env, "RaumFiPair"), Symbol::new(&env, "Skim")),
+            (to, amount0, amount1)
+        );
+
+        Ok(())
+    }
+
+    /// Forces the reserves to match the current token balances
+    fn sync(env: Env) -> Result<(), RaumFiPairError> {
+        Self::check_locked(&env)?;
+        let _guard = Guard::new(&env);
+
+        let (reserve0, reserve1) = Self::get_reserves(env.clone());
         let balance0 = Self::get_balance(env.clone(), DataKey::Token0);
         let balance1 = Self::get_balance(env.clone(), DataKey::Token1);
 
-        let amount0_in = if balance0 > reserve0 - amount0_out { balance0 - (reserve0 - amount0_out) } else { 0 };
-        let amount1_in = if balance1 > reserve1 - amount1_out { balance1 - (reserve1 - amount1_out) } else { 0 };
-
-        if amount0_in == 0 && amount1_in == 0 {
-            return Err(RaumFiPairError::InsufficientInputAmount);
+        if reserve0 > 0 && reserve1 > 0 && balance0 > 0 {
+            circuit_breaker::check_swap(&env, &encode_price(&env, reserve1, reserve0)?, &encode_price(&env, balance1, balance0)?)?;
         }
 
-        let balance0_adjusted = balance0.checked_mul(1000).unwrap() - amount0_in.checked_mul(3).unwrap();
-        let balance1_adjusted = balance1.checked_mul(1000).unwrap() - amount1_in.checked_mul(3).unwrap();
+        update(&env, balance0, balance1, reserve0, reserve1)
+    }
 
-        if balance0_adjusted.checked_mul(balance1_adjusted).unwrap() < reserve0.checked_mul(reserve1).unwrap().checked_mul(1000000).unwrap() {
-            return Err(RaumFiPairError::K);
+    /// Moves LP balances that an older pair kept in instance storage into
+    /// persistent storage, and restores the total supply it kept negated.
+    /// Anyone may call it; holders without a legacy entry are skipped.
+    /// Returns how many balances were moved.
+    fn migrate_balances(env: Env, holders: Vec<Address>) -> u32 {
+        bump_instance(&env);
+        PairTokenStorage::migrate_total_supply(&env);
+        let mut migrated = 0;
+        for holder in holders.iter() {
+            if PairTokenStorage::migrate_balance(&env, &holder) {
+                migrated += 1;
+            }
         }
+        migrated
+    }
 
-        update(&env, balance0, balance1)?;
+    /// Replaces the pair's code with the uploaded wasm `new_wasm_hash`, keeping its storage.
+    /// Only the factory may upgrade a pair.
+    fn upgrade(env: Env, new_wasm_hash: BytesN<32>) -> Result<(), RaumFiPairError> {
+        let factory: Address = env.storage().instance().get(&DataKey::Factory).ok_or(RaumFiPairError::NotInitialized)?;
+        factory.require_auth();
+        env.deployer().update_current_contract_wasm(new_wasm_hash.clone());
 
         env.events().publish(
-            (Symbol::new(&env, "RaumFiPair"), Symbol::new(&env, "Swap")),
-            (amount0_in, amount1_in, amount0_out, amount1_out, to)
+            (Symbol::new(&env, "RaumFiPair"), Symbol::new(&env, "Upgrade")),
+            new_wasm_hash
         );
+        Ok(())
+    }
+
+    /// Reports total supply, reserves, balances and k so the pair's accounting can be
+    /// monitored without touching state
+    fn check_invariants(env: Env) -> PairInvariants {
+        let (reserve0, reserve1) = Self::get_reserves(env.clone());
+        let balance0 = Self::get_balance(env.clone(), DataKey::Token0);
+        let balance1 = Self::get_balance(env.clone(), DataKey::Token1);
+        let total_supply = PairTokenStorage::total_supply(&env);
+        let locked_liquidity = PairTokenStorage::balance(&env, &env.current_contract_address());
+        let k = U256::from_u128(&env, reserve0 as u128).mul(&U256::from_u128(&env, reserve1 as u128));
+
+        let holds = balance0 >= reserve0
+            && balance1 >= reserve1
+            && (total_supply == 0 || locked_liquidity >= MINIMUM_LIQUIDITY);
+
+        PairInvariants {
+            total_supply,
+            locked_liquidity,
+            reserve0,
+            reserve1,
+            balance0,
+            balance1,
+            k,
+            holds,
+        }
+    }
+
+    /// Halts swaps and mints while leaving `withdraw` open, so LPs can always exit.
+    /// Only the factory may pause a pair.
+    fn pause(env: Env) -> Result<(), RaumFiPairError> {
+        set_paused(&env, true)
+    }
+
+    /// Lifts a pause set by the factory
+    fn unpause(env: Env) -> Result<(), RaumFiPairError> {
+        set_paused(&env, false)
+    }
+
+    fn is_paused(env: Env) -> bool {
+        env.storage().instance().get(&DataKey::Paused).unwrap_or(false)
+    }
+
+    /// Returns tokens, reserves, supply, fee and status of the pair in one call
+    fn get_pair_info(env: Env) -> Result<PairInfo, RaumFiPairError> {
+        let storage = env.storage().instance();
+        let (reserve0, reserve1, block_timestamp_last) = Self::get_reserves_with_timestamp(env.clone());
+        let (fee0_in, fee1_in) = fees_in(&env);
+
+        Ok(PairInfo {
+            token0: storage.get(&DataKey::Token0).ok_or(RaumFiPairError::NotInitialized)?,
+            token1: storage.get(&DataKey::Token1).ok_or(RaumFiPairError::NotInitialized)?,
+            factory: storage.get(&DataKey::Factory).ok_or(RaumFiPairError::NotInitialized)?,
+            reserve0,
+            reserve1,
+            total_supply: PairTokenStorage::total_supply(&env),
+            k_last: storage.get(&DataKey::KLast).unwrap_or(U256::from_u32(&env, 0)),
+            fee: Self::get_fee(env.clone()),
+            fee0_in,
+            fee1_in,
+            block_timestamp_last,
+            locked: Self::check_locked(&env).is_err(),
+            paused: Self::is_paused(env.clone()),
+        })
+    }
 
+    /// Returns cumulative swap volume, LP fees and protocol fees of the pair
+    fn get_stats(env: Env) -> PairStats {
+        env.storage().instance().get(&DataKey::Stats).unwrap_or_default()
+    }
+
+    /// Switches the pair to a fee derived from recent price volatility, bounded by
+    /// `config`, or back to its fee tier with `None`. Only the factory may call it.
+    fn set_dynamic_fee(env: Env, config: Option<DynamicFeeConfig>) -> Result<(), RaumFiPairError> {
+        let factory: Address = env.storage().instance().get(&DataKey::Factory).ok_or(RaumFiPairError::NotInitialized)?;
+        factory.require_auth();
+
+        match &config {
+            Some(bounds) => {
+                if bounds.min_fee > bounds.max_fee || bounds.max_fee as i128 >= FEE_DENOMINATOR {
+                    return Err(RaumFiPairError::InvalidFee);
+                }
+                env.storage().instance().set(&DataKey::DynamicFee, bounds);
+            }
+            None => env.storage().instance().remove(&DataKey::DynamicFee),
+        }
+
+        env.events().publish(
+            (Symbol::new(&env, "RaumFiPair"), Symbol::new(&env, "DynamicFee")),
+            config
+        );
         Ok(())
     }
 
-    fn burn(env: Env, to: Address) -> Result<(i128, i128), RaumFiPairError> {
-        Self::check_locked(&env)?;
-        let _guard = Guard::new(&env);
+    fn get_dynamic_fee(env: Env) -> Option<DynamicFeeConfig> {
+        dynamic_fee::read_config(&env)
+    }
 
-        let token0: Address = env.storage().instance().get(&DataKey::Token0).unwrap();
-        let token1: Address = env.storage().instance().get(&DataKey::Token1).unwrap();
+    /// Returns the moving average of the per-ledger price move, in basis points
+    fn get_volatility(env: Env) -> u32 {
         let (reserve0, reserve1) = Self::get_reserves(env.clone());
+        if reserve0 <= 0 || reserve1 <= 0 {
+            return 0;
+        }
+        encode_price(&env, reserve1, reserve0).map_or(0, |price| dynamic_fee::current_volatility(&env, &price).ema)
+    }
 
-        let balance0 = Self::get_balance(env.clone(), DataKey::Token0);
-        let balance1 = Self::get_balance(env.clone(), DataKey::Token1);
-        let liquidity = PairTokenStorage::get_balance(&env, &env.current_contract_address());
+    /// Charges `fees` on swaps by the token sent in, in place of the fee tier or dynamic
+    /// fee, or stops with `None`. Only the factory may call it.
+    fn set_directional_fees(env: Env, fees: Option<DirectionalFees>) -> Result<(), RaumFiPairError> {
+        let factory: Address = env.storage().instance().get(&DataKey::Factory).ok_or(RaumFiPairError::NotInitialized)?;
+        factory.require_auth();
 
-        let userbalance = liquidity.checked_sub(MINIMUM_LIQUIDITY).unwrap();
-        let total_supply = PairTokenStorage::get_total_supply(&env);
-        let amount0 = balance0.checked_mul(userbalance).unwrap().checked_div(total_supply).unwrap();
-        let amount1 = balance1.checked_mul(userbalance).unwrap().checked_div(total_supply).unwrap();
+        match &fees {
+            Some(fees) => {
+                if fees.fee0_in as i128 >= FEE_DENOMINATOR || fees.fee1_in as i128 >= FEE_DENOMINATOR {
+                    return Err(RaumFiPairError::InvalidFee);
+                }
+                env.storage().instance().set(&DataKey::DirectionalFees, fees);
+            }
+            None => env.storage().instance().remove(&DataKey::DirectionalFees),
+        }
 
-        if amount0 == 0 || amount1 == 0 {
-            return Err(RaumFiPairError::InsufficientLiquidityBurned);
+        env.events().publish(
+            (Symbol::new(&env, "RaumFiPair"), Symbol::new(&env, "DirectionalFees")),
+            fees
+        );
+        Ok(())
+    }
+
+    fn get_directional_fees(env: Env) -> Option<DirectionalFees> {
+        env.storage().instance().get(&DataKey::DirectionalFees)
+    }
+
+    /// Records the creator fee recipient and its share of the LP fee growth. Only the
+    /// factory may call it, when it creates the pair.
+    fn set_creator_fee(env: Env, creator_fee: CreatorFee) -> Result<(), RaumFiPairError> {
+        let factory: Address = env.storage().instance().get(&DataKey::Factory).ok_or(RaumFiPairError::NotInitialized)?;
+        factory.require_auth();
+        // Capped at half the growth, like the protocol share, so the two together never
+        // take all of it
+        if creator_fee.share == 0 || creator_fee.share as i128 > FEE_DENOMINATOR / 2 {
+            return Err(RaumFiPairError::InvalidFee);
         }
+        env.storage().instance().set(&DataKey::CreatorFee, &creator_fee);
 
-        PairToken::burn_token(&env, env.current_contract_address(), liquidity);
+        env.events().publish(
+            (Symbol::new(&env, "RaumFiPair"), Symbol::new(&env, "CreatorFee")),
+            (creator_fee.recipient, creator_fee.share)
+        );
+        Ok(())
+    }
 
-        token::Client::new(&env, &token0).transfer(&env.current_contract_address(), &to, &amount0);
-        token::Client::new(&env, &token1).transfer(&env.current_contract_address(), &to, &amount1);
+    /// Hands the creator fee over to `recipient`. Only the current recipient may call it.
+    fn transfer_creator_fee(env: Env, recipient: Address) -> Result<(), RaumFiPairError> {
+        let mut creator_fee = Self::get_creator_fee(env.clone()).ok_or(RaumFiPairError::Forbidden)?;
+        creator_fee.recipient.require_auth();
+        creator_fee.recipient = recipient;
+        env.storage().instance().set(&DataKey::CreatorFee, &creator_fee);
 
-        let balance0 = Self::get_balance(env.clone(), DataKey::Token0);
-        let balance1 = Self::get_balance(env.clone(), DataKey::Token1);
+        env.events().publish(
+            (Symbol::new(&env, "RaumFiPair"), Symbol::new(&env, "CreatorFee")),
+            (creator_fee.recipient, creator_fee.share)
+        );
+        Ok(())
+    }
 
-        update(&env, balance0, balance1)?;
+    fn get_creator_fee(env: Env) -> Option<CreatorFee> {
+        env.storage().instance().get(&DataKey::CreatorFee)
+    }
+
+    /// Makes LP wait `config.min_ledgers` after its position last grew before it can be
+    /// burned without penalty, or lifts the hold period with `None`. Only the factory may
+    /// call it.
+    fn set_lp_hold(env: Env, config: Option<LpHoldConfig>) -> Result<(), RaumFiPairError> {
+        let factory: Address = env.storage().instance().get(&DataKey::Factory).ok_or(RaumFiPairError::NotInitialized)?;
+        factory.require_auth();
+
+        match &config {
+            Some(config) => {
+                if config.min_ledgers == 0 || config.min_ledgers > MAX_LP_HOLD_LEDGERS || config.exit_fee as i128 >= FEE_DENOMINATOR {
+                    return Err(RaumFiPairError::InvalidHoldPeriod);
+                }
+                env.storage().instance().set(&DataKey::LpHold, config);
+            }
+            None => env.storage().instance().remove(&DataKey::LpHold),
+        }
 
         env.events().publish(
-            (Symbol::new(&env, "RaumFiPair"), Symbol::new(&env, "Burn")),
-            (to, amount0, amount1)
+            (Symbol::new(&env, "RaumFiPair"), Symbol::new(&env, "LpHold")),
+            config
         );
+        Ok(())
+    }
 
-        Ok((amount0, amount1))
+    fn get_lp_hold(env: Env) -> Option<LpHoldConfig> {
+        lp_hold::read_config(&env)
+    }
+
+    /// Returns the first ledger in which the LP of `holder` can be burned without penalty
+    fn get_lp_hold_end(env: Env, holder: Address) -> u32 {
+        match (lp_hold::read_config(&env), lp_hold::last_growth(&env, &holder)) {
+            (Some(config), Some(grown)) => grown.saturating_add(config.min_ledgers).max(env.ledger().sequence()),
+            _ => env.ledger().sequence(),
+        }
+    }
+
+    /// Bounds the spot price move of swaps within a ledger, and pauses the pair once the price
+    /// moves too far within a sliding window, or lifts both with `None`. Only the factory may
+    /// call it.
+    fn set_circuit_breaker(env: Env, config: Option<CircuitBreakerConfig>) -> Result<(), RaumFiPairError> {
+        let factory: Address = env.storage().instance().get(&DataKey::Factory).ok_or(RaumFiPairError::NotInitialized)?;
+        factory.require_auth();
+
+        match &config {
+            Some(config) => {
+                if config.max_ledger_move == 0 || config.max_window_move == 0 || config.window == 0 {
+                    return Err(RaumFiPairError::InvalidCircuitBreaker);
+                }
+                env.storage().instance().set(&DataKey::CircuitBreaker, config);
+            }
+            None => env.storage().instance().remove(&DataKey::CircuitBreaker),
+        }
+        circuit_breaker::reset(&env);
+
+        env.events().publish(
+            (Symbol::new(&env, "RaumFiPair"), Symbol::new(&env, "CircuitBreaker")),
+            config
+        );
+        Ok(())
+    }
+
+    fn get_circuit_breaker(env: Env) -> Option<CircuitBreakerConfig> {
+        circuit_breaker::read_config(&env)
+    }
+}
+
+/// The pair contract is itself the SEP-41 token of its liquidity shares
+#[contractimpl]
+impl token::Interface for RaumFiPair {
+    fn allowance(e: Env, from: Address, spender: Address) -> i128 {
+        PairTokenStorage::allowance(&e, &from, &spender).amount
+    }
+
+    fn approve(e: Env, from: Address, spender: Address, amount: i128, expiration_ledger: u32) {
+        from.require_auth();
+        bump_instance(&e);
+        PairTokenStorage::approve(&e, &from, &spender, amount, expiration_ledger);
+    }
+
+    fn balance(e: Env, id: Address) -> i128 {
+        PairTokenStorage::balance(&e, &id)
+    }
+
+    fn transfer(e: Env, from: Address, to: Address, amount: i128) {
+        from.require_auth();
+        bump_instance(&e);
+        PairTokenStorage::check_nonnegative_amount(&e, amount);
+        PairTokenStorage::transfer(&e, &from, &to, amount);
+        lp_hold::on_transfer(&e, &from, &to, amount);
+    }
+
+    fn transfer_from(e: Env, spender: Address, from: Address, to: Address, amount: i128) {
+        spender.require_auth();
+        bump_instance(&e);
+        PairTokenStorage::check_nonnegative_amount(&e, amount);
+        PairTokenStorage::spend_allowance(&e, &from, &spender, amount);
+        PairTokenStorage::transfer(&e, &from, &to, amount);
+        lp_hold::on_transfer(&e, &from, &to, amount);
+    }
+
+    fn burn(e: Env, from: Address, amount: i128) {
+        from.require_auth();
+        bump_instance(&e);
+        PairTokenStorage::check_nonnegative_amount(&e, amount);
+        PairTokenStorage::burn(&e, &from, amount);
+    }
+
+    fn burn_from(e: Env, spender: Address, from: Address, amount: i128) {
+        spender.require_auth();
+        bump_instance(&e);
+        PairTokenStorage::check_nonnegative_amount(&e, amount);
+        PairTokenStorage::spend_allowance(&e, &from, &spender, amount);
+        PairTokenStorage::burn(&e, &from, amount);
+    }
+
+    fn decimals(e: Env) -> u32 {
+        PairTokenStorage::read_metadata(&e).decimal
+    }
+
+    fn name(e: Env) -> String {
+        PairTokenStorage::read_metadata(&e).name
+    }
+
+    fn symbol(e: Env) -> String {
+        PairTokenStorage::read_metadata(&e).symbol
     }
 }
 
-fn update(env: &Env, balance0: i128, balance1: i128) -> Result<(), RaumFiPairError> {
+fn swap_logic(env: &Env, amount0_out: i128, amount1_out: i128, to: Address, flash: Option<(Address, Bytes)>) -> Result<(), RaumFiPairError> {
+    RaumFiPair::check_locked(env)?;
+    check_not_paused(env)?;
+    let _guard = Guard::new(env);
 
+    if amount0_out == 0 && amount1_out == 0 {
+        return Err(RaumFiPairError::InsufficientOutputAmount);
+    }
+
+    let (reserve0, reserve1) = RaumFiPair::get_reserves(env.clone());
+
+    if amount0_out > reserve0 || amount1_out > reserve1 {
+        return Err(RaumFiPairError::InsufficientLiquidity);
+    }
+
+    let token0: Address = env.storage().instance().get(&DataKey::Token0).unwrap();
+    let token1: Address = env.storage().instance().get(&DataKey::Token1).unwrap();
+
+    if to == token0 || to == token1 {
+        return Err(RaumFiPairError::InvalidTo);
+    }
+
+    if amount0_out > 0 {
+        token::Client::new(env, &token0).transfer(&env.current_contract_address(), &to, &amount0_out);
+    }
+    if amount1_out > 0 {
+        token::Client::new(env, &token1).transfer(&env.current_contract_address(), &to, &amount1_out);
+    }
+    if let Some((sender, data)) = flash {
+        RaumFiCalleeClient::new(env, &to).raumfi_call(&sender, &amount0_out, &amount1_out, &data);
+    }
+
+    let balance0 = RaumFiPair::get_balance(env.clone(), DataKey::Token0);
+    let balance1 = RaumFiPair::get_balance(env.clone(), DataKey::Token1);
+
+    let amount0_in = if balance0 > reserve0 - amount0_out { balance0 - (reserve0 - amount0_out) } else { 0 };
+    let amount1_in = if balance1 > reserve1 - amount1_out { balance1 - (reserve1 - amount1_out) } else { 0 };
+
+    if amount0_in == 0 && amount1_in == 0 {
+        return Err(RaumFiPairError::InsufficientInputAmount);
+    }
+
+    let (fee0_in, fee1_in) = fees_in(env);
+    if dynamic_fee::read_config(env).is_some() {
+        let volatility = dynamic_fee::current_volatility(env, &encode_price(env, reserve1, reserve0)?);
+        dynamic_fee::write_volatility(env, &volatility);
+    }
+    let balance0_adjusted = adjusted_balance(balance0, amount0_in, fee0_in as i128)?;
+    let balance1_adjusted = adjusted_balance(balance1, amount1_in, fee1_in as i128)?;
+    let reserve0_scaled = reserve0.checked_mul(FEE_DENOMINATOR).ok_or(RaumFiPairError::Overflow)?;
+    let reserve1_scaled = reserve1.checked_mul(FEE_DENOMINATOR).ok_or(RaumFiPairError::Overflow)?;
+
+    // The products of balances this large overflow i128, so they are compared in 256 bits
+    if compare_products(balance0_adjusted, balance1_adjusted, reserve0_scaled, reserve1_scaled) == Ordering::Less {
+        return Err(RaumFiPairError::K);
+    }
+
+    if reserve0 > 0 && reserve1 > 0 && balance0 > 0 {
+        circuit_breaker::check_swap(env, &encode_price(env, reserve1, reserve0)?, &encode_price(env, balance1, balance0)?)?;
+    }
+
+    update(env, balance0, balance1, reserve0, reserve1)?;
+
+    let mut stats = RaumFiPair::get_stats(env.clone());
+    stats.volume0 = stats.volume0.saturating_add(amount0_in).saturating_add(amount0_out);
+    stats.volume1 = stats.volume1.saturating_add(amount1_in).saturating_add(amount1_out);
+    stats.lp_fees0 = stats.lp_fees0.saturating_add(mul_div_floor(amount0_in, fee0_in as i128, FEE_DENOMINATOR)?);
+    stats.lp_fees1 = stats.lp_fees1.saturating_add(mul_div_floor(amount1_in, fee1_in as i128, FEE_DENOMINATOR)?);
+    env.storage().instance().set(&DataKey::Stats, &stats);
+
+    env.events().publish(
+        (Symbol::new(env, "RaumFiPair"), Symbol::new(env, "Swap")),
+        (amount0_in, amount1_in, amount0_out, amount1_out, to)
+    );
+
+    Ok(())
+}
+
+/// Fees charged on token0 and on token1 sent in. Directional fees take precedence over
+/// the fee tier and dynamic-fee mode.
+fn fees_in(env: &Env) -> (u32, u32) {
+    match RaumFiPair::get_directional_fees(env.clone()) {
+        Some(fees) => (fees.fee0_in, fees.fee1_in),
+        None => {
+            let fee = RaumFiPair::get_fee(env.clone());
+            (fee, fee)
+        }
+    }
+}
+
+/// Whether `token` is token0 of the pair rather than token1
+fn is_token0(env: &Env, token: &Address) -> Result<bool, RaumFiPairError> {
+    let token0: Address = env.storage().instance().get(&DataKey::Token0).ok_or(RaumFiPairError::NotInitialized)?;
+    let token1: Address = env.storage().instance().get(&DataKey::Token1).ok_or(RaumFiPairError::NotInitialized)?;
+    if *token == token0 {
+        Ok(true)
+    } else if *token == token1 {
+        Ok(false)
+    } else {
+        Err(RaumFiPairError::InvalidToken)
+    }
+}
+
+/// Reserves of `token` and of the other token of the pair, then the fees charged on each
+/// sent in, in the same order
+fn oriented_reserves(env: &Env, token: &Address) -> Result<(i128, i128, u32, u32), RaumFiPairError> {
+    let (reserve0, reserve1) = RaumFiPair::get_reserves(env.clone());
+    if reserve0 <= 0 || reserve1 <= 0 {
+        return Err(RaumFiPairError::InsufficientLiquidity);
+    }
+    let (fee0_in, fee1_in) = fees_in(env);
+    if is_token0(env, token)? {
+        Ok((reserve0, reserve1, fee0_in, fee1_in))
+    } else {
+        Ok((reserve1, reserve0, fee1_in, fee0_in))
+    }
+}
+
+/// Balance scaled by `FEE_DENOMINATOR`, less the fee charged on the input
+fn adjusted_balance(balance: i128, amount_in: i128, fee: i128) -> Result<i128, RaumFiPairError> {
+    balance
+        .checked_mul(FEE_DENOMINATOR)
+        .and_then(|scaled| scaled.checked_sub(amount_in.checked_mul(fee)?))
+        .ok_or(RaumFiPairError::Overflow)
+}
+
+/// Mints the protocol's share of the LP fee growth since the last liquidity event to
+/// the factory's `fee_to`, when the factory has protocol fees enabled, and the creator's
+/// share to the creator fee recipient, when the pair has one. Only `mint` and `withdraw`
+/// call it, with the stored reserves.
+fn mint_fee(env: &Env, reserve0: i128, reserve1: i128) -> Result<bool, RaumFiPairError> {
+    let factory: Address = env.storage().instance().get(&DataKey::Factory).unwrap();
+    let factory_client = RaumFiFactoryClient::new(env, &factory);
+    let protocol_fee_on = factory_client.fees_enabled();
+    let creator_fee = RaumFiPair::get_creator_fee(env.clone());
+    let fee_on = protocol_fee_on || creator_fee.is_some();
+    let k_last: U256 = env.storage().instance().get(&DataKey::KLast).unwrap_or(U256::from_u32(env, 0));
+
+    if fee_on {
+        if k_last != U256::from_u32(env, 0) {
+            let root_k = sqrt_product(reserve0, reserve1)?;
+            let root_k_last = root_k_last(&k_last);
+            if root_k > root_k_last {
+                let protocol_share = if protocol_fee_on { factory_client.get_protocol_fee_share() as i128 } else { 0 };
+                let creator_share = creator_fee.as_ref().map_or(0, |creator_fee| creator_fee.share as i128);
+                let share = protocol_share + creator_share;
+
+                // With a share s of the growth, the minted amount keeps s * (root_k - root_k_last) / root_k
+                // of the pool: liquidity = supply * s * (root_k - root_k_last) / (root_k * (1 - s) + root_k_last * s)
+                let total_supply = PairTokenStorage::total_supply(env);
+                let denominator = root_k
+                    .checked_mul(FEE_DENOMINATOR - share)
+                    .and_then(|kept| kept.checked_add(root_k_last.checked_mul(share)?))
+                    .ok_or(RaumFiPairError::Overflow)?;
+                let supply_share = total_supply.checked_mul(share).ok_or(RaumFiPairError::Overflow)?;
+                let liquidity = mul_div_floor(root_k - root_k_last, supply_share, denominator)?;
+
+                // The minted liquidity is split between the two in proportion to their shares
+                let creator_liquidity = mul_div_floor(liquidity, creator_share, share)?;
+                let protocol_liquidity = liquidity - creator_liquidity;
+                let mut stats = RaumFiPair::get_stats(env.clone());
+                if protocol_liquidity > 0 {
+                    PairToken::mint_token(env, factory_client.get_fee_to(), protocol_liquidity)
+                        .map_err(|_| RaumFiPairError::PairTokenError)?;
+                    stats.protocol_fees_minted = stats.protocol_fees_minted.saturating_add(protocol_liquidity);
+                }
+                if let Some(creator_fee) = creator_fee.filter(|_| creator_liquidity > 0) {
+                    PairToken::mint_token(env, creator_fee.recipient, creator_liquidity)
+                        .map_err(|_| RaumFiPairError::PairTokenError)?;
+                    stats.creator_fees_minted = stats.creator_fees_minted.saturating_add(creator_liquidity);
+                }
+                env.storage().instance().set(&DataKey::Stats, &stats);
+            }
+        }
+    } else if k_last != U256::from_u32(env, 0) {
+        env.storage().instance().set(&DataKey::KLast, &U256::from_u32(env, 0));
+    }
+
+    Ok(fee_on)
+}
+
+/// Records the product of the reserves for the next `mint_fee`, in 256 bits
+fn write_k_last(env: &Env, balance0: i128, balance1: i128) -> Result<(), RaumFiPairError> {
+    let k = wide_mul(balance0, balance1)?;
+    let k = U256::from_parts(env, (k.hi() >> 64) as u64, k.hi() as u64, (k.lo() >> 64) as u64, k.lo() as u64);
+    env.storage().instance().set(&DataKey::KLast, &k);
+    Ok(())
+}
+
+/// `floor(sqrt(k_last))`, which fits `i128` as `k_last` is a product of two reserves
+fn root_k_last(k_last: &U256) -> i128 {
+    let hi = k_last.shr(128);
+    let lo = k_last.sub(&hi.shl(128));
+    sqrt_u256(full_math::U256::from_words(hi.to_u128().unwrap_or_default(), lo.to_u128().unwrap_or_default())) as i128
+}
+
+fn set_paused(env: &Env, paused: bool) -> Result<(), RaumFiPairError> {
+    let factory: Address = env.storage().instance().get(&DataKey::Factory).ok_or(RaumFiPairError::Forbidden)?;
+    factory.require_auth();
+    env.storage().instance().set(&DataKey::Paused, &paused);
+    // A resumed pair measures circuit breaker moves from its new price
+    circuit_breaker::reset(env);
+
+    let event = if paused { "Pause" } else { "Unpause" };
+    env.events().publish(
+        (Symbol::new(env, "RaumFiPair"), Symbol::new(env, event)),
+        factory
+    );
+    Ok(())
+}
+
+fn check_not_paused(env: &Env) -> Result<(), RaumFiPairError> {
+    if RaumFiPair::is_paused(env.clone()) {
+        return Err(RaumFiPairError::Forbidden);
+    }
+    Ok(())
+}
+
+fn update(env: &Env, balance0: i128, balance1: i128, reserve0: i128, reserve1: i128) -> Result<(), RaumFiPairError> {
+    let block_timestamp = env.ledger().timestamp();
+    let block_timestamp_last: u64 = env.storage().instance().get(&DataKey::BlockTimestampLast).unwrap_or(0);
+    let time_elapsed = block_timestamp.saturating_sub(block_timestamp_last);
+
+    if time_elapsed > 0 && reserve0 > 0 && reserve1 > 0 {
+        // Accumulate the prices of the previous reserves, weighted by how long they were in effect
+        let (price0_cumulative_last, price1_cumulative_last) = RaumFiPair::get_price_cumulative_last(env.clone());
+        let elapsed = U256::from_u128(env, time_elapsed as u128);
+        let price0 = encode_price(env, reserve1, reserve0)?;
+        let price1 = encode_price(env, reserve0, reserve1)?;
+
+        env.storage().instance().set(&DataKey::Price0CumulativeLast, &price0_cumulative_last.add(&price0.mul(&elapsed)));
+        env.storage().instance().set(&DataKey::Price1CumulativeLast, &price1_cumulative_last.add(&price1.mul(&elapsed)));
+    }
 
     // Update reserves and block timestamp
     env.storage().instance().set(&DataKey::Reserve0, &balance0);
     env.storage().instance().set(&DataKey::Reserve1, &balance1);
+    env.storage().instance().set(&DataKey::BlockTimestampLast, &block_timestamp);
 
     env.events().publish(
         (Symbol::new(&env, "RaumFiPair"), Symbol::new(&env, "Sync")),
@@ -308,6 +1087,15 @@ fn update(env: &Env, balance0: i128, balance1: i128) -> Result<(), RaumFiPairErr
     Ok(())
 }
 
+/// Encodes `numerator / denominator` as a UQ64x64 fixed-point value, the encoding
+/// `raumfi_library::UQ64x64` reads cumulative prices and TWAPs in. A reserve that is
+/// empty, or negative, has no price.
+fn encode_price(env: &Env, numerator: i128, denominator: i128) -> Result<U256, RaumFiPairError> {
+    let price = full_math::encode_price(numerator, denominator).map_err(|_| RaumFiPairError::InsufficientLiquidity)?;
+    let (hi, lo) = (price.hi(), price.lo());
+    Ok(U256::from_parts(env, (hi >> 64) as u64, hi as u64, (lo >> 64) as u64, lo as u64))
+}
+
 struct Guard<'a> {
     env: &'a Env,
 }
@@ -7,4 +7,6 @@ pub enum PairTokenError {
     InsufficientBalance = 1,
     InsufficientAllowance = 2,
     InvalidAmount = 3,
+    NegativeNotAllowed = 4,
+    PastTimeNotAllowed = 5,
 }
\ No newline at end of file
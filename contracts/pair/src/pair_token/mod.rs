@@ -1,12 +1,10 @@
 //#![no_std]
 
 pub mod pair_token;
-pub mod events;
 pub mod token_storage;
 pub mod error;
+pub mod bump;
 
 pub use pair_token::PairToken;
-pub use events::PairTokenEvents;
 pub use token_storage::PairTokenStorage;
 pub use error::PairTokenError;
-pub use pair_token::{__mint_token, __burn_token};
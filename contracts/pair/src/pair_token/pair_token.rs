@@ -1,81 +1,68 @@
-use soroban_sdk::{
-    contract, contractimpl, Address, Env, String
-};
-use soroban_token_sdk::TokenUtils;
+use soroban_sdk::{token, Address, Env, String};
+use soroban_token_sdk::metadata::TokenMetadata;
+use raumfi_lp_token::LP_TOKEN_DECIMALS;
 
-use crate::pair_token::{PairTokenEvents, PairTokenStorage, PairTokenError};
+use crate::pair_token::{PairTokenStorage, PairTokenError};
 
+/// Longest underlying token symbol that is embedded in the LP token name
+const MAX_SYMBOL_LEN: usize = 32;
+const LP_NAME_PREFIX: &[u8] = b"RaumFi LP ";
+const LP_SYMBOL: &str = "RFLP";
 
-#[contract]
 pub struct PairToken;
 
-
-#[contractimpl]
 impl PairToken {
 
     pub fn mint_token(env: &Env, to: Address, amount: i128) -> Result<(), PairTokenError> {
         if amount <= 0 {
             return Err(PairTokenError::InvalidAmount);
         }
-        let balance = Self::balance(env.clone(), to.clone());
-        PairTokenStorage::mint_logic(env, &to, amount, balance);
+        PairTokenStorage::mint(env, &to, amount);
         Ok(())
     }
     
     pub fn burn_token(env: &Env, from: Address, amount: i128) -> Result<(), PairTokenError> {
         if amount < 0 {
-            panic!("amount cannot be less than 0 -> : {}", amount)
+            return Err(PairTokenError::NegativeNotAllowed);
         }
-        let balance = Self::balance(env.clone(), from.clone());
-        PairTokenStorage::burn_logic(env, &from, amount, balance);
+        PairTokenStorage::burn(env, &from, amount);
         Ok(())
     }
 
-    pub fn balance(e: Env, id: Address) -> i128 {
-        PairTokenStorage::get_balance(&e, &id)
-    }
-
-    pub fn allowance(e: Env, owner: Address, spender: Address) -> i128 {
-        PairTokenStorage::get_allowance(&e, &owner, &spender)
-    }
+    /// Writes the LP token metadata, named after the symbols of the underlying tokens
+    pub fn write_metadata(env: &Env, token0: &Address, token1: &Address) {
+        let mut name = [0u8; LP_NAME_PREFIX.len() + 2 * MAX_SYMBOL_LEN + 1];
+        name[..LP_NAME_PREFIX.len()].copy_from_slice(LP_NAME_PREFIX);
+        let mut len = LP_NAME_PREFIX.len();
+        len += Self::copy_symbol(env, token0, &mut name[len..]);
+        name[len] = b'-';
+        len += 1;
+        len += Self::copy_symbol(env, token1, &mut name[len..]);
 
-    pub fn approve(e: Env, owner: Address, spender: Address, amount: i128) -> bool {
-        owner.require_auth();
-        PairTokenStorage::set_allowance(&e, &owner, &spender, amount);
-        PairTokenEvents::approval(&e, &owner, &spender, &amount);
-        true
+        PairTokenStorage::write_metadata(
+            env,
+            TokenMetadata {
+                decimal: LP_TOKEN_DECIMALS,
+                name: String::from_bytes(env, &name[..len]),
+                symbol: String::from_str(env, LP_SYMBOL),
+            },
+        );
     }
 
-    pub fn transfer(e: Env, from: Address, to: Address, amount: i128) -> bool {
-        from.require_auth();
-        Self::do_transfer(&e, &from, &to, amount)
-    }
-
-    fn do_transfer(e: &Env, from: &Address, to: &Address, amount: i128) -> bool {
-        let from_balance = Self::balance(e.clone(), from.clone());
-        if from_balance < amount {
-            panic!("from_balance cannot be less than amount -> : {}", amount);
+    /// Copies the symbol of `token` into `buf`, falling back to a placeholder for tokens
+    /// that do not expose a symbol or whose symbol is too long.
+    fn copy_symbol(env: &Env, token: &Address, buf: &mut [u8]) -> usize {
+        match token::Client::new(env, token).try_symbol() {
+            Ok(Ok(symbol)) if symbol.len() as usize <= MAX_SYMBOL_LEN => {
+                let len = symbol.len() as usize;
+                symbol.copy_into_slice(&mut buf[..len]);
+                len
+            }
+            _ => {
+                let unknown = b"UNKNOWN";
+                buf[..unknown.len()].copy_from_slice(unknown);
+                unknown.len()
+            }
         }
-        PairTokenStorage::set_balance(e, from, from_balance - amount);
-        let to_balance = Self::balance(e.clone(), to.clone());
-        PairTokenStorage::set_balance(e, to, to_balance + amount);
-        PairTokenEvents::transfer(e, from, to, &amount);
-        true
     }
-
-    pub fn read_decimal(e: &Env) -> u32 {
-        let util = TokenUtils::new(e);
-        util.metadata().get_metadata().decimal
-    }
-    
-    pub fn read_name(e: &Env) -> String {
-        let util = TokenUtils::new(e);
-        util.metadata().get_metadata().name
-    }
-    
-    pub fn read_symbol(e: &Env) -> String {
-        let util = TokenUtils::new(e);
-        util.metadata().get_metadata().symbol
-    }
-
 }
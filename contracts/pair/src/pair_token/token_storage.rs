@@ -1,66 +1,39 @@
-use soroban_sdk::{Address, Env, Symbol, symbol_short , log};
-use soroban_token_sdk::{metadata::TokenMetadata, TokenUtils};
+use soroban_sdk::{symbol_short, Address, Env, Symbol};
+use raumfi_lp_token::{LpToken, LpTokenConfig};
 
-pub const TOTAL_SUPPLY_KEY: Symbol = symbol_short!("totspply");
+use crate::pair_token::PairTokenError;
+
+// Legacy instance-storage keys. Pairs deployed before balances moved to
+// persistent storage still hold `(BALANCES_KEY, id)` entries in instance
+// storage; they are read as a fallback and moved on the next write.
 pub const BALANCES_KEY: Symbol = symbol_short!("balances");
 pub const ALLOWANCES_KEY: Symbol = symbol_short!("allowance");
 
-pub struct PairTokenStorage;
-
-impl PairTokenStorage {
-    pub fn get_total_supply(e: &Env) -> i128 {
-        e.storage().instance().get(&TOTAL_SUPPLY_KEY).unwrap_or(0)
-    }
-
-    pub fn set_total_supply(e: &Env, amount: i128) {
-        e.storage().instance().set(&TOTAL_SUPPLY_KEY, &amount);
-    }
-
-    pub fn get_balance(e: &Env, id: &Address) -> i128 {
-        e.storage().instance().get(&(BALANCES_KEY, id)).unwrap_or(0)
-    }
+/// Plugs the pair's errors and its legacy instance storage into the shared LP token
+pub struct PairLp;
 
-    pub fn set_balance(e: &Env, id: &Address, amount: i128) {
-        e.storage().instance().set(&(BALANCES_KEY, id), &amount)
-    }
+impl LpTokenConfig for PairLp {
+    type Error = PairTokenError;
 
-    pub fn get_allowance(e: &Env, owner: &Address, spender: &Address) -> i128 {
-        e.storage().instance().get(&(ALLOWANCES_KEY, owner, spender)).unwrap_or(0)
-    }
+    const INSUFFICIENT_BALANCE: PairTokenError = PairTokenError::InsufficientBalance;
+    const INSUFFICIENT_ALLOWANCE: PairTokenError = PairTokenError::InsufficientAllowance;
+    const NEGATIVE_NOT_ALLOWED: PairTokenError = PairTokenError::NegativeNotAllowed;
+    const PAST_TIME_NOT_ALLOWED: PairTokenError = PairTokenError::PastTimeNotAllowed;
 
-    pub fn set_allowance(e: &Env, owner: &Address, spender: &Address, amount: i128) {
-        e.storage().instance().set(&(ALLOWANCES_KEY, owner, spender), &amount);
+    fn legacy_balance(e: &Env, id: &Address) -> Option<i128> {
+        e.storage().instance().get(&(BALANCES_KEY, id))
     }
 
-    pub fn write_metadata(e: &Env, metadata: TokenMetadata) {
-        let util = TokenUtils::new(e);
-        util.metadata().set_metadata(&metadata);
+    fn remove_legacy_balance(e: &Env, id: &Address) {
+        e.storage().instance().remove(&(BALANCES_KEY, id));
     }
 
-    pub fn burn_logic(e: &Env, to: &Address, amount: i128 , balance: i128) {
-        if amount < 0 {
-            panic!("amount cannot be less than 0 -> : {}", amount)
-        }
-        let new_balance = balance.checked_sub(amount)
-        .expect("Integer overflow occurred");
-        e.storage().instance().set(&(BALANCES_KEY, to), &new_balance);
-        let total_supply = Self::get_total_supply(e);
-        let new_total_supply = total_supply.checked_add(amount)
-        .expect("Integer overflow occurred");
-        Self::set_total_supply(e, new_total_supply);
+    // Allowances granted by an older pair lived in instance storage
+    // without a TTL; any new approval supersedes them.
+    fn remove_legacy_allowance(e: &Env, owner: &Address, spender: &Address) {
+        e.storage().instance().remove(&(ALLOWANCES_KEY, owner, spender));
     }
+}
 
-    pub fn mint_logic(e: &Env, to: &Address, amount: i128 , balance: i128) {
-        if amount < 0 {
-            panic!("amount cannot be less than 0 -> : {}", amount)
-        }
-        let new_balance = balance.checked_add(amount)
-        .expect("Integer overflow occurred");
-        e.storage().instance().set(&(BALANCES_KEY, to), &new_balance);
-        log!(&e, "new_balance: {}", e.storage().instance().get::<_, i128>(&(BALANCES_KEY, to)).unwrap());    
-        let total_supply = Self::get_total_supply(e);
-        let new_total_supply = total_supply.checked_sub(amount)
-        .expect("Integer overflow occurred");
-        Self::set_total_supply(e, new_total_supply);
-    }
-}
\ No newline at end of file
+/// Balances, allowances and supply of the pair's LP token
+pub type PairTokenStorage = LpToken<PairLp>;
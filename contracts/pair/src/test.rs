@@ -2,11 +2,14 @@
 use super::*;
 use pair_token::token_storage::BALANCES_KEY;
 use soroban_sdk::{
-    testutils::{Address as TestAddress, Ledger, LedgerInfo, Events as EventsExt},
-    Address, Env, token , BytesN , log
+    testutils::{Address as TestAddress, Ledger, LedgerInfo, Events as EventsExt, AuthorizedFunction},
+    contract, contractimpl, symbol_short, vec, Address, Bytes, Env, IntoVal, Symbol, token , BytesN , log, U256
 };
 
-use crate::pair::{RaumFiPair, DataKey, RaumFiPairTrait , RaumFiPairClient};
+use crate::pair::{RaumFiPair, CreatorFee, DataKey, DirectionalFees, PairStats, RaumFiPairTrait , RaumFiPairClient};
+use crate::dynamic_fee::DynamicFeeConfig;
+use crate::lp_hold::LpHoldConfig;
+use crate::circuit_breaker::CircuitBreakerConfig;
 // use factory::RaumFiFactory;
 use token::Client as TokenClient;
 use token::StellarAssetClient as TokenAdminClient;
@@ -78,7 +81,7 @@ fn test_get_reserves() {
     let client = RaumFiPairClient::new(&env, &contract_id);
     
     // Initialize the contract
-    client.initialize(&token0.address, &token1.address, &factory);
+    client.initialize(&token0.address, &token1.address, &factory, &30);
 
     // Set initial reserves using the client
     // client.set_reserves(&100, &200, &12345);
@@ -89,7 +92,7 @@ fn test_get_reserves() {
         env.storage().instance().set(&DataKey::Reserve1 , &200i128) 
     });
     env.as_contract(&contract_id, || {
-        env.storage().instance().set(&DataKey::BlockTimestampLast , &12345u32) 
+        env.storage().instance().set(&DataKey::BlockTimestampLast , &12345u64) 
     });
     // Get reserves using the client
     let (reserve0, reserve1) = client.get_reserves();
@@ -98,6 +101,7 @@ fn test_get_reserves() {
     // Assert the values
     assert_eq!(reserve0, 100);
     assert_eq!(reserve1, 200);
+    assert_eq!(client.get_reserves_with_timestamp(), (100, 200, 12345));
 }
 
 
@@ -107,6 +111,8 @@ fn test_mint() {
     let fee_to_setter = Address::generate(&env);
     let pair_wasm = pair_token_wasm(&env);
     let factory_client = create_factory_contract(&env, &fee_to_setter, &pair_wasm);
+    // The first LP here receives fewer units than the factory's default minimum
+    factory_client.set_min_initial_liquidity(&0);
     // Set reserves and balances
     env.as_contract(&client.address, || {
         env.storage().instance().set(&DataKey::Reserve0, &1000i128);
@@ -130,6 +136,7 @@ fn test_mint() {
 
 
 #[test]
+#[should_panic(expected = "Error(Contract, #1)")]
 fn test_locked_mint() {
     let (env, client, token0, token1, factory , token0client, token1client) = setup_test();
     let fee_to_setter = Address::generate(&env);
@@ -154,7 +161,7 @@ fn test_swap() {
     let pair_wasm = pair_token_wasm(&env);
     let factory_client = create_factory_contract(&env, &user, &pair_wasm);
     // Initialize contract and set initial state
-    client.initialize(&token0.address, &token1.address, &factory_client.address);
+    client.initialize(&token0.address, &token1.address, &factory_client.address, &30);
    
 
     // Mint tokens to user and transfer to contract
@@ -175,6 +182,95 @@ fn test_swap() {
     assert_eq!(100_000_000_i128.checked_sub(16624979).unwrap(), reserve1);
 }
 
+#[test]
+fn test_swap_large_reserves() {
+    let (env, client, token0, token1, _factory, token0client, token1client) = setup_test();
+    let user = Address::generate(&env);
+    let pair_wasm = pair_token_wasm(&env);
+    let factory_client = create_factory_contract(&env, &user, &pair_wasm);
+    client.initialize(&token0.address, &token1.address, &factory_client.address, &30);
+
+    // A trillion tokens of 18 decimals on each side: the product of the reserves is
+    // around 10^60, far beyond i128
+    let reserve0: i128 = 1_000_000_000_000_000_000_000_000_000_000;
+    let reserve1: i128 = 2 * reserve0;
+    token0client.mint(&client.address, &reserve0);
+    token1client.mint(&client.address, &reserve1);
+    assert_eq!(client.mint(&user), 1_414_213_562_373_095_048_801_688_723_209);
+
+    let amount_in: i128 = reserve0 / 100;
+    token0client.mint(&client.address, &amount_in);
+    let amount_out: i128 = 19_743_160_687_941_225_977_009_218_095;
+    assert_eq!(client.get_amount_out(&token0.address, &amount_in), amount_out);
+    assert_eq!(client.try_swap(&0, &(amount_out + 1), &user), Err(Ok(RaumFiPairError::K)));
+    client.swap(&0, &amount_out, &user);
+
+    assert_eq!(client.get_reserves(), (reserve0 + amount_in, reserve1 - amount_out));
+    assert_eq!(token1.balance(&user), amount_out);
+}
+
+fn setup_quote_test(fee: u32) -> (Env, RaumFiPairClient<'static>, TokenClient<'static>, TokenClient<'static>, TokenAdminClient<'static>, TokenAdminClient<'static>, Address) {
+    let (env, client, token0, token1, _factory, token0client, token1client) = setup_test();
+    let user = Address::generate(&env);
+    let pair_wasm = pair_token_wasm(&env);
+    let factory_client = create_factory_contract(&env, &user, &pair_wasm);
+    client.initialize(&token0.address, &token1.address, &factory_client.address, &fee);
+
+    token0client.mint(&client.address, &50_000_000);
+    token1client.mint(&client.address, &100_000_000);
+    client.mint(&user);
+    (env, client, token0, token1, token0client, token1client, user)
+}
+
+#[test]
+fn test_get_amount_out() {
+    let (_env, client, token0, token1, token0client, token1client, user) = setup_quote_test(30);
+    // The output of `test_swap` is exactly the largest one the pair accepts
+    assert_eq!(client.get_amount_out(&token0.address, &10_000_000), 16_624_979);
+
+    for amount_in in [1_000_i128, 777_777, 5_000_000] {
+        let amount_out = client.get_amount_out(&token1.address, &amount_in);
+        token1client.mint(&client.address, &amount_in);
+        assert_eq!(client.try_swap(&(amount_out + 1), &0, &user), Err(Ok(RaumFiPairError::K)));
+        client.swap(&amount_out, &0, &user);
+
+        let amount_out = client.get_amount_out(&token0.address, &amount_in);
+        token0client.mint(&client.address, &amount_in);
+        assert_eq!(client.try_swap(&0, &(amount_out + 1), &user), Err(Ok(RaumFiPairError::K)));
+        client.swap(&0, &amount_out, &user);
+    }
+}
+
+#[test]
+fn test_get_amount_in() {
+    let (_env, client, token0, token1, token0client, token1client, user) = setup_quote_test(100);
+
+    for amount_out in [1_000_i128, 333_333, 4_000_000] {
+        let amount_in = client.get_amount_in(&token0.address, &amount_out);
+        token1client.mint(&client.address, &(amount_in - 1));
+        assert_eq!(client.try_swap(&amount_out, &0, &user), Err(Ok(RaumFiPairError::K)));
+        token1client.mint(&client.address, &1);
+        client.swap(&amount_out, &0, &user);
+
+        let amount_in = client.get_amount_in(&token1.address, &amount_out);
+        token0client.mint(&client.address, &(amount_in - 1));
+        assert_eq!(client.try_swap(&0, &amount_out, &user), Err(Ok(RaumFiPairError::K)));
+        token0client.mint(&client.address, &1);
+        client.swap(&0, &amount_out, &user);
+    }
+}
+
+#[test]
+fn test_quote_errors() {
+    let (_env, client, token0, token1, _token0client, _token1client, user) = setup_quote_test(30);
+    assert_eq!(client.try_get_amount_out(&user, &1_000), Err(Ok(RaumFiPairError::InvalidToken)));
+    assert_eq!(client.try_get_amount_out(&token0.address, &0), Err(Ok(RaumFiPairError::InsufficientInputAmount)));
+    // Too small to buy a single unit
+    assert_eq!(client.try_get_amount_out(&token1.address, &1), Err(Ok(RaumFiPairError::InsufficientOutputAmount)));
+    assert_eq!(client.try_get_amount_in(&token0.address, &50_000_000), Err(Ok(RaumFiPairError::InsufficientLiquidity)));
+    assert_eq!(client.try_get_amount_in(&token1.address, &-1), Err(Ok(RaumFiPairError::InsufficientOutputAmount)));
+}
+
 #[test]
 fn test_burn() {
     let (env, client, token0, token1, factory, token0client, token1client) = setup_test();
@@ -182,19 +278,1087 @@ fn test_burn() {
     let pair_wasm = pair_token_wasm(&env);
     let factory_client = create_factory_contract(&env, &user, &pair_wasm);
     // Initialize contract and set initial state
-    client.initialize(&token0.address, &token1.address, &factory_client.address);
+    client.initialize(&token0.address, &token1.address, &factory_client.address, &30);
     token0client.mint(&user, &3_000_0000);
     token0.transfer(&user, &client.address, &3_000_000);
     token1client.mint(&user, &3_000_0000);
     token1.transfer(&user, &client.address, &2_000_000);
-    client.mint(&user);
+    let liquidity = client.mint(&user);
 
     // Burn liquidity tokens
-    let result = client.burn(&user);
+    client.transfer(&user, &client.address, &liquidity);
+    let (amount0, amount1) = client.withdraw(&user);
     // Check new reserves (should be lower)
     let (reserve0, reserve1) = client.get_reserves();
     log!(&env, "reserve0: {}", reserve0);
     log!(&env, "reserve1: {}", reserve1);
-    assert!(reserve0 < 3_000_0000);
-    assert!(reserve1 < 2_000_0000);
+    assert_eq!(reserve0, 3_000_000 - amount0);
+    assert_eq!(reserve1, 2_000_000 - amount1);
+    assert!(reserve0 > 0 && reserve1 > 0);
+    assert_eq!(client.balance(&user), 0);
+
+    // Only the locked minimum liquidity remains
+    let invariants = client.check_invariants();
+    assert_eq!(invariants.total_supply, 1000);
+    assert_eq!(invariants.locked_liquidity, 1000);
+    assert!(invariants.holds);
+}
+
+#[test]
+fn test_withdraw_partial() {
+    let (env, client, token0, token1, _factory, token0client, token1client) = setup_test();
+    let user = Address::generate(&env);
+    let other = Address::generate(&env);
+    let pair_wasm = pair_token_wasm(&env);
+    let factory_client = create_factory_contract(&env, &user, &pair_wasm);
+    client.initialize(&token0.address, &token1.address, &factory_client.address, &30);
+    token0client.mint(&user, &4_000_000);
+    token0.transfer(&user, &client.address, &4_000_000);
+    token1client.mint(&user, &1_000_000);
+    token1.transfer(&user, &client.address, &1_000_000);
+    let liquidity = client.mint(&user);
+    assert_eq!(liquidity, 2_000_000 - 1000);
+
+    // Total supply is the sum of all balances, including the locked minimum
+    client.transfer(&user, &other, &500_000);
+    assert_eq!(client.check_invariants().total_supply, client.balance(&user) + client.balance(&other) + client.balance(&client.address));
+
+    // Only the LP sent to the pair is redeemed
+    client.transfer(&other, &client.address, &500_000);
+    let (amount0, amount1) = client.withdraw(&other);
+    assert_eq!((amount0, amount1), (1_000_000, 250_000));
+    assert_eq!(client.balance(&user), liquidity - 500_000);
+    assert_eq!(client.balance(&client.address), 1000);
+
+    let invariants = client.check_invariants();
+    assert_eq!(invariants.total_supply, 1_500_000);
+    assert_eq!((invariants.reserve0, invariants.reserve1), (3_000_000, 750_000));
+    assert_eq!(invariants.k, U256::from_u128(&env, 3_000_000 * 750_000));
+    assert!(invariants.holds);
+}
+
+#[test]
+#[should_panic(expected = "Error(Contract, #15)")]
+fn test_withdraw_minimum_liquidity_locked() {
+    let (env, client, token0, token1, _factory, token0client, token1client) = setup_test();
+    let user = Address::generate(&env);
+    let pair_wasm = pair_token_wasm(&env);
+    let factory_client = create_factory_contract(&env, &user, &pair_wasm);
+    client.initialize(&token0.address, &token1.address, &factory_client.address, &30);
+    token0client.mint(&user, &1_000_000);
+    token0.transfer(&user, &client.address, &1_000_000);
+    token1client.mint(&user, &1_000_000);
+    token1.transfer(&user, &client.address, &1_000_000);
+    client.mint(&user);
+
+    // Nothing was sent, so the locked minimum liquidity must not be redeemed
+    client.withdraw(&user);
+}
+
+
+#[test]
+fn test_mint_initial_liquidity_too_small() {
+    let (env, client, token0, token1, _factory, token0client, token1client) = setup_test();
+    let user = Address::generate(&env);
+    let pair_wasm = pair_token_wasm(&env);
+    let factory_client = create_factory_contract(&env, &user, &pair_wasm);
+    client.initialize(&token0.address, &token1.address, &factory_client.address, &30);
+    factory_client.set_min_initial_liquidity(&0);
+    token0client.mint(&user, &1_001);
+    token1client.mint(&user, &1_001);
+
+    // Less than MINIMUM_LIQUIDITY would leave the first LP a negative amount
+    token0.transfer(&user, &client.address, &500);
+    token1.transfer(&user, &client.address, &500);
+    assert_eq!(client.try_mint(&user), Err(Ok(RaumFiPairError::InsufficientInitialLiquidity)));
+
+    // Exactly MINIMUM_LIQUIDITY would leave it nothing
+    token0.transfer(&user, &client.address, &500);
+    token1.transfer(&user, &client.address, &500);
+    assert_eq!(client.try_mint(&user), Err(Ok(RaumFiPairError::InsufficientInitialLiquidity)));
+
+    token0.transfer(&user, &client.address, &1);
+    token1.transfer(&user, &client.address, &1);
+    assert_eq!(client.mint(&user), 1);
+}
+
+
+#[test]
+fn test_min_initial_liquidity() {
+    let (env, client, token0, token1, _factory, token0client, token1client) = setup_test();
+    let user = Address::generate(&env);
+    let pair_wasm = pair_token_wasm(&env);
+    let factory_client = create_factory_contract(&env, &user, &pair_wasm);
+    client.initialize(&token0.address, &token1.address, &factory_client.address, &30);
+    token0client.mint(&user, &1_001_000);
+    token1client.mint(&user, &1_001_000);
+
+    // The factory's default asks the first LP for as much as is locked
+    token0.transfer(&user, &client.address, &1_999);
+    token1.transfer(&user, &client.address, &1_999);
+    assert_eq!(client.try_mint(&user), Err(Ok(RaumFiPairError::InsufficientInitialLiquidity)));
+
+    factory_client.set_min_initial_liquidity(&1_000_000);
+    token0.transfer(&user, &client.address, &998_000);
+    token1.transfer(&user, &client.address, &998_000);
+    assert_eq!(client.try_mint(&user), Err(Ok(RaumFiPairError::InsufficientInitialLiquidity)));
+
+    token0.transfer(&user, &client.address, &1_001);
+    token1.transfer(&user, &client.address, &1_001);
+    assert_eq!(client.mint(&user), 1_000_000);
+}
+
+
+#[test]
+fn test_mint_donation_inflation() {
+    let (env, client, token0, token1, _factory, token0client, token1client) = setup_test();
+    let attacker = Address::generate(&env);
+    let user = Address::generate(&env);
+    let pair_wasm = pair_token_wasm(&env);
+    let factory_client = create_factory_contract(&env, &attacker, &pair_wasm);
+    // The attacker is also the fee recipient here; keep the protocol fee out of the count
+    factory_client.set_fees_enabled(&false);
+    client.initialize(&token0.address, &token1.address, &factory_client.address, &30);
+
+    // The attacker seeds the pair as thinly as allowed, then donates to inflate the
+    // price of a share
+    token0client.mint(&attacker, &10_000_002_000);
+    token1client.mint(&attacker, &10_000_002_000);
+    token0.transfer(&attacker, &client.address, &2_000);
+    token1.transfer(&attacker, &client.address, &2_000);
+    assert_eq!(client.mint(&attacker), 1_000);
+    token0.transfer(&attacker, &client.address, &10_000_000_000);
+    token1.transfer(&attacker, &client.address, &10_000_000_000);
+    client.sync();
+
+    // Worth 79.99 shares, the deposit would lose 1.2% to rounding
+    token0client.mint(&user, &1_000_000_000);
+    token1client.mint(&user, &1_000_000_000);
+    token0.transfer(&user, &client.address, &400_000_000);
+    token1.transfer(&user, &client.address, &400_000_000);
+    assert_eq!(client.try_mint(&user), Err(Ok(RaumFiPairError::InsufficientLiquidityMinted)));
+
+    token0.transfer(&user, &client.address, &600_000_000);
+    token1.transfer(&user, &client.address, &600_000_000);
+    assert_eq!(client.mint(&user), 199);
+
+    // Of the 10_000_002_000 the attacker put in, about half stays behind the locked
+    // minimum, against less than one share taken from the user
+    client.transfer(&attacker, &client.address, &1_000);
+    assert_eq!(client.withdraw(&attacker), (5_002_274_670, 5_002_274_670));
+}
+
+
+#[test]
+fn test_price_cumulative_last() {
+    let (env, client, token0, token1, factory, token0client, token1client) = setup_test();
+    let user = Address::generate(&env);
+    let pair_wasm = pair_token_wasm(&env);
+    let factory_client = create_factory_contract(&env, &user, &pair_wasm);
+    client.initialize(&token0.address, &token1.address, &factory_client.address, &30);
+
+    env.ledger().set_timestamp(1_000);
+    token0client.mint(&user, &50_000_000);
+    token0.transfer(&user, &client.address, &50_000_000);
+    token1client.mint(&user, &100_000_000);
+    token1.transfer(&user, &client.address, &100_000_000);
+    client.mint(&user);
+
+    // No time has elapsed with non-zero reserves yet
+    let (price0_cumulative, price1_cumulative) = client.get_price_cumulative_last();
+    assert_eq!(price0_cumulative, U256::from_u32(&env, 0));
+    assert_eq!(price1_cumulative, U256::from_u32(&env, 0));
+    assert_eq!(client.get_reserves_with_timestamp(), (50_000_000, 100_000_000, 1_000));
+
+    env.ledger().set_timestamp(1_600);
+    token0client.mint(&user, &10_000_000);
+    token0.transfer(&user, &client.address, &10_000_000);
+    client.swap(&0, &16624979, &user);
+
+    // Prices of the reserves before the swap, held for 600 seconds, in UQ64x64
+    let elapsed = U256::from_u32(&env, 600);
+    let (price0_cumulative, price1_cumulative) = client.get_price_cumulative_last();
+    assert_eq!(price0_cumulative, U256::from_u128(&env, 2u128 << 64).mul(&elapsed));
+    assert_eq!(price1_cumulative, U256::from_u128(&env, 1u128 << 63).mul(&elapsed));
+
+    let (_, _, block_timestamp_last) = client.get_reserves_with_timestamp();
+    assert_eq!(block_timestamp_last, 1_600);
+}
+
+#[test]
+fn test_skim() {
+    let (env, client, token0, token1, _factory, token0client, token1client) = setup_test();
+    let user = Address::generate(&env);
+    let pair_wasm = pair_token_wasm(&env);
+    let factory_client = create_factory_contract(&env, &user, &pair_wasm);
+    client.initialize(&token0.address, &token1.address, &factory_client.address, &30);
+
+    token0client.mint(&user, &5_000_000);
+    token0.transfer(&user, &client.address, &5_000_000);
+    token1client.mint(&user, &10_000_000);
+    token1.transfer(&user, &client.address, &10_000_000);
+    client.mint(&user);
+
+    // Tokens sent directly to the pair are not part of the reserves
+    token0client.mint(&client.address, &1_000);
+    token1client.mint(&client.address, &2_000);
+
+    let recipient = Address::generate(&env);
+    client.skim(&recipient);
+
+    assert_eq!(token0.balance(&recipient), 1_000);
+    assert_eq!(token1.balance(&recipient), 2_000);
+    assert_eq!(token0.balance(&client.address), 5_000_000);
+    assert_eq!(token1.balance(&client.address), 10_000_000);
+    assert_eq!(client.get_reserves(), (5_000_000, 10_000_000));
+}
+
+#[test]
+fn test_sync() {
+    let (env, client, token0, token1, _factory, token0client, token1client) = setup_test();
+    let user = Address::generate(&env);
+    let pair_wasm = pair_token_wasm(&env);
+    let factory_client = create_factory_contract(&env, &user, &pair_wasm);
+    client.initialize(&token0.address, &token1.address, &factory_client.address, &30);
+
+    token0client.mint(&user, &5_000_000);
+    token0.transfer(&user, &client.address, &5_000_000);
+    token1client.mint(&user, &10_000_000);
+    token1.transfer(&user, &client.address, &10_000_000);
+    client.mint(&user);
+
+    token0client.mint(&client.address, &1_000);
+    client.sync();
+
+    assert_eq!(client.get_reserves(), (5_001_000, 10_000_000));
+}
+
+#[test]
+#[should_panic(expected = "Error(Contract, #1)")]
+fn test_locked_sync() {
+    let (env, client, token0, token1, factory, _token0client, _token1client) = setup_test();
+    client.initialize(&token0.address, &token1.address, &factory, &30);
+
+    env.as_contract(&client.address, || {
+        env.storage().instance().set(&DataKey::Unlocked, &false);
+    });
+
+    client.sync();
+}
+
+#[test]
+fn test_lp_token() {
+    extern crate std;
+    use std::string::ToString;
+    let (env, client, token0, token1, _factory, token0client, token1client) = setup_test();
+    let user = Address::generate(&env);
+    let pair_wasm = pair_token_wasm(&env);
+    let factory_client = create_factory_contract(&env, &user, &pair_wasm);
+    client.initialize(&token0.address, &token1.address, &factory_client.address, &30);
+
+    let name = std::format!("RaumFi LP {}-{}", token0.symbol().to_string(), token1.symbol().to_string());
+    assert_eq!(client.name(), soroban_sdk::String::from_str(&env, &name));
+    assert_eq!(client.symbol(), soroban_sdk::String::from_str(&env, "RFLP"));
+    assert_eq!(client.decimals(), 7);
+
+    token0client.mint(&user, &5_000_000);
+    token0.transfer(&user, &client.address, &5_000_000);
+    token1client.mint(&user, &5_000_000);
+    token1.transfer(&user, &client.address, &5_000_000);
+    let liquidity = client.mint(&user);
+    assert_eq!(client.balance(&user), liquidity);
+
+    let other = Address::generate(&env);
+    let spender = Address::generate(&env);
+    client.transfer(&user, &other, &100);
+    assert_eq!(client.balance(&other), 100);
+
+    client.approve(&user, &spender, &500, &200);
+    assert_eq!(client.allowance(&user, &spender), 500);
+
+    client.transfer_from(&spender, &user, &other, &200);
+    assert_eq!(client.allowance(&user, &spender), 300);
+    assert_eq!(client.balance(&other), 300);
+
+    client.burn_from(&spender, &user, &100);
+    assert_eq!(client.allowance(&user, &spender), 200);
+    assert_eq!(client.balance(&user), liquidity - 400);
+
+    client.burn(&other, &50);
+    assert_eq!(client.balance(&other), 250);
+
+    // Allowances lapse after their expiration ledger
+    env.ledger().set_sequence_number(201);
+    assert_eq!(client.allowance(&user, &spender), 0);
+}
+
+#[test]
+fn test_lp_token_burn_keeps_reserves() {
+    let (env, client, token0, token1, _factory, token0client, token1client) = setup_test();
+    let user = Address::generate(&env);
+    let pair_wasm = pair_token_wasm(&env);
+    let factory_client = create_factory_contract(&env, &user, &pair_wasm);
+    client.initialize(&token0.address, &token1.address, &factory_client.address, &30);
+
+    token0client.mint(&client.address, &5_000_000);
+    token1client.mint(&client.address, &5_000_000);
+    let liquidity = client.mint(&user);
+    let total_supply = client.check_invariants().total_supply;
+
+    // The SEP-41 `burn` destroys LP without paying out its share of the reserves; that
+    // takes `withdraw`, which the pair's `burn(to)` was renamed to
+    client.burn(&user, &liquidity);
+    assert_eq!(client.balance(&user), 0);
+    assert_eq!(client.check_invariants().total_supply, total_supply - liquidity);
+    assert_eq!(client.get_reserves(), (5_000_000, 5_000_000));
+    assert_eq!(token0.balance(&client.address), 5_000_000);
+    assert_eq!(token1.balance(&client.address), 5_000_000);
+    assert_eq!(token0.balance(&user), 0);
+    assert_eq!(token1.balance(&user), 0);
+}
+
+#[test]
+#[should_panic(expected = "Error(Contract, #5)")]
+fn test_lp_token_approve_expired() {
+    let (env, client, token0, token1, factory, _token0client, _token1client) = setup_test();
+    client.initialize(&token0.address, &token1.address, &factory, &30);
+
+    env.ledger().set_sequence_number(100);
+    client.approve(&Address::generate(&env), &Address::generate(&env), &500, &99);
+}
+
+#[test]
+fn test_lp_token_storage() {
+    use raumfi_lp_token::{AllowanceDataKey, LpTokenDataKey};
+    let (env, client, token0, token1, _factory, token0client, token1client) = setup_test();
+    let user = Address::generate(&env);
+    let spender = Address::generate(&env);
+    let pair_wasm = pair_token_wasm(&env);
+    let factory_client = create_factory_contract(&env, &user, &pair_wasm);
+    client.initialize(&token0.address, &token1.address, &factory_client.address, &30);
+
+    token0client.mint(&user, &5_000_000);
+    token0.transfer(&user, &client.address, &5_000_000);
+    token1client.mint(&user, &5_000_000);
+    token1.transfer(&user, &client.address, &5_000_000);
+    client.mint(&user);
+    client.approve(&user, &spender, &500, &200);
+
+    env.as_contract(&client.address, || {
+        assert!(env.storage().persistent().has(&LpTokenDataKey::Balance(user.clone())));
+        assert!(!env.storage().instance().has(&(BALANCES_KEY, user.clone())));
+        let allowance_key = LpTokenDataKey::Allowance(AllowanceDataKey {
+            from: user.clone(),
+            spender: spender.clone(),
+        });
+        assert!(env.storage().temporary().has(&allowance_key));
+    });
+}
+
+#[test]
+fn test_migrate_balances() {
+    use raumfi_lp_token::LpTokenDataKey;
+    let (env, client, token0, token1, factory, _token0client, _token1client) = setup_test();
+    client.initialize(&token0.address, &token1.address, &factory, &30);
+    let holder = Address::generate(&env);
+    let other = Address::generate(&env);
+
+    // Balance written by a pair that predates persistent LP storage
+    env.as_contract(&client.address, || {
+        env.storage().instance().set(&(BALANCES_KEY, holder.clone()), &1_000_i128);
+    });
+    assert_eq!(client.balance(&holder), 1_000);
+
+    let migrated = client.migrate_balances(&vec![&env, holder.clone(), other.clone()]);
+    assert_eq!(migrated, 1);
+    assert_eq!(client.balance(&holder), 1_000);
+    env.as_contract(&client.address, || {
+        assert!(!env.storage().instance().has(&(BALANCES_KEY, holder.clone())));
+        assert!(env.storage().persistent().has(&LpTokenDataKey::Balance(holder.clone())));
+    });
+
+    // Already migrated holders are skipped
+    assert_eq!(client.migrate_balances(&vec![&env, holder.clone()]), 0);
+}
+
+#[test]
+fn test_migrate_negative_total_supply() {
+    let (env, client, token0, token1, factory, _token0client, _token1client) = setup_test();
+    client.initialize(&token0.address, &token1.address, &factory, &30);
+    let holder = Address::generate(&env);
+    let other = Address::generate(&env);
+
+    // An older pair subtracted minted LP from its total supply
+    env.as_contract(&client.address, || {
+        env.storage().instance().set(&(BALANCES_KEY, holder.clone()), &1_000_i128);
+        env.storage().instance().set(&(BALANCES_KEY, other.clone()), &500_i128);
+        env.storage().instance().set(&raumfi_lp_token::TOTAL_SUPPLY_KEY, &-1_500_i128);
+    });
+
+    assert_eq!(client.migrate_balances(&vec![&env, holder.clone()]), 1);
+    assert_eq!(client.check_invariants().total_supply, 1_500);
+    assert_eq!(client.migrate_balances(&vec![&env, other.clone()]), 1);
+    assert_eq!(client.check_invariants().total_supply, client.balance(&holder) + client.balance(&other));
+}
+
+#[test]
+fn test_upgrade() {
+    let (env, client, token0, token1, factory, _token0client, _token1client) = setup_test();
+    env.budget().reset_unlimited();
+    client.initialize(&token0.address, &token1.address, &factory, &30);
+
+    let new_wasm_hash = pair_token_wasm(&env);
+    client.upgrade(&new_wasm_hash);
+    // Only the factory may upgrade the pair
+    let auths = env.auths();
+    assert_eq!(auths[0].0, factory);
+    assert_eq!(
+        auths[0].1.function,
+        AuthorizedFunction::Contract((client.address.clone(), Symbol::new(&env, "upgrade"), (new_wasm_hash,).into_val(&env)))
+    );
+
+    // The upgraded pair keeps its storage
+    assert_eq!(client.get_pair_info().token0, token0.address);
+}
+
+fn setup_protocol_fee_test(share: Option<u32>) -> (Env, RaumFiPairClient<'static>, FactoryClient<'static>, Address) {
+    let (env, client, token0, token1, _factory, token0client, token1client) = setup_test();
+    let user = Address::generate(&env);
+    let fee_to_setter = Address::generate(&env);
+    let pair_wasm = pair_token_wasm(&env);
+    let factory_client = create_factory_contract(&env, &fee_to_setter, &pair_wasm);
+    factory_client.set_fees_enabled(&true);
+    if let Some(share) = share {
+        factory_client.set_protocol_fee_share(&share);
+    }
+    client.initialize(&token0.address, &token1.address, &factory_client.address, &30);
+
+    token0client.mint(&user, &20_000_000);
+    token1client.mint(&user, &20_000_000);
+    token0.transfer(&user, &client.address, &10_000_000);
+    token1.transfer(&user, &client.address, &10_000_000);
+    client.mint(&user);
+
+    token0.transfer(&user, &client.address, &1_000_000);
+    client.swap(&0, &906_610, &user);
+
+    // The next liquidity event mints the protocol fee
+    token0.transfer(&user, &client.address, &10_000);
+    token1.transfer(&user, &client.address, &10_000);
+    client.mint(&user);
+
+    (env, client, factory_client, fee_to_setter)
+}
+
+#[test]
+fn test_protocol_fee_on() {
+    let (_env, client, _factory_client, fee_to) = setup_protocol_fee_test(None);
+    assert_eq!(client.balance(&fee_to), 227);
+    assert_eq!(client.get_stats().protocol_fees_minted, 227);
+}
+
+#[test]
+fn test_protocol_fee_share() {
+    let (_env, client, _factory_client, fee_to) = setup_protocol_fee_test(Some(5000));
+    assert_eq!(client.balance(&fee_to), 681);
+}
+
+#[test]
+fn test_protocol_fee_off_clears_k_last() {
+    let (env, client, factory_client, fee_to) = setup_protocol_fee_test(None);
+    let k_last: U256 = env.as_contract(&client.address, || {
+        env.storage().instance().get(&DataKey::KLast).unwrap()
+    });
+    assert_ne!(k_last, U256::from_u32(&env, 0));
+
+    factory_client.set_fees_enabled(&false);
+    let user = Address::generate(&env);
+    client.transfer(&fee_to, &client.address, &227);
+    client.withdraw(&user);
+
+    let k_last: U256 = env.as_contract(&client.address, || {
+        env.storage().instance().get(&DataKey::KLast).unwrap()
+    });
+    assert_eq!(k_last, U256::from_u32(&env, 0));
+    assert_eq!(client.balance(&fee_to), 0);
+}
+
+fn setup_creator_fee_test(protocol_fee_on: bool) -> (Env, RaumFiPairClient<'static>, TokenClient<'static>, TokenClient<'static>, Address, Address) {
+    let (env, client, token0, token1, _factory, token0client, token1client) = setup_test();
+    let user = Address::generate(&env);
+    let creator = Address::generate(&env);
+    let fee_to_setter = Address::generate(&env);
+    let pair_wasm = pair_token_wasm(&env);
+    let factory_client = create_factory_contract(&env, &fee_to_setter, &pair_wasm);
+    factory_client.set_fees_enabled(&protocol_fee_on);
+    client.initialize(&token0.address, &token1.address, &factory_client.address, &30);
+    client.set_creator_fee(&CreatorFee { recipient: creator.clone(), share: 5000 });
+
+    token0client.mint(&user, &20_000_000);
+    token1client.mint(&user, &20_000_000);
+    token0.transfer(&user, &client.address, &10_000_000);
+    token1.transfer(&user, &client.address, &10_000_000);
+    client.mint(&user);
+
+    token0.transfer(&user, &client.address, &1_000_000);
+    client.swap(&0, &906_610, &user);
+
+    token0.transfer(&user, &client.address, &10_000);
+    token1.transfer(&user, &client.address, &10_000);
+    client.mint(&user);
+
+    (env, client, token0, token1, creator, fee_to_setter)
+}
+
+#[test]
+fn test_creator_fee() {
+    // Without protocol fees the creator's half of the growth is minted on its own
+    let (_env, client, _token0, _token1, creator, fee_to) = setup_creator_fee_test(false);
+    assert_eq!(client.balance(&creator), 681);
+    assert_eq!(client.balance(&fee_to), 0);
+    let stats = client.get_stats();
+    assert_eq!((stats.creator_fees_minted, stats.protocol_fees_minted), (681, 0));
+}
+
+#[test]
+fn test_creator_fee_with_protocol_fee() {
+    // The creator's 50% and the protocol's 1/6 are minted together and split by share
+    let (_env, client, _token0, _token1, creator, fee_to) = setup_creator_fee_test(true);
+    assert_eq!(client.balance(&creator), 681);
+    assert_eq!(client.balance(&fee_to), 228);
+}
+
+#[test]
+fn test_mint_fee_not_callable() {
+    // Fee growth is only minted by `mint` and `withdraw`, against the stored reserves;
+    // reserves passed in from outside would mint LP out of nothing
+    let (env, client, _token0, _token1, creator, _fee_to) = setup_creator_fee_test(true);
+    let total_supply = client.get_pair_info().total_supply;
+    let reserves = vec![&env, (1i128 << 100).into_val(&env), (1i128 << 100).into_val(&env)];
+    for _ in 0..2 {
+        let result = env.try_invoke_contract::<bool, soroban_sdk::Error>(&client.address, &Symbol::new(&env, "mint_fee"), reserves.clone());
+        assert!(result.is_err());
+    }
+    assert_eq!(client.get_pair_info().total_supply, total_supply);
+    assert_eq!(client.balance(&creator), 681);
+}
+
+#[test]
+fn test_transfer_creator_fee() {
+    let (env, client, token0, token1, creator, _fee_to) = setup_creator_fee_test(false);
+    let new_creator = Address::generate(&env);
+    client.transfer_creator_fee(&new_creator);
+    assert_eq!(client.get_creator_fee(), Some(CreatorFee { recipient: new_creator.clone(), share: 5000 }));
+
+    let user = Address::generate(&env);
+    let token0client = TokenAdminClient::new(&env, &token0.address);
+    let token1client = TokenAdminClient::new(&env, &token1.address);
+    token1client.mint(&client.address, &1_000_000);
+    client.swap(&1_000_000, &0, &user);
+    token0client.mint(&client.address, &10_000);
+    token1client.mint(&client.address, &10_000);
+    client.mint(&user);
+
+    assert_eq!(client.balance(&creator), 681);
+    assert!(client.balance(&new_creator) > 0);
+}
+
+#[test]
+fn test_creator_fee_invalid() {
+    let (env, client, token0, token1, factory, _token0client, _token1client) = setup_test();
+    client.initialize(&token0.address, &token1.address, &factory, &30);
+    assert_eq!(client.try_transfer_creator_fee(&factory), Err(Ok(RaumFiPairError::Forbidden)));
+    let recipient = Address::generate(&env);
+    assert_eq!(
+        client.try_set_creator_fee(&CreatorFee { recipient, share: 5001 }),
+        Err(Ok(RaumFiPairError::InvalidFee))
+    );
+}
+
+fn setup_lp_hold_test(exit_fee: u32) -> (Env, RaumFiPairClient<'static>, Address, i128) {
+    let (env, client, _token0, _token1, token0client, token1client, _user) = setup_quote_test(30);
+    client.set_lp_hold(&Some(LpHoldConfig { min_ledgers: 10, exit_fee }));
+
+    // Liquidity minted right before a swap, as a JIT bot would
+    env.ledger().set_sequence_number(100);
+    let bot = Address::generate(&env);
+    token0client.mint(&client.address, &1_000_000);
+    token1client.mint(&client.address, &2_000_000);
+    let liquidity = client.mint(&bot);
+    (env, client, bot, liquidity)
+}
+
+#[test]
+fn test_lp_hold() {
+    let (env, client, bot, liquidity) = setup_lp_hold_test(0);
+    assert_eq!(client.get_lp_hold_end(&bot), 110);
+
+    // Transferred LP keeps the hold period of the position it came from
+    let other = Address::generate(&env);
+    client.transfer(&bot, &other, &(liquidity / 2));
+    assert_eq!(client.get_lp_hold_end(&other), 110);
+
+    env.ledger().set_sequence_number(110);
+    assert_eq!(client.get_lp_hold_end(&bot), 110);
+    client.transfer(&bot, &client.address, &(liquidity - liquidity / 2));
+    assert_eq!(client.withdraw(&bot), (500_000, 1_000_000));
+}
+
+#[test]
+#[should_panic(expected = "Error(Contract, #22)")]
+fn test_lp_hold_early_burn() {
+    let (env, client, bot, liquidity) = setup_lp_hold_test(0);
+    env.ledger().set_sequence_number(109);
+    client.transfer(&bot, &client.address, &liquidity);
+}
+
+#[test]
+#[should_panic(expected = "Error(Contract, #22)")]
+fn test_lp_hold_transferred_early_burn() {
+    let (env, client, bot, liquidity) = setup_lp_hold_test(0);
+    let other = Address::generate(&env);
+    client.transfer(&bot, &other, &liquidity);
+    client.transfer(&other, &client.address, &liquidity);
+}
+
+#[test]
+fn test_lp_hold_mint_to_pair() {
+    let (env, client, _token0, _token1, token0client, token1client, _user) = setup_quote_test(30);
+    client.set_lp_hold(&Some(LpHoldConfig { min_ledgers: 10, exit_fee: 0 }));
+
+    // Minting straight to the pair and withdrawing in the same ledger would dodge the hold
+    env.ledger().set_sequence_number(100);
+    let bot = Address::generate(&env);
+    token0client.mint(&client.address, &1_000_000);
+    token1client.mint(&client.address, &2_000_000);
+    assert_eq!(client.try_mint(&client.address), Err(Ok(RaumFiPairError::InvalidTo)));
+    assert_eq!(client.try_withdraw(&bot), Err(Ok(RaumFiPairError::InsufficientLiquidityBurned)));
+}
+
+#[test]
+fn test_lp_exit_fee() {
+    let (env, client, bot, liquidity) = setup_lp_hold_test(500);
+    let total_supply = client.check_invariants().total_supply;
+
+    // 5% of the LP sent early is burned, to the benefit of the LPs that stay
+    env.ledger().set_sequence_number(101);
+    client.transfer(&bot, &client.address, &liquidity);
+    let fee = (liquidity * 500 + 9_999) / 10_000;
+    assert_eq!(client.balance(&client.address), 1000 + liquidity - fee);
+    assert_eq!(client.check_invariants().total_supply, total_supply - fee);
+    // The burned fee is shared by all remaining LP, the bot's own included
+    assert_eq!(client.withdraw(&bot), (950_931, 1_901_863));
+}
+
+#[test]
+fn test_lp_hold_invalid() {
+    let (_env, client, _token0, _token1, _token0client, _token1client, _user) = setup_quote_test(30);
+    assert_eq!(client.try_set_lp_hold(&Some(LpHoldConfig { min_ledgers: 0, exit_fee: 0 })), Err(Ok(RaumFiPairError::InvalidHoldPeriod)));
+    assert_eq!(client.try_set_lp_hold(&Some(LpHoldConfig { min_ledgers: 10, exit_fee: 10_000 })), Err(Ok(RaumFiPairError::InvalidHoldPeriod)));
+    assert_eq!(client.get_lp_hold(), None);
+}
+
+/// Sends `amount_in` of token0 to the pair and swaps it for all the token1 it buys,
+/// handing the input back if the swap is rejected
+fn swap_token0_in(client: &RaumFiPairClient, token0: &TokenClient, token0client: &TokenAdminClient, user: &Address, amount_in: i128) -> Result<(), RaumFiPairError> {
+    let amount_out = client.get_amount_out(&token0.address, &amount_in);
+    token0client.mint(&client.address, &amount_in);
+    match client.try_swap(&0, &amount_out, user) {
+        Ok(_) => Ok(()),
+        Err(error) => {
+            client.skim(user);
+            Err(error.unwrap())
+        }
+    }
+}
+
+#[test]
+fn test_circuit_breaker_ledger_move() {
+    let (env, client, token0, _token1, token0client, _token1client, user) = setup_quote_test(30);
+    env.ledger().set_sequence_number(100);
+    client.set_circuit_breaker(&Some(CircuitBreakerConfig { max_ledger_move: 500, max_window_move: 2_000, window: 3_600 }));
+
+    // Each swap moves the price of token0 about 4%, two of them more than the 5% allowed
+    assert_eq!(swap_token0_in(&client, &token0, &token0client, &user, 1_000_000), Ok(()));
+    assert_eq!(swap_token0_in(&client, &token0, &token0client, &user, 1_000_000), Err(RaumFiPairError::PriceMoveTooLarge));
+    assert_eq!(client.get_reserves(), (51_000_000, 98_044_984));
+
+    // The next ledger measures from the price the last one closed at
+    env.ledger().set_sequence_number(101);
+    assert_eq!(swap_token0_in(&client, &token0, &token0client, &user, 1_000_000), Ok(()));
+    assert!(!client.is_paused());
+}
+
+#[test]
+fn test_circuit_breaker_window_move() {
+    let (env, client, token0, _token1, token0client, _token1client, user) = setup_quote_test(30);
+    env.ledger().set_sequence_number(100);
+    client.set_circuit_breaker(&Some(CircuitBreakerConfig { max_ledger_move: 500, max_window_move: 1_000, window: 3_600 }));
+
+    assert_eq!(swap_token0_in(&client, &token0, &token0client, &user, 1_000_000), Ok(()));
+    env.ledger().set_sequence_number(101);
+    assert_eq!(swap_token0_in(&client, &token0, &token0client, &user, 1_000_000), Ok(()));
+
+    // The third swap takes the price more than 10% from where it stood within the window.
+    // It goes through, but pauses the pair until the factory resumes it.
+    env.ledger().set_sequence_number(102);
+    assert_eq!(swap_token0_in(&client, &token0, &token0client, &user, 1_000_000), Ok(()));
+    assert!(client.is_paused());
+    assert!(env.events().all().iter().any(|event| event.1 == (Symbol::new(&env, "RaumFiPair"), Symbol::new(&env, "CircuitBreak")).into_val(&env)));
+
+    env.ledger().set_sequence_number(103);
+    assert_eq!(swap_token0_in(&client, &token0, &token0client, &user, 1_000_000), Err(RaumFiPairError::Forbidden));
+
+    // A resumed pair measures moves from its new price
+    client.unpause();
+    assert_eq!(swap_token0_in(&client, &token0, &token0client, &user, 1_000_000), Ok(()));
+    assert!(!client.is_paused());
+}
+
+#[test]
+fn test_circuit_breaker_window() {
+    let (env, client, token0, _token1, token0client, _token1client, user) = setup_quote_test(30);
+    env.ledger().set_sequence_number(100);
+    env.ledger().set_timestamp(1_000);
+    client.set_circuit_breaker(&Some(CircuitBreakerConfig { max_ledger_move: 500, max_window_move: 1_000, window: 3_600 }));
+
+    assert_eq!(swap_token0_in(&client, &token0, &token0client, &user, 1_000_000), Ok(()));
+    env.ledger().set_sequence_number(101);
+    assert_eq!(swap_token0_in(&client, &token0, &token0client, &user, 1_000_000), Ok(()));
+
+    // Moves that add up over more than the window do not trip the breaker
+    env.ledger().set_sequence_number(102);
+    env.ledger().set_timestamp(4_600);
+    assert_eq!(swap_token0_in(&client, &token0, &token0client, &user, 1_000_000), Ok(()));
+    assert!(!client.is_paused());
+}
+
+#[test]
+fn test_circuit_breaker_window_slides() {
+    let (env, client, token0, _token1, token0client, _token1client, user) = setup_quote_test(30);
+    env.ledger().set_sequence_number(100);
+    env.ledger().set_timestamp(1_000);
+    client.set_circuit_breaker(&Some(CircuitBreakerConfig { max_ledger_move: 500, max_window_move: 1_000, window: 3_600 }));
+
+    assert_eq!(swap_token0_in(&client, &token0, &token0client, &user, 1_000_000), Ok(()));
+    env.ledger().set_sequence_number(101);
+    env.ledger().set_timestamp(4_000);
+    assert_eq!(swap_token0_in(&client, &token0, &token0client, &user, 1_000_000), Ok(()));
+    // The price before the first swap is a window old, the one before the second is not
+    env.ledger().set_sequence_number(102);
+    env.ledger().set_timestamp(4_700);
+    assert_eq!(swap_token0_in(&client, &token0, &token0client, &user, 1_000_000), Ok(()));
+
+    assert!(!client.is_paused());
+
+    // Three swaps within the last hour move the price more than 10%, although only two
+    // of them fall after the first hour of trading
+    env.ledger().set_sequence_number(103);
+    env.ledger().set_timestamp(5_000);
+    assert_eq!(swap_token0_in(&client, &token0, &token0client, &user, 1_000_000), Ok(()));
+    assert!(client.is_paused());
+}
+
+#[test]
+fn test_circuit_breaker_sync() {
+    let (env, client, _token0, _token1, token0client, _token1client, _user) = setup_quote_test(30);
+    env.ledger().set_sequence_number(100);
+    client.set_circuit_breaker(&Some(CircuitBreakerConfig { max_ledger_move: 500, max_window_move: 1_000, window: 3_600 }));
+
+    // Syncing a donation moves the price as a swap would, about 6% here
+    token0client.mint(&client.address, &3_000_000);
+    assert_eq!(client.try_sync(), Err(Ok(RaumFiPairError::PriceMoveTooLarge)));
+    assert_eq!(client.get_reserves(), (50_000_000, 100_000_000));
+}
+
+#[test]
+fn test_circuit_breaker_invalid() {
+    let (_env, client, _token0, _token1, _token0client, _token1client, _user) = setup_quote_test(30);
+    assert_eq!(
+        client.try_set_circuit_breaker(&Some(CircuitBreakerConfig { max_ledger_move: 0, max_window_move: 1_000, window: 3_600 })),
+        Err(Ok(RaumFiPairError::InvalidCircuitBreaker))
+    );
+    assert_eq!(
+        client.try_set_circuit_breaker(&Some(CircuitBreakerConfig { max_ledger_move: 500, max_window_move: 1_000, window: 0 })),
+        Err(Ok(RaumFiPairError::InvalidCircuitBreaker))
+    );
+    assert_eq!(client.get_circuit_breaker(), None);
+}
+
+fn setup_paused_pair() -> (Env, RaumFiPairClient<'static>, TokenClient<'static>, TokenClient<'static>, TokenAdminClient<'static>, TokenAdminClient<'static>, Address) {
+    let (env, client, token0, token1, token0client, token1client, user) = setup_quote_test(30);
+    client.pause();
+    (env, client, token0, token1, token0client, token1client, user)
+}
+
+#[test]
+fn test_pause() {
+    let (env, client, _token0, _token1, _token0client, _token1client, user) = setup_paused_pair();
+    assert!(client.is_paused());
+
+    let pause_event = env.events().all().last().unwrap();
+    assert_eq!(pause_event.1, (Symbol::new(&env, "RaumFiPair"), Symbol::new(&env, "Pause")).into_val(&env));
+
+    // LPs can still exit while the pair is paused
+    client.transfer(&user, &client.address, &1_000_000);
+    let (amount0, amount1) = client.withdraw(&user);
+    assert_eq!((amount0, amount1), (707_106, 1_414_213));
+
+    client.unpause();
+    assert!(!client.is_paused());
+    let unpause_event = env.events().all().last().unwrap();
+    assert_eq!(unpause_event.1, (Symbol::new(&env, "RaumFiPair"), Symbol::new(&env, "Unpause")).into_val(&env));
+}
+
+#[test]
+#[should_panic(expected = "Error(Contract, #3)")]
+fn test_paused_swap() {
+    let (_env, client, _token0, _token1, token0client, _token1client, user) = setup_paused_pair();
+    token0client.mint(&client.address, &1_000_000);
+    client.swap(&0, &1_900_000, &user);
+}
+
+#[test]
+#[should_panic(expected = "Error(Contract, #3)")]
+fn test_paused_mint() {
+    let (_env, client, _token0, _token1, token0client, token1client, user) = setup_paused_pair();
+    token0client.mint(&client.address, &1_000_000);
+    token1client.mint(&client.address, &2_000_000);
+    client.mint(&user);
+}
+
+#[test]
+fn test_get_pair_info() {
+    let (env, client, token0, token1, _factory, token0client, token1client) = setup_test();
+    let user = Address::generate(&env);
+    let pair_wasm = pair_token_wasm(&env);
+    let factory_client = create_factory_contract(&env, &user, &pair_wasm);
+    client.initialize(&token0.address, &token1.address, &factory_client.address, &5);
+
+    env.ledger().set_timestamp(1_234);
+    token0client.mint(&user, &4_000_000);
+    token0.transfer(&user, &client.address, &4_000_000);
+    token1client.mint(&user, &1_000_000);
+    token1.transfer(&user, &client.address, &1_000_000);
+    client.mint(&user);
+
+    let info = client.get_pair_info();
+    assert_eq!(info.token0, token0.address);
+    assert_eq!(info.token1, token1.address);
+    assert_eq!(info.factory, factory_client.address);
+    assert_eq!((info.reserve0, info.reserve1), (4_000_000, 1_000_000));
+    assert_eq!(info.total_supply, 2_000_000);
+    // Protocol fees are on, so the mint records k for the next fee
+    assert_eq!(info.k_last, U256::from_u128(&env, 4_000_000_000_000));
+    assert_eq!(info.fee, 5);
+    assert_eq!(info.block_timestamp_last, 1_234);
+    assert!(!info.locked);
+    assert!(!info.paused);
+}
+
+#[test]
+#[should_panic(expected = "Error(Contract, #17)")]
+fn test_get_pair_info_uninitialized() {
+    let (_env, client, _token0, _token1, _factory, _token0client, _token1client) = setup_test();
+    client.get_pair_info();
+}
+
+#[test]
+fn test_get_stats() {
+    let (env, client, token0, token1, _factory, token0client, token1client) = setup_test();
+    let user = Address::generate(&env);
+    let pair_wasm = pair_token_wasm(&env);
+    let factory_client = create_factory_contract(&env, &user, &pair_wasm);
+    client.initialize(&token0.address, &token1.address, &factory_client.address, &30);
+    assert_eq!(client.get_stats(), PairStats::default());
+
+    token0client.mint(&user, &20_000_000);
+    token1client.mint(&user, &20_000_000);
+    token0.transfer(&user, &client.address, &10_000_000);
+    token1.transfer(&user, &client.address, &10_000_000);
+    client.mint(&user);
+
+    token0.transfer(&user, &client.address, &1_000_000);
+    client.swap(&0, &906_610, &user);
+    token1.transfer(&user, &client.address, &500_000);
+    client.swap(&450_000, &0, &user);
+
+    let stats = client.get_stats();
+    assert_eq!(stats.volume0, 1_000_000 + 450_000);
+    assert_eq!(stats.volume1, 906_610 + 500_000);
+    assert_eq!(stats.lp_fees0, 3_000);
+    assert_eq!(stats.lp_fees1, 1_500);
+    assert_eq!(stats.protocol_fees_minted, 0);
+}
+
+fn setup_dynamic_fee_test() -> (Env, RaumFiPairClient<'static>, TokenClient<'static>, TokenClient<'static>, TokenAdminClient<'static>, TokenAdminClient<'static>, Address) {
+    let (env, client, token0, token1, token0client, token1client, user) = setup_quote_test(30);
+    client.set_dynamic_fee(&Some(DynamicFeeConfig { min_fee: 5, max_fee: 100 }));
+    (env, client, token0, token1, token0client, token1client, user)
+}
+
+/// Moves the price of token0 about 17% within the current ledger
+fn swap_dynamic_fee_pair(client: &RaumFiPairClient, token0: &TokenClient, token0client: &TokenAdminClient, user: &Address) {
+    let amount_out = client.get_amount_out(&token0.address, &5_000_000);
+    token0client.mint(&client.address, &5_000_000);
+    client.swap(&0, &amount_out, user);
+}
+
+#[test]
+fn test_dynamic_fee() {
+    let (env, client, token0, token1, token0client, token1client, user) = setup_dynamic_fee_test();
+    // A calm pair pays the lower bound
+    assert_eq!(client.get_fee(), 5);
+    swap_dynamic_fee_pair(&client, &token0, &token0client, &user);
+    assert_eq!(client.get_volatility(), 0);
+
+    // The move shows up once the next ledger trades
+    env.ledger().set_sequence_number(1);
+    assert_eq!(client.get_volatility(), 216);
+    assert_eq!(client.get_fee(), 100);
+    assert_eq!(client.get_amount_out(&token1.address, &500_000), 297_839);
+
+    token1client.mint(&client.address, &500_000);
+    client.swap(&297_839, &0, &user);
+    assert_eq!(client.get_stats().lp_fees1, 5_000);
+
+    client.set_dynamic_fee(&None);
+    assert_eq!(client.get_dynamic_fee(), None);
+    assert_eq!(client.get_fee(), 30);
+}
+
+#[test]
+#[should_panic(expected = "Error(Contract, #10)")]
+fn test_dynamic_fee_enforced() {
+    let (env, client, token0, _token1, token0client, token1client, user) = setup_dynamic_fee_test();
+    swap_dynamic_fee_pair(&client, &token0, &token0client, &user);
+    env.ledger().set_sequence_number(1);
+
+    // Output priced at the calm 5 bps fee no longer satisfies k
+    token1client.mint(&client.address, &500_000);
+    client.swap(&300_682, &0, &user);
+}
+
+#[test]
+#[should_panic(expected = "Error(Contract, #16)")]
+fn test_dynamic_fee_invalid_bounds() {
+    let (_env, client, token0, token1, factory, _token0client, _token1client) = setup_test();
+    client.initialize(&token0.address, &token1.address, &factory, &30);
+    client.set_dynamic_fee(&Some(DynamicFeeConfig { min_fee: 50, max_fee: 10 }));
+}
+
+#[test]
+fn test_directional_fees() {
+    let (_env, client, token0, token1, token0client, token1client, user) = setup_quote_test(30);
+    client.set_dynamic_fee(&Some(DynamicFeeConfig { min_fee: 5, max_fee: 100 }));
+    let fees = DirectionalFees { fee0_in: 30, fee1_in: 100 };
+    client.set_directional_fees(&Some(fees.clone()));
+
+    // Directional fees take precedence over the dynamic fee
+    assert_eq!(client.get_fee(), 5);
+    assert_eq!(client.get_fee_in(&token0.address), 30);
+    assert_eq!(client.get_fee_in(&token1.address), 100);
+    assert_eq!(client.get_directional_fees(), Some(fees));
+    let info = client.get_pair_info();
+    assert_eq!((info.fee, info.fee0_in, info.fee1_in), (5, 30, 100));
+
+    // Selling token1 pays 1%
+    let amount_out = client.get_amount_out(&token1.address, &1_000_000);
+    token1client.mint(&client.address, &1_000_000);
+    assert_eq!(client.try_swap(&(amount_out + 1), &0, &user), Err(Ok(RaumFiPairError::K)));
+    client.swap(&amount_out, &0, &user);
+    assert_eq!(client.get_stats().lp_fees1, 10_000);
+
+    // Buying it back pays 0.3%
+    let amount_in = client.get_amount_in(&token1.address, &1_000_000);
+    token0client.mint(&client.address, &(amount_in - 1));
+    assert_eq!(client.try_swap(&0, &1_000_000, &user), Err(Ok(RaumFiPairError::K)));
+    token0client.mint(&client.address, &1);
+    client.swap(&0, &1_000_000, &user);
+    assert_eq!(client.get_stats().lp_fees0, amount_in * 30 / 10_000);
+
+    client.set_directional_fees(&None);
+    assert_eq!(client.get_directional_fees(), None);
+    assert_eq!(client.get_fee_in(&token1.address), client.get_fee());
+}
+
+#[test]
+fn test_directional_fees_invalid() {
+    let (_env, client, token0, _token1, _token0client, _token1client, user) = setup_quote_test(30);
+    assert_eq!(
+        client.try_set_directional_fees(&Some(DirectionalFees { fee0_in: 30, fee1_in: 10_000 })),
+        Err(Ok(RaumFiPairError::InvalidFee))
+    );
+    assert_eq!(client.try_get_fee_in(&user), Err(Ok(RaumFiPairError::InvalidToken)));
+    assert_eq!(client.get_fee_in(&token0.address), 30);
+}
+
+#[contract]
+pub struct FlashBorrower;
+
+#[contractimpl]
+impl FlashBorrower {
+    pub fn init(env: Env, pair: Address, token1: Address, repay: bool) {
+        env.storage().instance().set(&symbol_short!("pair"), &pair);
+        env.storage().instance().set(&symbol_short!("token1"), &token1);
+        env.storage().instance().set(&symbol_short!("repay"), &repay);
+    }
+
+    pub fn raumfi_call(env: Env, sender: Address, _amount0_out: i128, amount1_out: i128, data: Bytes) {
+        assert_eq!(sender, env.current_contract_address());
+        assert_eq!(data, Bytes::from_array(&env, &[1, 2, 3]));
+
+        let repay: bool = env.storage().instance().get(&symbol_short!("repay")).unwrap();
+        if repay {
+            // Principal plus the 0.3% fee, rounded up
+            let pair: Address = env.storage().instance().get(&symbol_short!("pair")).unwrap();
+            let token1: Address = env.storage().instance().get(&symbol_short!("token1")).unwrap();
+            let amount1_in = amount1_out * 10_000 / 9_970 + 1;
+            token::Client::new(&env, &token1).transfer(&env.current_contract_address(), &pair, &amount1_in);
+        }
+    }
+}
+
+fn setup_flash_swap(repay: bool) -> (Env, RaumFiPairClient<'static>, TokenClient<'static>, Address) {
+    let (env, client, token0, token1, _factory, token0client, token1client) = setup_test();
+    let user = Address::generate(&env);
+    let pair_wasm = pair_token_wasm(&env);
+    let factory_client = create_factory_contract(&env, &user, &pair_wasm);
+    client.initialize(&token0.address, &token1.address, &factory_client.address, &30);
+
+    token0client.mint(&user, &50_000_000);
+    token0.transfer(&user, &client.address, &50_000_000);
+    token1client.mint(&user, &100_000_000);
+    token1.transfer(&user, &client.address, &100_000_000);
+    client.mint(&user);
+
+    let borrower = env.register_contract(None, FlashBorrower{});
+    FlashBorrowerClient::new(&env, &borrower).init(&client.address, &token1.address, &repay);
+    // The borrower only holds enough to pay the fee
+    token1client.mint(&borrower, &10_000);
+
+    (env, client, token1, borrower)
+}
+
+#[test]
+fn test_flash_swap() {
+    let (env, client, token1, borrower) = setup_flash_swap(true);
+
+    let data = Bytes::from_array(&env, &[1, 2, 3]);
+    client.swap_with_data(&borrower, &0, &1_000_000, &borrower, &data);
+
+    // The loan was repaid with the fee in the same invocation
+    let (reserve0, reserve1) = client.get_reserves();
+    assert_eq!(reserve0, 50_000_000);
+    assert_eq!(reserve1, 100_000_000 + 1_000_000 * 10_000 / 9_970 + 1 - 1_000_000);
+    assert_eq!(token1.balance(&borrower), 10_000 - (1_000_000 * 10_000 / 9_970 + 1 - 1_000_000));
+}
+
+#[test]
+#[should_panic(expected = "Error(Contract, #9)")]
+fn test_flash_swap_not_repaid() {
+    let (env, client, _token1, borrower) = setup_flash_swap(false);
+
+    let data = Bytes::from_array(&env, &[1, 2, 3]);
+    client.swap_with_data(&borrower, &0, &1_000_000, &borrower, &data);
 }
@@ -0,0 +1,48 @@
+//! Commit-reveal swaps, for trades too large to show their path and slippage bound in the
+//! mempool. The trader escrows the input with the router alongside the hash of the swap,
+//! then reveals the swap in a later ledger, when it is executed straight away.
+
+use soroban_sdk::{contracttype, xdr::ToXdr, Address, BytesN, Env, Vec};
+
+/// Ledgers after the commitment within which a swap can be revealed, about an hour
+pub const REVEAL_WINDOW_LEDGERS: u32 = 720;
+
+#[contracttype]
+#[derive(Clone)]
+pub enum DataKey {
+    /// Swap committed by a trader, under the hash of its terms
+    SwapCommitment(Address, BytesN<32>),
+}
+
+/// Input escrowed by the router for a committed swap
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct SwapCommitment {
+    pub token_in: Address,
+    pub amount_in: i128,
+    /// Ledger of the commitment; the swap can be revealed in the ledgers after it
+    pub ledger: u32,
+}
+
+/// Hash a trader commits to for a swap of `amount_in` along `path`. The salt keeps
+/// the terms from being guessed from the hash.
+pub fn commitment_hash(env: &Env, amount_in: i128, amount_out_min: i128, path: &Vec<Address>, to: &Address, salt: &BytesN<32>) -> BytesN<32> {
+    let terms = (amount_in, amount_out_min, path.clone(), to.clone(), salt.clone()).to_xdr(env);
+    env.crypto().sha256(&terms).into()
+}
+
+pub fn read_commitment(env: &Env, trader: &Address, commitment: &BytesN<32>) -> Option<SwapCommitment> {
+    env.storage().persistent().get(&DataKey::SwapCommitment(trader.clone(), commitment.clone()))
+}
+
+pub fn write_commitment(env: &Env, trader: &Address, commitment: &BytesN<32>, escrow: &SwapCommitment) {
+    let key = DataKey::SwapCommitment(trader.clone(), commitment.clone());
+    env.storage().persistent().set(&key, escrow);
+    // Escrowed funds stay recorded until revealed or cancelled; the entry only has to
+    // stay live through the reveal window
+    env.storage().persistent().extend_ttl(&key, REVEAL_WINDOW_LEDGERS, REVEAL_WINDOW_LEDGERS);
+}
+
+pub fn remove_commitment(env: &Env, trader: &Address, commitment: &BytesN<32>) {
+    env.storage().persistent().remove(&DataKey::SwapCommitment(trader.clone(), commitment.clone()));
+}
@@ -4,6 +4,7 @@ mod router;
 mod error;
 mod factory_client;
 mod helper;
+mod commit_reveal;
 
 #[cfg(test)]
 mod test;
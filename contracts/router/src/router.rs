@@ -2,19 +2,28 @@ use core::clone;
 
 use raumfi_library::RaumFiV2Library;
 use soroban_sdk::{
-    contract, contractimpl, Address, Env,  Symbol, Vec,
+    contract, contractimpl, Address, BytesN, Env,  Symbol, Vec,
      symbol_short , token::Client as TokenClient, log
 };
 use raumfi_library::*;
 
 use crate::factory_client::FactoryClient;
 use crate::helper::*;
+use crate::commit_reveal::{self, SwapCommitment, REVEAL_WINDOW_LEDGERS};
 
 soroban_sdk::contractimport!(
     file = "D:/Raum-Core/target/wasm32-unknown-unknown/release/pair.wasm"
 );
 pub type PairClient<'a> = Client<'a>;
 
+mod stable_pair {
+    soroban_sdk::contractimport!(
+        file = "D:/Raum-Core/target/wasm32-unknown-unknown/release/stable_pair.wasm"
+    );
+    pub type StablePairClient<'a> = Client<'a>;
+}
+use stable_pair::StablePairClient;
+
 pub const FACTORY: Symbol = symbol_short!("Factory");
 pub const NATIVE: Symbol = symbol_short!("Native");
 
@@ -67,14 +76,14 @@ impl RaumFiRouter {
         if reserve_a == 0 && reserve_b == 0 {
             (amount_a_desired, amount_b_desired)
         } else {
-            let amount_b_optimal =  RaumFiV2Library::calculate_quote(env, amount_a_desired, reserve_a, reserve_b).unwrap();
+            let amount_b_optimal =  RaumFiV2Library::calculate_quote(amount_a_desired, reserve_a, reserve_b).unwrap();
             if amount_b_optimal <= amount_b_desired {
                 if amount_b_optimal < amount_b_min {
                     panic!("RaumFiRouter: INSUFFICIENT_B_AMOUNT");
                 }
                 (amount_a_desired, amount_b_optimal)
             } else {
-                let amount_a_optimal = RaumFiV2Library::calculate_quote(env , amount_b_desired, reserve_b, reserve_a).unwrap();
+                let amount_a_optimal = RaumFiV2Library::calculate_quote(amount_b_desired, reserve_b, reserve_a).unwrap();
                 assert!(amount_a_optimal <= amount_a_desired);
                 if amount_a_optimal < amount_a_min {
                     panic!("RaumFiRouter: INSUFFICIENT_A_AMOUNT");
@@ -137,7 +146,7 @@ impl RaumFiRouter {
         let pair = crate::helper::pair_for(env.clone(), Self::factory(&env).clone(), token_a.clone(), token_b.clone()).unwrap();
         let pair_client = PairClient::new(&env, &pair);
         pair_client.transfer(&to , &pair, &liquidity);
-        let (amount0, amount1) = pair_client.burn(&to);
+        let (amount0, amount1) = pair_client.withdraw(&to);
         let (token0, _) = crate::helper::sort_tokens(token_a.clone(), token_b.clone()).unwrap();
         let (amount_a, amount_b) = if token_a == token0 {
             (amount0, amount1)
@@ -154,17 +163,17 @@ impl RaumFiRouter {
     }
 
     pub fn quote(env: Env, amount_a: i128, reserve_a: i128, reserve_b: i128) -> i128 {
-        let quote = RaumFiV2Library::calculate_quote(&env, amount_a, reserve_a, reserve_b).unwrap();
+        let quote = RaumFiV2Library::calculate_quote(amount_a, reserve_a, reserve_b).unwrap();
         log!(&env, "quote: {}", quote);
         quote
     }
 
-    pub fn get_amount_out(env: Env, amount_in: i128, reserve_in: i128, reserve_out: i128) -> i128 {
-        RaumFiV2Library::get_amount_out(&env, amount_in, reserve_in, reserve_out).unwrap()
+    pub fn get_amount_out(env: Env, amount_in: i128, reserve_in: i128, reserve_out: i128, fee: u32) -> i128 {
+        RaumFiV2Library::calculate_amount_out_with_fee(amount_in, reserve_in, reserve_out, fee).unwrap()
     }
 
-    pub fn get_amount_in(env: Env, amount_out: i128, reserve_in: i128, reserve_out: i128) -> i128 {
-        RaumFiV2Library::get_amount_in(&env, amount_out, reserve_in, reserve_out).unwrap()
+    pub fn get_amount_in(env: Env, amount_out: i128, reserve_in: i128, reserve_out: i128, fee: u32) -> i128 {
+        RaumFiV2Library::calculate_amount_in_with_fee(amount_out, reserve_in, reserve_out, fee).unwrap()
     }
 
     pub fn get_amounts_out(env: Env, amount_in: i128, path: Vec<Address>) -> Vec<i128> {
@@ -217,6 +226,241 @@ impl RaumFiRouter {
         amounts
     }
 
+    /// Hash to pass to `commit_swap` for a swap of `amount_in` along `path` paying out to
+    /// `to`, no less than `amount_out_min`
+    pub fn swap_commitment(
+        env: Env,
+        amount_in: i128,
+        amount_out_min: i128,
+        path: Vec<Address>,
+        to: Address,
+        salt: BytesN<32>,
+    ) -> BytesN<32> {
+        commit_reveal::commitment_hash(&env, amount_in, amount_out_min, &path, &to, &salt)
+    }
+
+    /// Escrows `amount_in` of `token_in` from `trader` against `commitment`, a swap that
+    /// stays hidden until `reveal_swap` in one of the next `REVEAL_WINDOW_LEDGERS` ledgers
+    pub fn commit_swap(env: Env, trader: Address, token_in: Address, amount_in: i128, commitment: BytesN<32>) {
+        trader.require_auth();
+        if amount_in <= 0 {
+            panic!("RaumFiRouter: INSUFFICIENT_INPUT_AMOUNT");
+        }
+        if commit_reveal::read_commitment(&env, &trader, &commitment).is_some() {
+            panic!("RaumFiRouter: COMMITMENT_EXISTS");
+        }
+        TokenClient::new(&env, &token_in).transfer(&trader, &env.current_contract_address(), &amount_in);
+        let escrow = SwapCommitment { token_in, amount_in, ledger: env.ledger().sequence() };
+        commit_reveal::write_commitment(&env, &trader, &commitment, &escrow);
+    }
+
+    /// Executes the swap `trader` committed to with the escrowed input, given its terms.
+    /// A swap whose output falls short of `amount_out_min` fails and stays committed.
+    pub fn reveal_swap(
+        env: Env,
+        trader: Address,
+        amount_in: i128,
+        amount_out_min: i128,
+        path: Vec<Address>,
+        to: Address,
+        salt: BytesN<32>,
+        deadline: u64,
+    ) -> Vec<i128> {
+        Self::ensure(&env, deadline);
+        trader.require_auth();
+        let commitment = commit_reveal::commitment_hash(&env, amount_in, amount_out_min, &path, &to, &salt);
+        let escrow = match commit_reveal::read_commitment(&env, &trader, &commitment) {
+            Some(escrow) => escrow,
+            None => panic!("RaumFiRouter: COMMITMENT_NOT_FOUND"),
+        };
+        let ledger = env.ledger().sequence();
+        if ledger <= escrow.ledger {
+            panic!("RaumFiRouter: REVEAL_TOO_EARLY");
+        }
+        if ledger > escrow.ledger.saturating_add(REVEAL_WINDOW_LEDGERS) {
+            panic!("RaumFiRouter: COMMITMENT_EXPIRED");
+        }
+        if path.len() < 2 || path.get(0).unwrap() != escrow.token_in || amount_in != escrow.amount_in {
+            panic!("RaumFiRouter: COMMITMENT_MISMATCH");
+        }
+        commit_reveal::remove_commitment(&env, &trader, &commitment);
+
+        let amounts = RaumFiV2Library::get_amounts_out(&env, Self::factory(&env), amount_in, path.clone()).unwrap();
+        if amounts.get(amounts.len() - 1).unwrap() < amount_out_min {
+            panic!("RaumFiRouter: INSUFFICIENT_OUTPUT_AMOUNT");
+        }
+        let pair = RaumFiV2Library::pair_for(&env, Self::factory(&env), path.get(0).unwrap(), path.get(1).unwrap()).unwrap();
+        TokenClient::new(&env, &escrow.token_in).transfer(&env.current_contract_address(), &pair, &amount_in);
+        Self::_swap(&env, &amounts, &path, &to);
+        amounts
+    }
+
+    /// Returns the input escrowed for `commitment` to `trader`, whether or not its reveal
+    /// window is over
+    pub fn cancel_swap_commitment(env: Env, trader: Address, commitment: BytesN<32>) -> i128 {
+        trader.require_auth();
+        let escrow = match commit_reveal::read_commitment(&env, &trader, &commitment) {
+            Some(escrow) => escrow,
+            None => panic!("RaumFiRouter: COMMITMENT_NOT_FOUND"),
+        };
+        commit_reveal::remove_commitment(&env, &trader, &commitment);
+        TokenClient::new(&env, &escrow.token_in).transfer(&env.current_contract_address(), &trader, &escrow.amount_in);
+        escrow.amount_in
+    }
+
+    pub fn get_swap_commitment(env: Env, trader: Address, commitment: BytesN<32>) -> Option<SwapCommitment> {
+        commit_reveal::read_commitment(&env, &trader, &commitment)
+    }
+
+    fn stable_pair(env: &Env, token_a: &Address, token_b: &Address) -> Address {
+        match FactoryClient::new(env, &Self::factory(env)).get_stable_pair(token_a, token_b) {
+            Some(pair) => pair,
+            None => panic!("RaumFiRouter: STABLE_PAIR_DOES_NOT_EXIST"),
+        }
+    }
+
+    /// Deposits any mix of the two tokens into their StableSwap pair
+    pub fn add_liquidity_stable(
+        env: Env,
+        token_a: Address,
+        token_b: Address,
+        amount_a: i128,
+        amount_b: i128,
+        liquidity_min: i128,
+        to: Address,
+        deadline: u64,
+    ) -> i128 {
+        Self::ensure(&env, deadline);
+        to.require_auth();
+        let pair = Self::stable_pair(&env, &token_a, &token_b);
+        if amount_a > 0 {
+            TokenClient::new(&env, &token_a).transfer(&to, &pair, &amount_a);
+        }
+        if amount_b > 0 {
+            TokenClient::new(&env, &token_b).transfer(&to, &pair, &amount_b);
+        }
+        let liquidity = StablePairClient::new(&env, &pair).mint(&to);
+        if liquidity < liquidity_min {
+            panic!("RaumFiRouter: INSUFFICIENT_LIQUIDITY_MINTED");
+        }
+        liquidity
+    }
+
+    pub fn remove_liquidity_stable(
+        env: Env,
+        token_a: Address,
+        token_b: Address,
+        liquidity: i128,
+        amount_a_min: i128,
+        amount_b_min: i128,
+        to: Address,
+        deadline: u64,
+    ) -> (i128, i128) {
+        Self::ensure(&env, deadline);
+        to.require_auth();
+        let pair = Self::stable_pair(&env, &token_a, &token_b);
+        let pair_client = StablePairClient::new(&env, &pair);
+        pair_client.transfer(&to, &pair, &liquidity);
+        let (amount0, amount1) = pair_client.withdraw(&to);
+        let (token0, _) = crate::helper::sort_tokens(token_a.clone(), token_b.clone()).unwrap();
+        let (amount_a, amount_b) = if token_a == token0 { (amount0, amount1) } else { (amount1, amount0) };
+        if amount_a < amount_a_min {
+            panic!("RaumFiRouter: INSUFFICIENT_A_AMOUNT");
+        }
+        if amount_b < amount_b_min {
+            panic!("RaumFiRouter: INSUFFICIENT_B_AMOUNT");
+        }
+        (amount_a, amount_b)
+    }
+
+    /// Quotes a swap along `path` through StableSwap pairs
+    pub fn get_amounts_out_stable(env: Env, amount_in: i128, path: Vec<Address>) -> Vec<i128> {
+        if path.len() < 2 {
+            panic!("RaumFiRouter: INVALID_PATH");
+        }
+        let mut amounts = Vec::new(&env);
+        amounts.push_back(amount_in);
+        for i in 0..path.len() - 1 {
+            let (input, output) = (path.get(i).unwrap(), path.get(i + 1).unwrap());
+            let pair = Self::stable_pair(&env, &input, &output);
+            let amount_out = StablePairClient::new(&env, &pair).get_amount_out(&input, &amounts.get(i).unwrap());
+            amounts.push_back(amount_out);
+        }
+        amounts
+    }
+
+    /// Quotes the inputs needed along `path` through StableSwap pairs to receive `amount_out`
+    pub fn get_amounts_in_stable(env: Env, amount_out: i128, path: Vec<Address>) -> Vec<i128> {
+        if path.len() < 2 {
+            panic!("RaumFiRouter: INVALID_PATH");
+        }
+        let mut amounts = Vec::new(&env);
+        amounts.push_front(amount_out);
+        for i in (1..path.len()).rev() {
+            let (input, output) = (path.get(i - 1).unwrap(), path.get(i).unwrap());
+            let pair = Self::stable_pair(&env, &input, &output);
+            let amount_in = StablePairClient::new(&env, &pair).get_amount_in(&output, &amounts.get(0).unwrap());
+            amounts.push_front(amount_in);
+        }
+        amounts
+    }
+
+    pub fn swap_exact_in_stable(
+        env: Env,
+        amount_in: i128,
+        amount_out_min: i128,
+        path: Vec<Address>,
+        to: Address,
+        deadline: u64,
+    ) -> Vec<i128> {
+        Self::ensure(&env, deadline);
+        to.require_auth();
+        let amounts = Self::get_amounts_out_stable(env.clone(), amount_in, path.clone());
+        if amounts.get(amounts.len() - 1).unwrap() < amount_out_min {
+            panic!("RaumFiRouter: INSUFFICIENT_OUTPUT_AMOUNT");
+        }
+        let pair = Self::stable_pair(&env, &path.get(0).unwrap(), &path.get(1).unwrap());
+        TokenClient::new(&env, &path.get(0).unwrap()).transfer(&to, &pair, &amounts.get(0).unwrap());
+        Self::_swap_stable(&env, &amounts, &path, &to);
+        amounts
+    }
+
+    pub fn swap_exact_out_stable(
+        env: Env,
+        amount_out: i128,
+        amount_in_max: i128,
+        path: Vec<Address>,
+        to: Address,
+        deadline: u64,
+    ) -> Vec<i128> {
+        Self::ensure(&env, deadline);
+        to.require_auth();
+        let amounts = Self::get_amounts_in_stable(env.clone(), amount_out, path.clone());
+        if amounts.get(0).unwrap() > amount_in_max {
+            panic!("RaumFiRouter: EXCESSIVE_INPUT_AMOUNT");
+        }
+        let pair = Self::stable_pair(&env, &path.get(0).unwrap(), &path.get(1).unwrap());
+        TokenClient::new(&env, &path.get(0).unwrap()).transfer(&to, &pair, &amounts.get(0).unwrap());
+        Self::_swap_stable(&env, &amounts, &path, &to);
+        amounts
+    }
+
+    fn _swap_stable(env: &Env, amounts: &Vec<i128>, path: &Vec<Address>, _to: &Address) {
+        for i in 0..path.len() - 1 {
+            let (input, output) = (path.get(i).unwrap(), path.get(i + 1).unwrap());
+            let (token0, _) = RaumFiV2Library::sort_tokens(input.clone(), output.clone()).unwrap();
+            let amount_out = amounts.get(i + 1).unwrap();
+            let (amount0_out, amount1_out) = if input == token0 { (0, amount_out) } else { (amount_out, 0) };
+            let to = if i < path.len() - 2 {
+                Self::stable_pair(env, &output, &path.get(i + 2).unwrap())
+            } else {
+                _to.clone()
+            };
+            let pair = Self::stable_pair(env, &input, &output);
+            StablePairClient::new(env, &pair).swap(&amount0_out, &amount1_out, &to);
+        }
+    }
+
     fn _swap(env: &Env, amounts: &Vec<i128>, path: &Vec<Address>, _to: &Address) {
         for i in 0..path.len() - 1 {
             let (input, output) = (path.get(i).unwrap().clone(), path.get(i + 1).unwrap().clone());
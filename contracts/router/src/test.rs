@@ -45,7 +45,7 @@ fn create_pair_contract<'a>(e: &Env, factory: &Address, token_a: &Address, token
     let salt_hash = e.crypto().sha256(&salt);
     let pair_address = e.deployer().with_address(factory.clone(), salt_hash).deployed_address();
     let pair = PairClient::new(e, &pair_address);
-    pair.initialize(token_a, token_b, factory);
+    pair.initialize(token_a, token_b, factory, &30);
     pair
 }
 
@@ -62,6 +62,13 @@ fn create_factory_contract<'a>(e: & Env, setter: & Address,pair_wasm_hash: & Byt
     factory
 }
 
+fn stable_pair_wasm(e: &Env) -> BytesN<32> {
+    soroban_sdk::contractimport!(
+        file = "D:/RaumFiV2/RaumFiV2/target/wasm32-unknown-unknown/release/stable_pair.wasm"
+    );
+    e.deployer().upload_contract_wasm(WASM)
+}
+
 fn create_test_contract(env: &Env) -> RaumFiRouterClient<'static> {
     let contract_id = env.register_contract(None, RaumFiRouter{});
     let client = RaumFiRouterClient::new(env, &contract_id);
@@ -127,6 +134,7 @@ fn test_add_liquidity() {
 fn test_remove_liquidity() {
     let env = Env::default();
     env.mock_all_auths();
+    env.budget().reset_unlimited();
     let admin = Address::generate(&env);
     let pair_wasm_hash = pair_token_wasm(&env);
     let factory = create_factory_contract(&env, &admin, &pair_wasm_hash);
@@ -181,6 +189,109 @@ fn test_remove_liquidity() {
     assert!(amount_b >= amount_b_min);
 }
 
+#[test]
+fn test_stable_pair_liquidity_and_swaps() {
+    let env = Env::default();
+    env.mock_all_auths();
+    env.budget().reset_unlimited();
+    let admin = Address::generate(&env);
+    let factory = create_factory_contract(&env, &admin, &pair_token_wasm(&env));
+    factory.set_stable_pair_wasm_hash(&stable_pair_wasm(&env));
+
+    let (_, token_a_client) = create_token_contract(&env, &admin);
+    let (_, token_b_client) = create_token_contract(&env, &admin);
+    let (_, token_c_client) = create_token_contract(&env, &admin);
+    token_a_client.mint(&admin, &1000000000000000);
+    token_b_client.mint(&admin, &1000000000000000);
+    let (token_a, token_b) = (token_a_client.address.clone(), token_b_client.address.clone());
+    factory.create_stable_pair(&token_a, &token_b, &100, &5);
+
+    let router = create_test_contract(&env);
+    router.initialize(&factory.address, &token_c_client.address);
+
+    let liquidity = router.add_liquidity_stable(&token_a, &token_b, &1_000_000_000, &1_000_000_000, &1, &admin, &1000000);
+    assert!(liquidity > 0);
+
+    // A balanced pool trades close to 1:1
+    let path = vec![&env, token_a.clone(), token_b.clone()];
+    let amounts = router.swap_exact_in_stable(&1_000_000, &990_000, &path, &admin, &1000000);
+    assert_eq!(amounts.get(0).unwrap(), 1_000_000);
+    assert!(amounts.get(1).unwrap() >= 990_000);
+
+    let path = vec![&env, token_b.clone(), token_a.clone()];
+    let amounts = router.swap_exact_out_stable(&1_000_000, &1_010_000, &path, &admin, &1000000);
+    assert_eq!(amounts.get(1).unwrap(), 1_000_000);
+    assert!(amounts.get(0).unwrap() <= 1_010_000);
+
+    let (amount_a, amount_b) = router.remove_liquidity_stable(&token_a, &token_b, &liquidity, &1, &1, &admin, &1000000);
+    assert!(amount_a > 0 && amount_b > 0);
+}
+
+#[test]
+#[should_panic(expected = "RaumFiRouter: STABLE_PAIR_DOES_NOT_EXIST")]
+fn test_stable_pair_missing() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let admin = Address::generate(&env);
+    let factory = create_factory_contract(&env, &admin, &pair_token_wasm(&env));
+    let (_, token_a_client) = create_token_contract(&env, &admin);
+    let (_, token_b_client) = create_token_contract(&env, &admin);
+    let router = create_test_contract(&env);
+    router.initialize(&factory.address, &token_a_client.address);
+
+    let path = vec![&env, token_a_client.address.clone(), token_b_client.address.clone()];
+    router.get_amounts_out_stable(&1_000, &path);
+}
+
+/// Seeds a pair of two new tokens charging `fee` with 1_000_000 of each, returning the
+/// router and the tokens in pair order
+fn setup_fee_tier_pair(env: &Env, fee: u32) -> (RaumFiRouterClient<'static>, TokenClient<'static>, TokenClient<'static>, Address) {
+    env.mock_all_auths();
+    env.budget().reset_unlimited();
+    let admin = Address::generate(env);
+    let factory = create_factory_contract(env, &admin, &pair_token_wasm(env));
+    let (token_a, token_a_client) = create_token_contract(env, &admin);
+    let (token_b, token_b_client) = create_token_contract(env, &admin);
+    let (_, token_c_client) = create_token_contract(env, &admin);
+    token_a_client.mint(&admin, &1_000_000_000);
+    token_b_client.mint(&admin, &1_000_000_000);
+    let (token_0, token_1) = if token_a.address < token_b.address { (token_a, token_b) } else { (token_b, token_a) };
+    factory.create_pair_with_fee(&token_0.address, &token_1.address, &fee);
+
+    let router = create_test_contract(env);
+    router.initialize(&factory.address, &token_c_client.address);
+    router.add_liquidity(&token_0.address, &token_1.address, &1_000_000, &1_000_000, &1, &1, &admin, &1000000);
+    (router, token_0, token_1, admin)
+}
+
+#[test]
+fn test_swap_quoted_amounts_every_fee_tier() {
+    // The factory's FEE_TIERS
+    for fee in [1, 5, 30, 100] {
+        let env = Env::default();
+        let (router, token_0, token_1, trader) = setup_fee_tier_pair(&env, fee);
+        let path = vec![&env, token_0.address.clone(), token_1.address.clone()];
+
+        // The pair pays out the full quote for an exact input
+        let quote = router.get_amounts_out(&3_999, &path).get(1).unwrap();
+        let balance_1 = token_1.balance(&trader);
+        let amounts = router.swap_exact_tokens_for_tokens(&3_999, &quote, &path, &trader, &1000000);
+        assert_eq!(amounts.get(1).unwrap(), quote);
+        assert_eq!(token_1.balance(&trader), balance_1 + quote);
+        if fee == 5 {
+            assert_eq!(quote, 3_981);
+        }
+
+        // and takes no more than the quote for an exact output
+        let path = vec![&env, token_1.address.clone(), token_0.address.clone()];
+        let quote = router.get_amounts_in(&3_999, &path).get(0).unwrap();
+        let balance_1 = token_1.balance(&trader);
+        let amounts = router.swap_tokens_for_exact_tokens(&3_999, &quote, &path, &trader, &1000000);
+        assert_eq!(amounts.get(0).unwrap(), quote);
+        assert_eq!(token_1.balance(&trader), balance_1 - quote);
+    }
+}
+
 // #[test]
 // fn test_swap_exact_tokens_for_tokens() {
 //     let env = Env::default();
@@ -283,3 +394,111 @@ fn test_remove_liquidity() {
 //     assert_eq!(amounts.get(1).unwrap(), amount_out);
 // }
 
+
+/// Seeds a pair of two new tokens through the router and commits `admin` to swap
+/// 1_000_000 of the first for at least the quoted output, in ledger 100
+fn setup_commit_reveal(env: &Env) -> (RaumFiRouterClient<'static>, TokenClient<'static>, TokenClient<'static>, Address, BytesN<32>, BytesN<32>) {
+    env.mock_all_auths();
+    env.budget().reset_unlimited();
+    let admin = Address::generate(env);
+    let factory = create_factory_contract(env, &admin, &pair_token_wasm(env));
+    let (token_a, token_a_client) = create_token_contract(env, &admin);
+    let (token_b, token_b_client) = create_token_contract(env, &admin);
+    let (_, token_c_client) = create_token_contract(env, &admin);
+    token_a_client.mint(&admin, &1_000_000_000_000);
+    token_b_client.mint(&admin, &1_000_000_000_000);
+
+    let router = create_test_contract(env);
+    router.initialize(&factory.address, &token_c_client.address);
+    router.add_liquidity(&token_a.address, &token_b.address, &1_000_000_000, &1_000_000_000, &1, &1, &admin, &1000000);
+
+    env.ledger().set_sequence_number(100);
+    let path = vec![env, token_a.address.clone(), token_b.address.clone()];
+    let amount_out_min = router.get_amounts_out(&1_000_000, &path).get(1).unwrap();
+    let salt = BytesN::from_array(env, &[7; 32]);
+    let commitment = router.swap_commitment(&1_000_000, &amount_out_min, &path, &admin, &salt);
+    router.commit_swap(&admin, &token_a.address, &1_000_000, &commitment);
+    (router, token_a, token_b, admin, salt, commitment)
+}
+
+#[test]
+fn test_commit_reveal_swap() {
+    let env = Env::default();
+    let (router, token_a, token_b, trader, salt, commitment) = setup_commit_reveal(&env);
+    let path = vec![&env, token_a.address.clone(), token_b.address.clone()];
+    let amount_out_min = router.get_amounts_out(&1_000_000, &path).get(1).unwrap();
+
+    // The input waits in escrow until the swap is revealed
+    assert_eq!(token_a.balance(&router.address), 1_000_000);
+    assert_eq!(router.get_swap_commitment(&trader, &commitment).unwrap().ledger, 100);
+
+    env.ledger().set_sequence_number(101);
+    let balance_b = token_b.balance(&trader);
+    let amounts = router.reveal_swap(&trader, &1_000_000, &amount_out_min, &path, &trader, &salt, &1000000);
+    assert_eq!(amounts.get(1).unwrap(), amount_out_min);
+    assert_eq!(token_b.balance(&trader), balance_b + amount_out_min);
+    assert_eq!(token_a.balance(&router.address), 0);
+    assert_eq!(router.get_swap_commitment(&trader, &commitment), None);
+}
+
+#[test]
+#[should_panic(expected = "RaumFiRouter: REVEAL_TOO_EARLY")]
+fn test_reveal_swap_same_ledger() {
+    let env = Env::default();
+    let (router, token_a, token_b, trader, salt, _commitment) = setup_commit_reveal(&env);
+    let path = vec![&env, token_a.address.clone(), token_b.address.clone()];
+    let amount_out_min = router.get_amounts_out(&1_000_000, &path).get(1).unwrap();
+    router.reveal_swap(&trader, &1_000_000, &amount_out_min, &path, &trader, &salt, &1000000);
+}
+
+#[test]
+#[should_panic(expected = "RaumFiRouter: COMMITMENT_EXPIRED")]
+fn test_reveal_swap_expired() {
+    let env = Env::default();
+    let (router, token_a, token_b, trader, salt, _commitment) = setup_commit_reveal(&env);
+    let path = vec![&env, token_a.address.clone(), token_b.address.clone()];
+    let amount_out_min = router.get_amounts_out(&1_000_000, &path).get(1).unwrap();
+    env.ledger().set_sequence_number(821);
+    router.reveal_swap(&trader, &1_000_000, &amount_out_min, &path, &trader, &salt, &1000000);
+}
+
+#[test]
+#[should_panic(expected = "RaumFiRouter: COMMITMENT_NOT_FOUND")]
+fn test_reveal_swap_other_terms() {
+    let env = Env::default();
+    let (router, token_a, token_b, trader, salt, _commitment) = setup_commit_reveal(&env);
+    let path = vec![&env, token_a.address.clone(), token_b.address.clone()];
+    env.ledger().set_sequence_number(101);
+    // A slippage bound other than the committed one does not match the hash
+    router.reveal_swap(&trader, &1_000_000, &1, &path, &trader, &salt, &1000000);
+}
+
+#[test]
+fn test_cancel_swap_commitment() {
+    let env = Env::default();
+    let (router, token_a, _token_b, trader, _salt, commitment) = setup_commit_reveal(&env);
+    let balance_a = token_a.balance(&trader);
+
+    assert_eq!(router.cancel_swap_commitment(&trader, &commitment), 1_000_000);
+    assert_eq!(token_a.balance(&trader), balance_a + 1_000_000);
+    assert_eq!(token_a.balance(&router.address), 0);
+    assert_eq!(router.get_swap_commitment(&trader, &commitment), None);
+}
+
+#[test]
+fn test_reveal_swap_low_fee_tier() {
+    let env = Env::default();
+    let (router, token_0, token_1, trader) = setup_fee_tier_pair(&env, 5);
+    env.ledger().set_sequence_number(100);
+    let path = vec![&env, token_0.address.clone(), token_1.address.clone()];
+    let amount_out_min = router.get_amounts_out(&3_999, &path).get(1).unwrap();
+    let salt = BytesN::from_array(&env, &[7; 32]);
+    let commitment = router.swap_commitment(&3_999, &amount_out_min, &path, &trader, &salt);
+    router.commit_swap(&trader, &token_0.address, &3_999, &commitment);
+
+    env.ledger().set_sequence_number(101);
+    let balance_1 = token_1.balance(&trader);
+    let amounts = router.reveal_swap(&trader, &3_999, &amount_out_min, &path, &trader, &salt, &1000000);
+    assert_eq!(amounts.get(1).unwrap(), 3_981);
+    assert_eq!(token_1.balance(&trader), balance_1 + 3_981);
+}
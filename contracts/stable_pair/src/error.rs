@@ -0,0 +1,32 @@
+use soroban_sdk::contracterror;
+
+#[contracterror]
+#[derive(Copy, Clone, Debug, Eq, PartialEq, PartialOrd, Ord)]
+#[repr(u32)]
+pub enum StablePairError {
+    Locked = 1,
+    AlreadyInitialized = 2,
+    Forbidden = 3,
+    InsufficientLiquidityMinted = 4,
+    InsufficientOutputAmount = 6,
+    InsufficientLiquidity = 7,
+    InvalidTo = 8,
+    InsufficientInputAmount = 9,
+    /// The swap would decrease the StableSwap invariant D
+    D = 10,
+    Overflow = 11,
+    InvalidAmount = 14,
+    InsufficientLiquidityBurned = 15,
+    InvalidFee = 16,
+    NotInitialized = 17,
+    InvalidAmp = 18,
+    /// A ramp of the amplification coefficient was started too recently
+    RampTooSoon = 19,
+    /// The Newton iteration for D or y did not converge
+    NotConverged = 20,
+    InvalidToken = 21,
+    InsufficientBalance = 22,
+    InsufficientAllowance = 23,
+    NegativeNotAllowed = 24,
+    PastTimeNotAllowed = 25,
+}
@@ -0,0 +1,12 @@
+#![no_std]
+
+mod error;
+mod lp_token;
+pub mod math;
+pub mod stable_pair;
+
+pub use error::StablePairError;
+pub use stable_pair::{AmpState, RaumFiStablePairClient};
+
+#[cfg(test)]
+mod test;
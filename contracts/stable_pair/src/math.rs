@@ -0,0 +1,88 @@
+//! StableSwap invariant for two coins:
+//!
+//! `A * n^n * (x + y) + D = A * D * n^n + D^(n + 1) / (n^n * x * y)` with `n = 2`
+//!
+//! Balances passed in are normalized to a common precision by the caller.
+
+use soroban_sdk::{Env, U256};
+
+use crate::error::StablePairError;
+
+const N_COINS: u32 = 2;
+/// Newton iterations before giving up on convergence
+const MAX_ITERATIONS: u32 = 255;
+
+fn u256(env: &Env, value: u128) -> U256 {
+    U256::from_u128(env, value)
+}
+
+fn converged(a: &U256, b: &U256, env: &Env) -> bool {
+    let one = U256::from_u32(env, 1);
+    if a > b {
+        a.sub(b) <= one
+    } else {
+        b.sub(a) <= one
+    }
+}
+
+/// Computes the invariant D of the balances `x` and `y` for the amplification `amp`
+pub fn compute_d(env: &Env, x: u128, y: u128, amp: u32) -> Result<u128, StablePairError> {
+    if x == 0 && y == 0 {
+        return Ok(0);
+    }
+    if x == 0 || y == 0 {
+        return Err(StablePairError::InsufficientLiquidity);
+    }
+
+    let n = U256::from_u32(env, N_COINS);
+    let x = u256(env, x);
+    let y = u256(env, y);
+    let sum = x.add(&y);
+    let ann = U256::from_u32(env, amp * N_COINS * N_COINS);
+    let one = U256::from_u32(env, 1);
+
+    let mut d = sum.clone();
+    for _ in 0..MAX_ITERATIONS {
+        // d_p = D^(n + 1) / (n^n * x * y)
+        let d_p = d.mul(&d).div(&x.mul(&n)).mul(&d).div(&y.mul(&n));
+        let d_prev = d.clone();
+        let numerator = ann.mul(&sum).add(&d_p.mul(&n)).mul(&d);
+        let denominator = ann.sub(&one).mul(&d).add(&n.add(&one).mul(&d_p));
+        d = numerator.div(&denominator);
+        if converged(&d, &d_prev, env) {
+            return d.to_u128().ok_or(StablePairError::Overflow);
+        }
+    }
+    Err(StablePairError::NotConverged)
+}
+
+/// Computes the balance of one coin that keeps the invariant at `d` when the other
+/// coin's balance is `x`
+pub fn compute_y(env: &Env, x: u128, d: u128, amp: u32) -> Result<u128, StablePairError> {
+    if x == 0 {
+        return Err(StablePairError::InsufficientLiquidity);
+    }
+
+    let n = U256::from_u32(env, N_COINS);
+    let x = u256(env, x);
+    let d = u256(env, d);
+    let ann = U256::from_u32(env, amp * N_COINS * N_COINS);
+
+    // c = D^(n + 1) / (n^n * x * Ann), b = x + D / Ann
+    let c = d.mul(&d).div(&x.mul(&n)).mul(&d).div(&ann.mul(&n));
+    let b = x.add(&d.div(&ann));
+
+    let mut y = d.clone();
+    for _ in 0..MAX_ITERATIONS {
+        let y_prev = y.clone();
+        let denominator = y.mul(&n).add(&b);
+        if denominator <= d {
+            return Err(StablePairError::NotConverged);
+        }
+        y = y.mul(&y).add(&c).div(&denominator.sub(&d));
+        if converged(&y, &y_prev, env) {
+            return y.to_u128().ok_or(StablePairError::Overflow);
+        }
+    }
+    Err(StablePairError::NotConverged)
+}
@@ -0,0 +1,509 @@
+use soroban_sdk::token::Interface as _;
+use soroban_sdk::{
+    contract, contractimpl, contractmeta, contracttype, token, Address, Env, String, Symbol, U256
+};
+
+use crate::error::StablePairError;
+use crate::lp_token::{lp_metadata, LpToken};
+use crate::math::{compute_d, compute_y};
+
+const MINIMUM_LIQUIDITY: i128 = 1000;
+/// Swap fees are expressed in basis points of the input amount
+const FEE_DENOMINATOR: i128 = 10_000;
+/// Largest amplification coefficient a pool may use
+pub const MAX_A: u32 = 1_000_000;
+/// A ramp may change the amplification coefficient by at most this factor
+pub const MAX_A_CHANGE: u32 = 10;
+/// Minimum duration of a ramp, and minimum time between two ramps, in seconds
+pub const MIN_RAMP_TIME: u64 = 86_400;
+
+contractmeta!(
+    key = "Description",
+    val = "RaumFi StableSwap pair for pegged assets"
+);
+
+#[contracttype]
+#[derive(Clone)]
+pub enum DataKey {
+    Factory,
+    Token0,
+    Token1,
+    Reserve0,
+    Reserve1,
+    /// Multipliers bringing both token amounts to the precision of the more precise token
+    Rate0,
+    Rate1,
+    Fee,
+    Amp,
+    Unlocked,
+}
+
+/// Amplification coefficient ramp. A moves linearly from `initial_a` at
+/// `initial_time` to `future_a` at `future_time`.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct AmpState {
+    pub initial_a: u32,
+    pub future_a: u32,
+    pub initial_time: u64,
+    pub future_time: u64,
+}
+
+pub trait RaumFiStablePairTrait {
+    fn initialize(env: Env, token0: Address, token1: Address, factory: Address, fee: u32, amp: u32) -> Result<(), StablePairError>;
+    fn get_reserves(env: Env) -> (i128, i128);
+    fn get_fee(env: Env) -> u32;
+    fn get_amp(env: Env) -> u32;
+    fn get_amp_state(env: Env) -> AmpState;
+    fn ramp_a(env: Env, future_a: u32, future_time: u64) -> Result<(), StablePairError>;
+    fn stop_ramp_a(env: Env) -> Result<(), StablePairError>;
+    fn get_amount_out(env: Env, token_in: Address, amount_in: i128) -> Result<i128, StablePairError>;
+    fn get_amount_in(env: Env, token_out: Address, amount_out: i128) -> Result<i128, StablePairError>;
+    fn mint(env: Env, to: Address) -> Result<i128, StablePairError>;
+    fn swap(env: Env, amount0_out: i128, amount1_out: i128, to: Address) -> Result<(), StablePairError>;
+    fn withdraw(env: Env, to: Address) -> Result<(i128, i128), StablePairError>;
+}
+
+#[contract]
+pub struct RaumFiStablePair;
+
+#[contractimpl]
+impl RaumFiStablePairTrait for RaumFiStablePair {
+    fn initialize(env: Env, token0: Address, token1: Address, factory: Address, fee: u32, amp: u32) -> Result<(), StablePairError> {
+        if env.storage().instance().has(&DataKey::Factory) {
+            return Err(StablePairError::AlreadyInitialized);
+        }
+        if fee as i128 >= FEE_DENOMINATOR {
+            return Err(StablePairError::InvalidFee);
+        }
+        if amp == 0 || amp > MAX_A {
+            return Err(StablePairError::InvalidAmp);
+        }
+
+        let decimals0 = token::Client::new(&env, &token0).decimals();
+        let decimals1 = token::Client::new(&env, &token1).decimals();
+        let precision = decimals0.max(decimals1);
+
+        let storage = env.storage().instance();
+        storage.set(&DataKey::Factory, &factory);
+        storage.set(&DataKey::Token0, &token0);
+        storage.set(&DataKey::Token1, &token1);
+        storage.set(&DataKey::Rate0, &10u128.pow(precision - decimals0));
+        storage.set(&DataKey::Rate1, &10u128.pow(precision - decimals1));
+        storage.set(&DataKey::Fee, &fee);
+        storage.set(&DataKey::Amp, &AmpState {
+            initial_a: amp,
+            future_a: amp,
+            initial_time: env.ledger().timestamp(),
+            future_time: env.ledger().timestamp(),
+        });
+        LpToken::write_metadata(&env, lp_metadata(&env));
+        Ok(())
+    }
+
+    fn get_reserves(env: Env) -> (i128, i128) {
+        let reserve0 = env.storage().instance().get(&DataKey::Reserve0).unwrap_or(0);
+        let reserve1 = env.storage().instance().get(&DataKey::Reserve1).unwrap_or(0);
+        (reserve0, reserve1)
+    }
+
+    /// Returns the swap fee of the pool in basis points
+    fn get_fee(env: Env) -> u32 {
+        env.storage().instance().get(&DataKey::Fee).unwrap_or(0)
+    }
+
+    /// Returns the amplification coefficient in effect at the current ledger timestamp
+    fn get_amp(env: Env) -> u32 {
+        let amp = Self::get_amp_state(env.clone());
+        let now = env.ledger().timestamp();
+        if now >= amp.future_time {
+            return amp.future_a;
+        }
+        let elapsed = (now - amp.initial_time) as i128;
+        let duration = (amp.future_time - amp.initial_time) as i128;
+        let delta = amp.future_a as i128 - amp.initial_a as i128;
+        (amp.initial_a as i128 + delta * elapsed / duration) as u32
+    }
+
+    fn get_amp_state(env: Env) -> AmpState {
+        env.storage().instance().get(&DataKey::Amp).unwrap()
+    }
+
+    /// Starts moving the amplification coefficient towards `future_a`, reaching it at
+    /// `future_time`. Only the factory may ramp A.
+    fn ramp_a(env: Env, future_a: u32, future_time: u64) -> Result<(), StablePairError> {
+        let factory: Address = env.storage().instance().get(&DataKey::Factory).ok_or(StablePairError::NotInitialized)?;
+        factory.require_auth();
+
+        let now = env.ledger().timestamp();
+        let amp = Self::get_amp_state(env.clone());
+        if now < amp.initial_time + MIN_RAMP_TIME {
+            return Err(StablePairError::RampTooSoon);
+        }
+        if future_time < now + MIN_RAMP_TIME {
+            return Err(StablePairError::RampTooSoon);
+        }
+
+        let current_a = Self::get_amp(env.clone());
+        if future_a == 0
+            || future_a > MAX_A
+            || future_a > current_a * MAX_A_CHANGE
+            || future_a * MAX_A_CHANGE < current_a
+        {
+            return Err(StablePairError::InvalidAmp);
+        }
+
+        env.storage().instance().set(&DataKey::Amp, &AmpState {
+            initial_a: current_a,
+            future_a,
+            initial_time: now,
+            future_time,
+        });
+        env.events().publish(
+            (Symbol::new(&env, "RaumFiStablePair"), Symbol::new(&env, "RampA")),
+            (current_a, future_a, now, future_time)
+        );
+        Ok(())
+    }
+
+    /// Freezes the amplification coefficient at its current value
+    fn stop_ramp_a(env: Env) -> Result<(), StablePairError> {
+        let factory: Address = env.storage().instance().get(&DataKey::Factory).ok_or(StablePairError::NotInitialized)?;
+        factory.require_auth();
+
+        let now = env.ledger().timestamp();
+        let current_a = Self::get_amp(env.clone());
+        env.storage().instance().set(&DataKey::Amp, &AmpState {
+            initial_a: current_a,
+            future_a: current_a,
+            initial_time: now,
+            future_time: now,
+        });
+        env.events().publish(
+            (Symbol::new(&env, "RaumFiStablePair"), Symbol::new(&env, "StopRampA")),
+            (current_a, now)
+        );
+        Ok(())
+    }
+
+    /// Quotes the output of swapping `amount_in` of `token_in` against the current reserves
+    fn get_amount_out(env: Env, token_in: Address, amount_in: i128) -> Result<i128, StablePairError> {
+        if amount_in <= 0 {
+            return Err(StablePairError::InsufficientInputAmount);
+        }
+        let (reserve_in, reserve_out, rate_in, rate_out) = oriented_reserves(&env, &token_in)?;
+        let amp = Self::get_amp(env.clone());
+        let d = compute_d(&env, reserve_in, reserve_out, amp)?;
+
+        let amount_in_after_fee = amount_in - fee_amount(&env, amount_in);
+        let x = reserve_in + amount_in_after_fee as u128 * rate_in;
+        let y = compute_y(&env, x, d, amp)?;
+        if y + 1 >= reserve_out {
+            return Err(StablePairError::InsufficientOutputAmount);
+        }
+        let amount_out = ((reserve_out - y - 1) / rate_out) as i128;
+        if amount_out <= 0 {
+            return Err(StablePairError::InsufficientOutputAmount);
+        }
+        Ok(amount_out)
+    }
+
+    /// Quotes how much of the other token must be sent in to receive `amount_out` of `token_out`
+    fn get_amount_in(env: Env, token_out: Address, amount_out: i128) -> Result<i128, StablePairError> {
+        if amount_out <= 0 {
+            return Err(StablePairError::InsufficientOutputAmount);
+        }
+        let (reserve_out, reserve_in, rate_out, rate_in) = oriented_reserves(&env, &token_out)?;
+        let amp = Self::get_amp(env.clone());
+        let d = compute_d(&env, reserve_in, reserve_out, amp)?;
+
+        let amount_out_scaled = amount_out as u128 * rate_out;
+        if amount_out_scaled >= reserve_out {
+            return Err(StablePairError::InsufficientLiquidity);
+        }
+        let x = compute_y(&env, reserve_out - amount_out_scaled, d, amp)?;
+        let amount_in_after_fee = ((x - reserve_in + 1) + rate_in - 1) / rate_in;
+        let fee = Self::get_fee(env.clone()) as u128;
+        let amount_in = (amount_in_after_fee * FEE_DENOMINATOR as u128) / (FEE_DENOMINATOR as u128 - fee) + 1;
+        i128::try_from(amount_in).map_err(|_| StablePairError::Overflow)
+    }
+
+    /// Mints LP tokens for the tokens sent to the pool since the last update. Deposits that
+    /// move the pool away from its current composition pay the swap fee on the imbalance.
+    fn mint(env: Env, to: Address) -> Result<i128, StablePairError> {
+        check_locked(&env)?;
+        let _guard = Guard::new(&env);
+
+        let (reserve0, reserve1) = Self::get_reserves(env.clone());
+        let balance0 = get_balance(&env, DataKey::Token0);
+        let balance1 = get_balance(&env, DataKey::Token1);
+        let amount0 = balance0 - reserve0;
+        let amount1 = balance1 - reserve1;
+        if amount0 < 0 || amount1 < 0 || (amount0 == 0 && amount1 == 0) {
+            return Err(StablePairError::InvalidAmount);
+        }
+
+        let amp = Self::get_amp(env.clone());
+        let (rate0, rate1) = rates(&env);
+        let total_supply = LpToken::total_supply(&env);
+        let d0 = if total_supply == 0 { 0 } else { compute_d(&env, reserve0 as u128 * rate0, reserve1 as u128 * rate1, amp)? };
+        let d1 = compute_d(&env, balance0 as u128 * rate0, balance1 as u128 * rate1, amp)?;
+        if d1 <= d0 {
+            return Err(StablePairError::InsufficientLiquidityMinted);
+        }
+
+        let liquidity = if total_supply == 0 {
+            LpToken::mint(&env, &env.current_contract_address(), MINIMUM_LIQUIDITY);
+            d1 as i128 - MINIMUM_LIQUIDITY
+        } else {
+            // Each coin pays fee / 2 on the distance from a proportional deposit
+            let fee = Self::get_fee(env.clone()) as u128;
+            let adjusted0 = charge_imbalance(&env, balance0 as u128 * rate0, reserve0 as u128 * rate0, d0, d1, fee);
+            let adjusted1 = charge_imbalance(&env, balance1 as u128 * rate1, reserve1 as u128 * rate1, d0, d1, fee);
+            let d2 = compute_d(&env, adjusted0, adjusted1, amp)?;
+            U256::from_u128(&env, total_supply as u128)
+                .mul(&U256::from_u128(&env, d2.saturating_sub(d0)))
+                .div(&U256::from_u128(&env, d0))
+                .to_u128()
+                .ok_or(StablePairError::Overflow)? as i128
+        };
+        if liquidity <= 0 {
+            return Err(StablePairError::InsufficientLiquidityMinted);
+        }
+        LpToken::mint(&env, &to, liquidity);
+
+        update(&env, balance0, balance1);
+        env.events().publish(
+            (Symbol::new(&env, "RaumFiStablePair"), Symbol::new(&env, "Mint")),
+            (to, amount0, amount1)
+        );
+        Ok(liquidity)
+    }
+
+    /// Sends the tokens already transferred in out at `amount0_out`/`amount1_out`, as long as
+    /// the invariant D of the fee-adjusted balances does not decrease
+    fn swap(env: Env, amount0_out: i128, amount1_out: i128, to: Address) -> Result<(), StablePairError> {
+        check_locked(&env)?;
+        let _guard = Guard::new(&env);
+
+        if amount0_out < 0 || amount1_out < 0 || (amount0_out == 0 && amount1_out == 0) {
+            return Err(StablePairError::InsufficientOutputAmount);
+        }
+        let (reserve0, reserve1) = Self::get_reserves(env.clone());
+        if amount0_out >= reserve0 || amount1_out >= reserve1 {
+            return Err(StablePairError::InsufficientLiquidity);
+        }
+
+        let token0: Address = env.storage().instance().get(&DataKey::Token0).unwrap();
+        let token1: Address = env.storage().instance().get(&DataKey::Token1).unwrap();
+        if to == token0 || to == token1 {
+            return Err(StablePairError::InvalidTo);
+        }
+        if amount0_out > 0 {
+            token::Client::new(&env, &token0).transfer(&env.current_contract_address(), &to, &amount0_out);
+        }
+        if amount1_out > 0 {
+            token::Client::new(&env, &token1).transfer(&env.current_contract_address(), &to, &amount1_out);
+        }
+
+        let balance0 = get_balance(&env, DataKey::Token0);
+        let balance1 = get_balance(&env, DataKey::Token1);
+        let amount0_in = if balance0 > reserve0 - amount0_out { balance0 - (reserve0 - amount0_out) } else { 0 };
+        let amount1_in = if balance1 > reserve1 - amount1_out { balance1 - (reserve1 - amount1_out) } else { 0 };
+        if amount0_in == 0 && amount1_in == 0 {
+            return Err(StablePairError::InsufficientInputAmount);
+        }
+
+        let amp = Self::get_amp(env.clone());
+        let (rate0, rate1) = rates(&env);
+        let adjusted0 = (balance0 - fee_amount(&env, amount0_in)) as u128 * rate0;
+        let adjusted1 = (balance1 - fee_amount(&env, amount1_in)) as u128 * rate1;
+        let d_before = compute_d(&env, reserve0 as u128 * rate0, reserve1 as u128 * rate1, amp)?;
+        let d_after = compute_d(&env, adjusted0, adjusted1, amp)?;
+        if d_after < d_before {
+            return Err(StablePairError::D);
+        }
+
+        update(&env, balance0, balance1);
+        env.events().publish(
+            (Symbol::new(&env, "RaumFiStablePair"), Symbol::new(&env, "Swap")),
+            (amount0_in, amount1_in, amount0_out, amount1_out, to)
+        );
+        Ok(())
+    }
+
+    /// Burns the LP tokens sent to the pool on top of the locked minimum liquidity and
+    /// sends the proportional share of both tokens to `to`
+    fn withdraw(env: Env, to: Address) -> Result<(i128, i128), StablePairError> {
+        check_locked(&env)?;
+        let _guard = Guard::new(&env);
+
+        let token0: Address = env.storage().instance().get(&DataKey::Token0).unwrap();
+        let token1: Address = env.storage().instance().get(&DataKey::Token1).unwrap();
+        let balance0 = get_balance(&env, DataKey::Token0);
+        let balance1 = get_balance(&env, DataKey::Token1);
+        let liquidity = LpToken::balance(&env, &env.current_contract_address())
+            .checked_sub(MINIMUM_LIQUIDITY)
+            .ok_or(StablePairError::InsufficientLiquidityBurned)?;
+
+        let total_supply = LpToken::total_supply(&env);
+        let amount0 = balance0.checked_mul(liquidity).ok_or(StablePairError::Overflow)? / total_supply;
+        let amount1 = balance1.checked_mul(liquidity).ok_or(StablePairError::Overflow)? / total_supply;
+        if amount0 <= 0 && amount1 <= 0 {
+            return Err(StablePairError::InsufficientLiquidityBurned);
+        }
+
+        LpToken::burn(&env, &env.current_contract_address(), liquidity);
+        if amount0 > 0 {
+            token::Client::new(&env, &token0).transfer(&env.current_contract_address(), &to, &amount0);
+        }
+        if amount1 > 0 {
+            token::Client::new(&env, &token1).transfer(&env.current_contract_address(), &to, &amount1);
+        }
+
+        update(&env, balance0 - amount0, balance1 - amount1);
+        env.events().publish(
+            (Symbol::new(&env, "RaumFiStablePair"), Symbol::new(&env, "Burn")),
+            (to, amount0, amount1)
+        );
+        Ok((amount0, amount1))
+    }
+}
+
+/// The pool is itself the SEP-41 token of its liquidity shares
+#[contractimpl]
+impl token::Interface for RaumFiStablePair {
+    fn allowance(e: Env, from: Address, spender: Address) -> i128 {
+        LpToken::allowance(&e, &from, &spender).amount
+    }
+
+    fn approve(e: Env, from: Address, spender: Address, amount: i128, expiration_ledger: u32) {
+        from.require_auth();
+        LpToken::approve(&e, &from, &spender, amount, expiration_ledger);
+    }
+
+    fn balance(e: Env, id: Address) -> i128 {
+        LpToken::balance(&e, &id)
+    }
+
+    fn transfer(e: Env, from: Address, to: Address, amount: i128) {
+        from.require_auth();
+        LpToken::check_nonnegative_amount(&e, amount);
+        LpToken::transfer(&e, &from, &to, amount);
+    }
+
+    fn transfer_from(e: Env, spender: Address, from: Address, to: Address, amount: i128) {
+        spender.require_auth();
+        LpToken::check_nonnegative_amount(&e, amount);
+        LpToken::spend_allowance(&e, &from, &spender, amount);
+        LpToken::transfer(&e, &from, &to, amount);
+    }
+
+    fn burn(e: Env, from: Address, amount: i128) {
+        from.require_auth();
+        LpToken::check_nonnegative_amount(&e, amount);
+        LpToken::burn(&e, &from, amount);
+    }
+
+    fn burn_from(e: Env, spender: Address, from: Address, amount: i128) {
+        spender.require_auth();
+        LpToken::check_nonnegative_amount(&e, amount);
+        LpToken::spend_allowance(&e, &from, &spender, amount);
+        LpToken::burn(&e, &from, amount);
+    }
+
+    fn decimals(e: Env) -> u32 {
+        LpToken::read_metadata(&e).decimal
+    }
+
+    fn name(e: Env) -> String {
+        LpToken::read_metadata(&e).name
+    }
+
+    fn symbol(e: Env) -> String {
+        LpToken::read_metadata(&e).symbol
+    }
+}
+
+fn rates(env: &Env) -> (u128, u128) {
+    let rate0 = env.storage().instance().get(&DataKey::Rate0).unwrap_or(1);
+    let rate1 = env.storage().instance().get(&DataKey::Rate1).unwrap_or(1);
+    (rate0, rate1)
+}
+
+/// Returns the normalized reserves and rates of `token` and of the other token, in that order
+fn oriented_reserves(env: &Env, token: &Address) -> Result<(u128, u128, u128, u128), StablePairError> {
+    let token0: Address = env.storage().instance().get(&DataKey::Token0).ok_or(StablePairError::NotInitialized)?;
+    let token1: Address = env.storage().instance().get(&DataKey::Token1).ok_or(StablePairError::NotInitialized)?;
+    let (reserve0, reserve1) = RaumFiStablePair::get_reserves(env.clone());
+    if reserve0 <= 0 || reserve1 <= 0 {
+        return Err(StablePairError::InsufficientLiquidity);
+    }
+    let (rate0, rate1) = rates(env);
+    let xp0 = reserve0 as u128 * rate0;
+    let xp1 = reserve1 as u128 * rate1;
+    if *token == token0 {
+        Ok((xp0, xp1, rate0, rate1))
+    } else if *token == token1 {
+        Ok((xp1, xp0, rate1, rate0))
+    } else {
+        Err(StablePairError::InvalidToken)
+    }
+}
+
+/// Swap fee charged on `amount_in`, rounded up in favor of the pool
+fn fee_amount(env: &Env, amount_in: i128) -> i128 {
+    let fee = RaumFiStablePair::get_fee(env.clone()) as i128;
+    (amount_in * fee + FEE_DENOMINATOR - 1) / FEE_DENOMINATOR
+}
+
+/// Deducts fee / 2 on the distance between `new_balance` and the balance a deposit
+/// proportional to the pool would have left
+fn charge_imbalance(env: &Env, new_balance: u128, old_balance: u128, d0: u128, d1: u128, fee: u128) -> u128 {
+    let ideal = U256::from_u128(env, d1)
+        .mul(&U256::from_u128(env, old_balance))
+        .div(&U256::from_u128(env, d0))
+        .to_u128()
+        .unwrap_or(u128::MAX);
+    let difference = if ideal > new_balance { ideal - new_balance } else { new_balance - ideal };
+    new_balance - difference * fee / 2 / FEE_DENOMINATOR as u128
+}
+
+fn get_balance(env: &Env, token_key: DataKey) -> i128 {
+    let token: Address = env.storage().instance().get(&token_key).unwrap();
+    token::Client::new(env, &token).balance(&env.current_contract_address())
+}
+
+fn update(env: &Env, balance0: i128, balance1: i128) {
+    env.storage().instance().set(&DataKey::Reserve0, &balance0);
+    env.storage().instance().set(&DataKey::Reserve1, &balance1);
+    env.events().publish(
+        (Symbol::new(env, "RaumFiStablePair"), Symbol::new(env, "Sync")),
+        (balance0, balance1)
+    );
+}
+
+fn check_locked(env: &Env) -> Result<(), StablePairError> {
+    let unlocked: bool = env.storage().instance().get(&DataKey::Unlocked).unwrap_or(true);
+    if !unlocked {
+        return Err(StablePairError::Locked);
+    }
+    Ok(())
+}
+
+struct Guard<'a> {
+    env: &'a Env,
+}
+
+impl<'a> Guard<'a> {
+    fn new(env: &'a Env) -> Self {
+        env.storage().instance().set(&DataKey::Unlocked, &false);
+        Guard { env }
+    }
+}
+
+impl<'a> Drop for Guard<'a> {
+    fn drop(&mut self) {
+        self.env.storage().instance().set(&DataKey::Unlocked, &true);
+    }
+}
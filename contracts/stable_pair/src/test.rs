@@ -0,0 +1,203 @@
+#![cfg(test)]
+extern crate std;
+
+use soroban_sdk::{
+    testutils::{Address as _, Ledger},
+    token, Address, Env,
+};
+
+use crate::math::{compute_d, compute_y};
+use crate::stable_pair::{RaumFiStablePair, RaumFiStablePairClient};
+use token::Client as TokenClient;
+use token::StellarAssetClient as TokenAdminClient;
+
+fn create_token_contract<'a>(e: &Env, admin: &Address) -> (TokenClient<'a>, TokenAdminClient<'a>) {
+    let sac = e.register_stellar_asset_contract(admin.clone());
+    (
+        token::Client::new(e, &sac),
+        token::StellarAssetClient::new(e, &sac),
+    )
+}
+
+struct StablePairTest<'a> {
+    env: Env,
+    client: RaumFiStablePairClient<'a>,
+    token0: TokenClient<'a>,
+    token1: TokenClient<'a>,
+    user: Address,
+}
+
+fn setup_test(amp: u32, fee: u32) -> StablePairTest<'static> {
+    let env = Env::default();
+    env.mock_all_auths();
+    env.ledger().set_timestamp(1_000);
+
+    let client = RaumFiStablePairClient::new(&env, &env.register_contract(None, RaumFiStablePair {}));
+    let user = Address::generate(&env);
+    let (token_a, token_a_admin) = create_token_contract(&env, &user);
+    let (token_b, token_b_admin) = create_token_contract(&env, &user);
+    token_a_admin.mint(&user, &1_000_000_000);
+    token_b_admin.mint(&user, &1_000_000_000);
+    let (token0, token1) = if token_a.address < token_b.address { (token_a, token_b) } else { (token_b, token_a) };
+
+    client.initialize(&token0.address, &token1.address, &Address::generate(&env), &fee, &amp);
+    StablePairTest { env, client, token0, token1, user }
+}
+
+fn add_liquidity(test: &StablePairTest, amount0: i128, amount1: i128) -> i128 {
+    test.token0.transfer(&test.user, &test.client.address, &amount0);
+    test.token1.transfer(&test.user, &test.client.address, &amount1);
+    test.client.mint(&test.user)
+}
+
+#[test]
+fn test_compute_d() {
+    let env = Env::default();
+    // A balanced pool's invariant is the sum of its balances
+    assert_eq!(compute_d(&env, 1_000_000, 1_000_000, 100).unwrap(), 2_000_000);
+    assert_eq!(compute_d(&env, 0, 0, 100).unwrap(), 0);
+
+    // Imbalance lowers D below the sum, less so for a higher amplification
+    let d_low = compute_d(&env, 1_500_000, 500_000, 10).unwrap();
+    let d_high = compute_d(&env, 1_500_000, 500_000, 1_000).unwrap();
+    assert!(d_low < d_high && d_high < 2_000_000);
+
+    // compute_y inverts compute_d
+    let y = compute_y(&env, 1_500_000, d_low, 10).unwrap();
+    assert!(y.abs_diff(500_000) <= 1);
+}
+
+#[test]
+fn test_initialize() {
+    let test = setup_test(100, 4);
+    assert_eq!(test.client.get_amp(), 100);
+    assert_eq!(test.client.get_fee(), 4);
+    assert_eq!(test.client.get_reserves(), (0, 0));
+    assert_eq!(test.client.symbol(), soroban_sdk::String::from_str(&test.env, "RFSLP"));
+}
+
+#[test]
+#[should_panic(expected = "Error(Contract, #2)")]
+fn test_initialize_twice() {
+    let test = setup_test(100, 4);
+    test.client.initialize(&test.token0.address, &test.token1.address, &test.user, &4, &100);
+}
+
+#[test]
+fn test_mint_and_withdraw() {
+    let test = setup_test(100, 4);
+    let liquidity = add_liquidity(&test, 1_000_000, 1_000_000);
+    assert_eq!(liquidity, 2_000_000 - 1000);
+    assert_eq!(test.client.get_reserves(), (1_000_000, 1_000_000));
+
+    // A proportional deposit pays no imbalance fee
+    let liquidity2 = add_liquidity(&test, 500_000, 500_000);
+    assert_eq!(liquidity2, 1_000_000);
+
+    test.client.transfer(&test.user, &test.client.address, &liquidity2);
+    let (amount0, amount1) = test.client.withdraw(&test.user);
+    assert_eq!((amount0, amount1), (500_000, 500_000));
+    assert_eq!(test.client.get_reserves(), (1_000_000, 1_000_000));
+    assert_eq!(test.client.balance(&test.user), liquidity);
+}
+
+#[test]
+fn test_imbalanced_mint_pays_fee() {
+    let test = setup_test(100, 30);
+    add_liquidity(&test, 10_000_000, 10_000_000);
+
+    // A one-sided deposit followed by a proportional exit cannot beat a swap
+    let liquidity = add_liquidity(&test, 1_000_000, 0);
+    test.client.transfer(&test.user, &test.client.address, &liquidity);
+    let (amount0, amount1) = test.client.withdraw(&test.user);
+    assert!(amount1 > 0);
+    assert!(amount0 + amount1 < 1_000_000);
+}
+
+#[test]
+fn test_swap_low_slippage() {
+    let test = setup_test(100, 4);
+    add_liquidity(&test, 10_000_000, 10_000_000);
+
+    let amount_out = test.client.get_amount_out(&test.token0.address, &1_000_000);
+    // x * y = k would pay out 909_090; the stable curve stays close to 1:1
+    assert!(amount_out > 995_000 && amount_out < 1_000_000);
+
+    let balance_before = test.token1.balance(&test.user);
+    test.token0.transfer(&test.user, &test.client.address, &1_000_000);
+    test.client.swap(&0, &amount_out, &test.user);
+    assert_eq!(test.token1.balance(&test.user) - balance_before, amount_out);
+    assert_eq!(test.client.get_reserves(), (11_000_000, 10_000_000 - amount_out));
+}
+
+#[test]
+fn test_swap_exact_output() {
+    let test = setup_test(200, 5);
+    add_liquidity(&test, 50_000_000, 30_000_000);
+
+    for amount_out in [1_000_i128, 250_000, 4_000_000] {
+        let amount_in = test.client.get_amount_in(&test.token0.address, &amount_out);
+        test.token1.transfer(&test.user, &test.client.address, &amount_in);
+        test.client.swap(&amount_out, &0, &test.user);
+
+        let amount_in = test.client.get_amount_in(&test.token1.address, &amount_out);
+        test.token0.transfer(&test.user, &test.client.address, &amount_in);
+        test.client.swap(&0, &amount_out, &test.user);
+    }
+}
+
+#[test]
+#[should_panic(expected = "Error(Contract, #10)")]
+fn test_swap_breaks_invariant() {
+    let test = setup_test(100, 4);
+    add_liquidity(&test, 10_000_000, 10_000_000);
+
+    let amount_out = test.client.get_amount_out(&test.token0.address, &1_000_000);
+    test.token0.transfer(&test.user, &test.client.address, &1_000_000);
+    test.client.swap(&0, &(amount_out + 10), &test.user);
+}
+
+#[test]
+#[should_panic(expected = "Error(Contract, #21)")]
+fn test_quote_unknown_token() {
+    let test = setup_test(100, 4);
+    add_liquidity(&test, 10_000_000, 10_000_000);
+    test.client.get_amount_out(&test.user, &1_000);
+}
+
+#[test]
+fn test_ramp_a() {
+    let test = setup_test(100, 4);
+    add_liquidity(&test, 10_000_000, 10_000_000);
+    let start = 1_000 + 86_400;
+    test.env.ledger().set_timestamp(start);
+
+    test.client.ramp_a(&200, &(start + 2 * 86_400));
+    assert_eq!(test.client.get_amp(), 100);
+
+    test.env.ledger().set_timestamp(start + 86_400);
+    assert_eq!(test.client.get_amp(), 150);
+    // Higher A flattens the curve further
+    let amount_out = test.client.get_amount_out(&test.token0.address, &1_000_000);
+
+    test.client.stop_ramp_a();
+    test.env.ledger().set_timestamp(start + 3 * 86_400);
+    assert_eq!(test.client.get_amp(), 150);
+    assert_eq!(test.client.get_amount_out(&test.token0.address, &1_000_000), amount_out);
+}
+
+#[test]
+#[should_panic(expected = "Error(Contract, #19)")]
+fn test_ramp_a_too_soon() {
+    let test = setup_test(100, 4);
+    test.client.ramp_a(&200, &(1_000 + 2 * 86_400));
+}
+
+#[test]
+#[should_panic(expected = "Error(Contract, #18)")]
+fn test_ramp_a_too_large() {
+    let test = setup_test(100, 4);
+    let start = 1_000 + 86_400;
+    test.env.ledger().set_timestamp(start);
+    test.client.ramp_a(&1_001, &(start + 86_400));
+}
@@ -0,0 +1,45 @@
+use raumfi_library::RaumFiLibraryError;
+use soroban_sdk::contracterror;
+
+#[contracterror]
+#[derive(Copy, Clone, Debug, Eq, PartialEq, PartialOrd, Ord)]
+#[repr(u32)]
+pub enum WeightedPoolError {
+    Locked = 1,
+    AlreadyInitialized = 2,
+    Forbidden = 3,
+    InsufficientLiquidityMinted = 4,
+    InsufficientOutputAmount = 6,
+    InsufficientLiquidity = 7,
+    InvalidTo = 8,
+    InsufficientInputAmount = 9,
+    /// The swap pays out more than the weighted math allows for its input
+    Invariant = 10,
+    Overflow = 11,
+    InvalidAmount = 14,
+    InsufficientLiquidityBurned = 15,
+    InvalidFee = 16,
+    NotInitialized = 17,
+    InvalidToken = 21,
+    InsufficientBalance = 22,
+    InsufficientAllowance = 23,
+    NegativeNotAllowed = 24,
+    PastTimeNotAllowed = 25,
+    /// Between 2 and 8 tokens, each weighted at least 1%, with weights summing to 100%
+    InvalidWeights = 26,
+    /// The trade exceeds the share of a balance a single operation may move
+    MaxRatio = 27,
+}
+
+impl From<RaumFiLibraryError> for WeightedPoolError {
+    fn from(error: RaumFiLibraryError) -> Self {
+        match error {
+            RaumFiLibraryError::InvalidWeight => WeightedPoolError::InvalidWeights,
+            RaumFiLibraryError::MaxInRatio | RaumFiLibraryError::MaxOutRatio => WeightedPoolError::MaxRatio,
+            RaumFiLibraryError::InsufficientLiquidity => WeightedPoolError::InsufficientLiquidity,
+            RaumFiLibraryError::InsufficientAmount => WeightedPoolError::InvalidAmount,
+            RaumFiLibraryError::InsufficientTotalSupply => WeightedPoolError::InsufficientLiquidityBurned,
+            _ => WeightedPoolError::Overflow,
+        }
+    }
+}
@@ -0,0 +1,11 @@
+#![no_std]
+
+mod error;
+mod lp_token;
+pub mod weighted_pool;
+
+pub use error::WeightedPoolError;
+pub use weighted_pool::RaumFiWeightedPoolClient;
+
+#[cfg(test)]
+mod test;
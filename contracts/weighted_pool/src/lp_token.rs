@@ -0,0 +1,32 @@
+use soroban_sdk::{Env, String};
+use soroban_token_sdk::metadata::TokenMetadata;
+
+use raumfi_lp_token::{LpTokenConfig, LP_TOKEN_DECIMALS};
+
+use crate::error::WeightedPoolError;
+
+const LP_NAME: &str = "RaumFi Weighted LP";
+const LP_SYMBOL: &str = "RFWLP";
+
+/// Plugs the weighted pool's errors into the shared LP token
+pub struct PoolLp;
+
+impl LpTokenConfig for PoolLp {
+    type Error = WeightedPoolError;
+
+    const INSUFFICIENT_BALANCE: WeightedPoolError = WeightedPoolError::InsufficientBalance;
+    const INSUFFICIENT_ALLOWANCE: WeightedPoolError = WeightedPoolError::InsufficientAllowance;
+    const NEGATIVE_NOT_ALLOWED: WeightedPoolError = WeightedPoolError::NegativeNotAllowed;
+    const PAST_TIME_NOT_ALLOWED: WeightedPoolError = WeightedPoolError::PastTimeNotAllowed;
+}
+
+/// Balances, allowances and supply of the pool's LP token
+pub type LpToken = raumfi_lp_token::LpToken<PoolLp>;
+
+pub fn lp_metadata(e: &Env) -> TokenMetadata {
+    TokenMetadata {
+        decimal: LP_TOKEN_DECIMALS,
+        name: String::from_str(e, LP_NAME),
+        symbol: String::from_str(e, LP_SYMBOL),
+    }
+}
@@ -0,0 +1,160 @@
+#![cfg(test)]
+extern crate std;
+
+use soroban_sdk::{testutils::Address as _, token, vec, Address, Env, Vec};
+
+use crate::weighted_pool::{RaumFiWeightedPool, RaumFiWeightedPoolClient};
+use token::Client as TokenClient;
+use token::StellarAssetClient as TokenAdminClient;
+
+fn create_token_contract<'a>(e: &Env, admin: &Address) -> (TokenClient<'a>, TokenAdminClient<'a>) {
+    let sac = e.register_stellar_asset_contract(admin.clone());
+    (
+        token::Client::new(e, &sac),
+        token::StellarAssetClient::new(e, &sac),
+    )
+}
+
+struct WeightedPoolTest<'a> {
+    env: Env,
+    client: RaumFiWeightedPoolClient<'a>,
+    tokens: std::vec::Vec<TokenClient<'a>>,
+    user: Address,
+}
+
+fn setup_test(weights: &[u32], fee: u32) -> WeightedPoolTest<'static> {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let client = RaumFiWeightedPoolClient::new(&env, &env.register_contract(None, RaumFiWeightedPool {}));
+    let user = Address::generate(&env);
+    let mut tokens = std::vec::Vec::new();
+    for _ in weights {
+        let (token, token_admin) = create_token_contract(&env, &user);
+        token_admin.mint(&user, &1_000_000_000_000);
+        tokens.push(token);
+    }
+    tokens.sort_by(|a, b| a.address.cmp(&b.address));
+
+    let mut addresses = Vec::new(&env);
+    for token in tokens.iter() {
+        addresses.push_back(token.address.clone());
+    }
+    let mut pool_weights = Vec::new(&env);
+    for weight in weights {
+        pool_weights.push_back(*weight);
+    }
+    client.initialize(&addresses, &pool_weights, &Address::generate(&env), &fee);
+    WeightedPoolTest { env, client, tokens, user }
+}
+
+fn join(test: &WeightedPoolTest, amounts: &[i128]) -> i128 {
+    for (token, amount) in test.tokens.iter().zip(amounts) {
+        token.transfer(&test.user, &test.client.address, amount);
+    }
+    test.client.join(&test.user)
+}
+
+#[test]
+fn test_initialize() {
+    let test = setup_test(&[8000, 2000], 30);
+    assert_eq!(test.client.get_weights(), vec![&test.env, 8000, 2000]);
+    assert_eq!(test.client.get_reserves(), vec![&test.env, 0, 0]);
+    assert_eq!(test.client.get_fee(), 30);
+    assert_eq!(test.client.symbol(), soroban_sdk::String::from_str(&test.env, "RFWLP"));
+}
+
+#[test]
+#[should_panic(expected = "Error(Contract, #26)")]
+fn test_initialize_invalid_weights() {
+    setup_test(&[7000, 2000], 30);
+}
+
+#[test]
+#[should_panic(expected = "Error(Contract, #26)")]
+fn test_initialize_too_many_tokens() {
+    setup_test(&[1000, 1000, 1000, 1000, 1000, 1000, 1000, 1000, 2000], 30);
+}
+
+#[test]
+fn test_join_and_exit() {
+    let test = setup_test(&[8000, 2000], 30);
+
+    // The first join mints the weighted invariant, less the locked minimum liquidity
+    let liquidity = join(&test, &[8_000_000, 2_000_000]);
+    assert_eq!(liquidity, 6_062_866 - 1000);
+
+    // A proportional join mints the same share of the supply
+    let more = join(&test, &[800_000, 200_000]);
+    assert_eq!(more, 606_286);
+
+    // Burning everything returns the proportional share of each token
+    test.client.transfer(&test.user, &test.client.address, &(liquidity + more));
+    let amounts = test.client.exit(&test.user);
+    assert_eq!(amounts.len(), 2);
+    assert_eq!(test.client.balance(&test.user), 0);
+    let reserves = test.client.get_reserves();
+    assert!(reserves.get(0).unwrap() > 0 && reserves.get(0).unwrap() < 8_000);
+    assert!(reserves.get(1).unwrap() > 0 && reserves.get(1).unwrap() < 2_000);
+}
+
+#[test]
+fn test_single_asset_join_and_exit() {
+    let test = setup_test(&[5000, 3000, 2000], 30);
+    let liquidity = join(&test, &[5_000_000, 3_000_000, 2_000_000]);
+
+    test.tokens[0].transfer(&test.user, &test.client.address, &500_000);
+    let minted = test.client.join_single(&test.tokens[0].address, &test.user);
+    // A proportional 5% join would mint 5% of the supply; going through a single token costs fees
+    assert!(minted > 0 && minted < (liquidity + 1000) * 5 / 100);
+
+    let balance_before = test.tokens[2].balance(&test.user);
+    test.client.transfer(&test.user, &test.client.address, &minted);
+    let amount_out = test.client.exit_single(&test.tokens[2].address, &test.user);
+    assert_eq!(test.tokens[2].balance(&test.user), balance_before + amount_out);
+    assert_eq!(test.client.get_reserves().get(2).unwrap(), 2_000_000 - amount_out);
+    // All tokens are priced equally; the round trip returns the deposit less fees and the
+    // price impact of moving two balances on their own
+    assert!(amount_out > 420_000 && amount_out < 500_000);
+}
+
+#[test]
+fn test_swap() {
+    let test = setup_test(&[8000, 2000], 30);
+    join(&test, &[8_000_000, 2_000_000]);
+    let (token0, token1) = (&test.tokens[0], &test.tokens[1]);
+
+    // Balances in proportion to the weights price both tokens equally
+    assert_eq!(test.client.get_spot_price(&token0.address, &token1.address), 1_000_000_000_000_000_000);
+
+    let amount_out = test.client.get_amount_out(&token0.address, &token1.address, &100_000);
+    let amount_in = test.client.get_amount_in(&token0.address, &token1.address, &amount_out);
+    assert!(amount_in <= 100_000);
+
+    token0.transfer(&test.user, &test.client.address, &100_000);
+    test.client.swap(&token0.address, &token1.address, &amount_out, &test.user);
+    assert_eq!(
+        test.client.get_reserves(),
+        vec![&test.env, 8_100_000, 2_000_000 - amount_out]
+    );
+}
+
+#[test]
+#[should_panic(expected = "Error(Contract, #10)")]
+fn test_swap_above_quote() {
+    let test = setup_test(&[8000, 2000], 30);
+    join(&test, &[8_000_000, 2_000_000]);
+    let (token0, token1) = (&test.tokens[0], &test.tokens[1]);
+
+    let amount_out = test.client.get_amount_out(&token0.address, &token1.address, &100_000);
+    token0.transfer(&test.user, &test.client.address, &100_000);
+    test.client.swap(&token0.address, &token1.address, &(amount_out + 1), &test.user);
+}
+
+#[test]
+#[should_panic(expected = "Error(Contract, #27)")]
+fn test_swap_max_in_ratio() {
+    let test = setup_test(&[5000, 5000], 30);
+    join(&test, &[1_000_000, 1_000_000]);
+    test.client.get_amount_out(&test.tokens[0].address, &test.tokens[1].address, &300_001);
+}
@@ -0,0 +1,451 @@
+use raumfi_library::{
+    calculate_weighted_amount_in, calculate_weighted_amount_out, calculate_weighted_invariant,
+    calculate_weighted_single_exit, calculate_weighted_single_join, calculate_weighted_spot_price,
+    validate_weights,
+};
+use soroban_sdk::token::Interface as _;
+use soroban_sdk::{contract, contractimpl, contractmeta, contracttype, token, Address, Env, String, Symbol, Vec};
+
+use crate::error::WeightedPoolError;
+use crate::lp_token::{lp_metadata, LpToken};
+
+const MINIMUM_LIQUIDITY: i128 = 1000;
+/// Swap fees are expressed in basis points of the input amount
+const FEE_DENOMINATOR: i128 = 10_000;
+
+contractmeta!(
+    key = "Description",
+    val = "RaumFi weighted pool of 2 to 8 tokens"
+);
+
+#[contracttype]
+#[derive(Clone)]
+pub enum DataKey {
+    Factory,
+    /// Pool tokens, sorted by address
+    Tokens,
+    /// Weight of each token in basis points, in the order of `Tokens`
+    Weights,
+    Reserves,
+    Fee,
+    Unlocked,
+}
+
+pub trait RaumFiWeightedPoolTrait {
+    fn initialize(env: Env, tokens: Vec<Address>, weights: Vec<u32>, factory: Address, fee: u32) -> Result<(), WeightedPoolError>;
+    fn get_tokens(env: Env) -> Vec<Address>;
+    fn get_weights(env: Env) -> Vec<u32>;
+    fn get_reserves(env: Env) -> Vec<i128>;
+    fn get_fee(env: Env) -> u32;
+    fn get_spot_price(env: Env, token_in: Address, token_out: Address) -> Result<i128, WeightedPoolError>;
+    fn get_amount_out(env: Env, token_in: Address, token_out: Address, amount_in: i128) -> Result<i128, WeightedPoolError>;
+    fn get_amount_in(env: Env, token_in: Address, token_out: Address, amount_out: i128) -> Result<i128, WeightedPoolError>;
+    fn join(env: Env, to: Address) -> Result<i128, WeightedPoolError>;
+    fn join_single(env: Env, token_in: Address, to: Address) -> Result<i128, WeightedPoolError>;
+    fn exit(env: Env, to: Address) -> Result<Vec<i128>, WeightedPoolError>;
+    fn exit_single(env: Env, token_out: Address, to: Address) -> Result<i128, WeightedPoolError>;
+    fn swap(env: Env, token_in: Address, token_out: Address, amount_out: i128, to: Address) -> Result<(), WeightedPoolError>;
+}
+
+#[contract]
+pub struct RaumFiWeightedPool;
+
+#[contractimpl]
+impl RaumFiWeightedPoolTrait for RaumFiWeightedPool {
+    /// Sets up a pool of `tokens`, sorted by address without duplicates, weighted by
+    /// `weights` in basis points
+    fn initialize(env: Env, tokens: Vec<Address>, weights: Vec<u32>, factory: Address, fee: u32) -> Result<(), WeightedPoolError> {
+        if env.storage().instance().has(&DataKey::Factory) {
+            return Err(WeightedPoolError::AlreadyInitialized);
+        }
+        if fee as i128 >= FEE_DENOMINATOR {
+            return Err(WeightedPoolError::InvalidFee);
+        }
+        if tokens.len() != weights.len() {
+            return Err(WeightedPoolError::InvalidWeights);
+        }
+        validate_weights(&weights)?;
+        for i in 1..tokens.len() {
+            if tokens.get(i - 1).unwrap() >= tokens.get(i).unwrap() {
+                return Err(WeightedPoolError::InvalidToken);
+            }
+        }
+
+        let mut reserves = Vec::new(&env);
+        for _ in tokens.iter() {
+            reserves.push_back(0i128);
+        }
+        let storage = env.storage().instance();
+        storage.set(&DataKey::Factory, &factory);
+        storage.set(&DataKey::Tokens, &tokens);
+        storage.set(&DataKey::Weights, &weights);
+        storage.set(&DataKey::Reserves, &reserves);
+        storage.set(&DataKey::Fee, &fee);
+        LpToken::write_metadata(&env, lp_metadata(&env));
+        Ok(())
+    }
+
+    fn get_tokens(env: Env) -> Vec<Address> {
+        env.storage().instance().get(&DataKey::Tokens).unwrap_or(Vec::new(&env))
+    }
+
+    fn get_weights(env: Env) -> Vec<u32> {
+        env.storage().instance().get(&DataKey::Weights).unwrap_or(Vec::new(&env))
+    }
+
+    fn get_reserves(env: Env) -> Vec<i128> {
+        env.storage().instance().get(&DataKey::Reserves).unwrap_or(Vec::new(&env))
+    }
+
+    /// Returns the swap fee of the pool in basis points
+    fn get_fee(env: Env) -> u32 {
+        env.storage().instance().get(&DataKey::Fee).unwrap_or(0)
+    }
+
+    /// Returns the price of `token_in` in units of `token_out`, scaled by 10^18
+    fn get_spot_price(env: Env, token_in: Address, token_out: Address) -> Result<i128, WeightedPoolError> {
+        let (index_in, index_out) = swap_indices(&env, &token_in, &token_out)?;
+        let (reserves, weights) = (Self::get_reserves(env.clone()), Self::get_weights(env.clone()));
+        Ok(calculate_weighted_spot_price(
+            reserves.get(index_in).unwrap(),
+            weights.get(index_in).unwrap(),
+            reserves.get(index_out).unwrap(),
+            weights.get(index_out).unwrap(),
+        )?)
+    }
+
+    /// Quotes the output of swapping `amount_in` of `token_in` for `token_out`
+    fn get_amount_out(env: Env, token_in: Address, token_out: Address, amount_in: i128) -> Result<i128, WeightedPoolError> {
+        let (index_in, index_out) = swap_indices(&env, &token_in, &token_out)?;
+        let (reserves, weights) = (Self::get_reserves(env.clone()), Self::get_weights(env.clone()));
+        Ok(calculate_weighted_amount_out(
+            amount_in,
+            reserves.get(index_in).unwrap(),
+            weights.get(index_in).unwrap(),
+            reserves.get(index_out).unwrap(),
+            weights.get(index_out).unwrap(),
+            Self::get_fee(env.clone()),
+        )?)
+    }
+
+    /// Quotes how much of `token_in` must be sent in to receive `amount_out` of `token_out`
+    fn get_amount_in(env: Env, token_in: Address, token_out: Address, amount_out: i128) -> Result<i128, WeightedPoolError> {
+        let (index_in, index_out) = swap_indices(&env, &token_in, &token_out)?;
+        let (reserves, weights) = (Self::get_reserves(env.clone()), Self::get_weights(env.clone()));
+        Ok(calculate_weighted_amount_in(
+            amount_out,
+            reserves.get(index_in).unwrap(),
+            weights.get(index_in).unwrap(),
+            reserves.get(index_out).unwrap(),
+            weights.get(index_out).unwrap(),
+            Self::get_fee(env.clone()),
+        )?)
+    }
+
+    /// Mints LP tokens for a proportional deposit of the tokens sent to the pool since the
+    /// last update. Liquidity follows the smallest deposit relative to its reserve; the
+    /// excess of the other tokens is left to the pool.
+    fn join(env: Env, to: Address) -> Result<i128, WeightedPoolError> {
+        check_locked(&env)?;
+        let _guard = Guard::new(&env);
+
+        let reserves = Self::get_reserves(env.clone());
+        let balances = get_balances(&env);
+        let total_supply = LpToken::total_supply(&env);
+
+        let liquidity = if total_supply == 0 {
+            let invariant = calculate_weighted_invariant(balances.clone(), Self::get_weights(env.clone()))?;
+            LpToken::mint(&env, &env.current_contract_address(), MINIMUM_LIQUIDITY);
+            invariant - MINIMUM_LIQUIDITY
+        } else {
+            let mut liquidity = i128::MAX;
+            for i in 0..reserves.len() {
+                let amount = balances.get(i).unwrap() - reserves.get(i).unwrap();
+                let share = amount.checked_mul(total_supply).ok_or(WeightedPoolError::Overflow)? / reserves.get(i).unwrap();
+                liquidity = liquidity.min(share);
+            }
+            liquidity
+        };
+        if liquidity <= 0 {
+            return Err(WeightedPoolError::InsufficientLiquidityMinted);
+        }
+        LpToken::mint(&env, &to, liquidity);
+
+        let amounts = deltas(&env, &balances, &reserves);
+        update(&env, &balances);
+        env.events().publish(
+            (Symbol::new(&env, "RaumFiWeightedPool"), Symbol::new(&env, "Join")),
+            (to, amounts, liquidity)
+        );
+        Ok(liquidity)
+    }
+
+    /// Mints LP tokens for the amount of `token_in` sent to the pool since the last update.
+    /// The part of the deposit beyond a proportional join pays the swap fee.
+    fn join_single(env: Env, token_in: Address, to: Address) -> Result<i128, WeightedPoolError> {
+        check_locked(&env)?;
+        let _guard = Guard::new(&env);
+
+        let total_supply = LpToken::total_supply(&env);
+        if total_supply == 0 {
+            return Err(WeightedPoolError::InsufficientLiquidity);
+        }
+        let index = token_index(&env, &token_in)?;
+        let reserves = Self::get_reserves(env.clone());
+        let balances = get_balances(&env);
+        let reserve = reserves.get(index).unwrap();
+        let amount_in = balances.get(index).unwrap() - reserve;
+        if amount_in <= 0 {
+            return Err(WeightedPoolError::InsufficientInputAmount);
+        }
+
+        let weight = Self::get_weights(env.clone()).get(index).unwrap();
+        let liquidity = calculate_weighted_single_join(amount_in, reserve, weight, total_supply, Self::get_fee(env.clone()))?;
+        if liquidity <= 0 {
+            return Err(WeightedPoolError::InsufficientLiquidityMinted);
+        }
+        LpToken::mint(&env, &to, liquidity);
+
+        let amounts = deltas(&env, &balances, &reserves);
+        update(&env, &balances);
+        env.events().publish(
+            (Symbol::new(&env, "RaumFiWeightedPool"), Symbol::new(&env, "Join")),
+            (to, amounts, liquidity)
+        );
+        Ok(liquidity)
+    }
+
+    /// Burns the LP tokens sent to the pool on top of the locked minimum liquidity and
+    /// sends the proportional share of every token to `to`
+    fn exit(env: Env, to: Address) -> Result<Vec<i128>, WeightedPoolError> {
+        check_locked(&env)?;
+        let _guard = Guard::new(&env);
+
+        let liquidity = burnable_liquidity(&env)?;
+        let total_supply = LpToken::total_supply(&env);
+        let tokens = Self::get_tokens(env.clone());
+        let mut balances = get_balances(&env);
+
+        let mut amounts = Vec::new(&env);
+        for i in 0..tokens.len() {
+            let balance = balances.get(i).unwrap();
+            let amount = balance.checked_mul(liquidity).ok_or(WeightedPoolError::Overflow)? / total_supply;
+            amounts.push_back(amount);
+            balances.set(i, balance - amount);
+        }
+        if amounts.iter().all(|amount| amount <= 0) {
+            return Err(WeightedPoolError::InsufficientLiquidityBurned);
+        }
+
+        LpToken::burn(&env, &env.current_contract_address(), liquidity);
+        for (token, amount) in tokens.iter().zip(amounts.iter()) {
+            if amount > 0 {
+                token::Client::new(&env, &token).transfer(&env.current_contract_address(), &to, &amount);
+            }
+        }
+
+        update(&env, &balances);
+        env.events().publish(
+            (Symbol::new(&env, "RaumFiWeightedPool"), Symbol::new(&env, "Exit")),
+            (to, amounts.clone(), liquidity)
+        );
+        Ok(amounts)
+    }
+
+    /// Burns the LP tokens sent to the pool on top of the locked minimum liquidity and
+    /// pays them out in `token_out` alone. The part of the exit beyond a proportional exit
+    /// pays the swap fee.
+    fn exit_single(env: Env, token_out: Address, to: Address) -> Result<i128, WeightedPoolError> {
+        check_locked(&env)?;
+        let _guard = Guard::new(&env);
+
+        let index = token_index(&env, &token_out)?;
+        let liquidity = burnable_liquidity(&env)?;
+        let total_supply = LpToken::total_supply(&env);
+        let mut balances = get_balances(&env);
+        let balance = balances.get(index).unwrap();
+
+        let weight = Self::get_weights(env.clone()).get(index).unwrap();
+        let amount_out = calculate_weighted_single_exit(liquidity, balance, weight, total_supply, Self::get_fee(env.clone()))?;
+        if amount_out <= 0 {
+            return Err(WeightedPoolError::InsufficientLiquidityBurned);
+        }
+
+        LpToken::burn(&env, &env.current_contract_address(), liquidity);
+        token::Client::new(&env, &token_out).transfer(&env.current_contract_address(), &to, &amount_out);
+
+        balances.set(index, balance - amount_out);
+        let mut amounts = Vec::new(&env);
+        for i in 0..balances.len() {
+            amounts.push_back(if i == index { amount_out } else { 0 });
+        }
+        update(&env, &balances);
+        env.events().publish(
+            (Symbol::new(&env, "RaumFiWeightedPool"), Symbol::new(&env, "Exit")),
+            (to, amounts, liquidity)
+        );
+        Ok(amount_out)
+    }
+
+    /// Sends `amount_out` of `token_out` for the `token_in` already transferred to the pool,
+    /// as long as the weighted math quotes at least that much for it
+    fn swap(env: Env, token_in: Address, token_out: Address, amount_out: i128, to: Address) -> Result<(), WeightedPoolError> {
+        check_locked(&env)?;
+        let _guard = Guard::new(&env);
+
+        if amount_out <= 0 {
+            return Err(WeightedPoolError::InsufficientOutputAmount);
+        }
+        let (index_in, index_out) = swap_indices(&env, &token_in, &token_out)?;
+        if to == token_in || to == token_out {
+            return Err(WeightedPoolError::InvalidTo);
+        }
+        let reserves = Self::get_reserves(env.clone());
+        let mut balances = get_balances(&env);
+        let amount_in = balances.get(index_in).unwrap() - reserves.get(index_in).unwrap();
+        if amount_in <= 0 {
+            return Err(WeightedPoolError::InsufficientInputAmount);
+        }
+
+        let max_amount_out = Self::get_amount_out(env.clone(), token_in.clone(), token_out.clone(), amount_in)?;
+        if amount_out > max_amount_out {
+            return Err(WeightedPoolError::Invariant);
+        }
+        token::Client::new(&env, &token_out).transfer(&env.current_contract_address(), &to, &amount_out);
+
+        balances.set(index_out, balances.get(index_out).unwrap() - amount_out);
+        update(&env, &balances);
+        env.events().publish(
+            (Symbol::new(&env, "RaumFiWeightedPool"), Symbol::new(&env, "Swap")),
+            (token_in, token_out, amount_in, amount_out, to)
+        );
+        Ok(())
+    }
+}
+
+/// The pool is itself the SEP-41 token of its liquidity shares
+#[contractimpl]
+impl token::Interface for RaumFiWeightedPool {
+    fn allowance(e: Env, from: Address, spender: Address) -> i128 {
+        LpToken::allowance(&e, &from, &spender).amount
+    }
+
+    fn approve(e: Env, from: Address, spender: Address, amount: i128, expiration_ledger: u32) {
+        from.require_auth();
+        LpToken::approve(&e, &from, &spender, amount, expiration_ledger);
+    }
+
+    fn balance(e: Env, id: Address) -> i128 {
+        LpToken::balance(&e, &id)
+    }
+
+    fn transfer(e: Env, from: Address, to: Address, amount: i128) {
+        from.require_auth();
+        LpToken::check_nonnegative_amount(&e, amount);
+        LpToken::transfer(&e, &from, &to, amount);
+    }
+
+    fn transfer_from(e: Env, spender: Address, from: Address, to: Address, amount: i128) {
+        spender.require_auth();
+        LpToken::check_nonnegative_amount(&e, amount);
+        LpToken::spend_allowance(&e, &from, &spender, amount);
+        LpToken::transfer(&e, &from, &to, amount);
+    }
+
+    fn burn(e: Env, from: Address, amount: i128) {
+        from.require_auth();
+        LpToken::check_nonnegative_amount(&e, amount);
+        LpToken::burn(&e, &from, amount);
+    }
+
+    fn burn_from(e: Env, spender: Address, from: Address, amount: i128) {
+        spender.require_auth();
+        LpToken::check_nonnegative_amount(&e, amount);
+        LpToken::spend_allowance(&e, &from, &spender, amount);
+        LpToken::burn(&e, &from, amount);
+    }
+
+    fn decimals(e: Env) -> u32 {
+        LpToken::read_metadata(&e).decimal
+    }
+
+    fn name(e: Env) -> String {
+        LpToken::read_metadata(&e).name
+    }
+
+    fn symbol(e: Env) -> String {
+        LpToken::read_metadata(&e).symbol
+    }
+}
+
+fn token_index(env: &Env, token: &Address) -> Result<u32, WeightedPoolError> {
+    let tokens: Vec<Address> = env.storage().instance().get(&DataKey::Tokens).ok_or(WeightedPoolError::NotInitialized)?;
+    tokens.first_index_of(token).ok_or(WeightedPoolError::InvalidToken)
+}
+
+fn swap_indices(env: &Env, token_in: &Address, token_out: &Address) -> Result<(u32, u32), WeightedPoolError> {
+    if token_in == token_out {
+        return Err(WeightedPoolError::InvalidToken);
+    }
+    Ok((token_index(env, token_in)?, token_index(env, token_out)?))
+}
+
+/// LP tokens sent to the pool for burning, on top of the locked minimum liquidity
+fn burnable_liquidity(env: &Env) -> Result<i128, WeightedPoolError> {
+    let liquidity = LpToken::balance(env, &env.current_contract_address())
+        .checked_sub(MINIMUM_LIQUIDITY)
+        .ok_or(WeightedPoolError::InsufficientLiquidityBurned)?;
+    if liquidity <= 0 {
+        return Err(WeightedPoolError::InsufficientLiquidityBurned);
+    }
+    Ok(liquidity)
+}
+
+fn get_balances(env: &Env) -> Vec<i128> {
+    let mut balances = Vec::new(env);
+    for token in RaumFiWeightedPool::get_tokens(env.clone()).iter() {
+        balances.push_back(token::Client::new(env, &token).balance(&env.current_contract_address()));
+    }
+    balances
+}
+
+fn deltas(env: &Env, balances: &Vec<i128>, reserves: &Vec<i128>) -> Vec<i128> {
+    let mut amounts = Vec::new(env);
+    for (balance, reserve) in balances.iter().zip(reserves.iter()) {
+        amounts.push_back(balance - reserve);
+    }
+    amounts
+}
+
+fn update(env: &Env, balances: &Vec<i128>) {
+    env.storage().instance().set(&DataKey::Reserves, balances);
+    env.events().publish(
+        (Symbol::new(env, "RaumFiWeightedPool"), Symbol::new(env, "Sync")),
+        balances.clone()
+    );
+}
+
+fn check_locked(env: &Env) -> Result<(), WeightedPoolError> {
+    let unlocked: bool = env.storage().instance().get(&DataKey::Unlocked).unwrap_or(true);
+    if !unlocked {
+        return Err(WeightedPoolError::Locked);
+    }
+    Ok(())
+}
+
+struct Guard<'a> {
+    env: &'a Env,
+}
+
+impl<'a> Guard<'a> {
+    fn new(env: &'a Env) -> Self {
+        env.storage().instance().set(&DataKey::Unlocked, &false);
+        Guard { env }
+    }
+}
+
+impl<'a> Drop for Guard<'a> {
+    fn drop(&mut self) {
+        self.env.storage().instance().set(&DataKey::Unlocked, &true);
+    }
+}